@@ -0,0 +1,374 @@
+use anyhow::{Result, bail};
+
+/// Magic of the textures of the 3.3.5 client.
+pub const BLP2_MAGIC: &[u8; 4] = b"BLP2";
+
+/// Magic, type, encoding bytes, size, 16 mip offsets, 16 mip sizes and a
+/// 256 color palette.
+const HEADER_SIZE: usize = 20 + 16 * 4 * 2 + 256 * 4;
+
+/// The first mip level of a `.blp` texture as 8-bit RGBA.
+#[derive(Debug, Clone)]
+pub struct BlpImage {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height` RGBA pixels, row by row.
+    pub pixels: Vec<u8>,
+}
+
+/// How the pixels of a BLP2 file are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// Indices into the header palette, then alpha of `alpha_depth` bits.
+    Palette {
+        alpha_depth: u8,
+    },
+    Dxt1,
+    Dxt3,
+    Dxt5,
+    /// Uncompressed BGRA.
+    Bgra,
+}
+
+impl BlpImage {
+    /// Decode the largest mip level of a BLP2 file.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE || &data[..4] != BLP2_MAGIC {
+            bail!("not a BLP2 file");
+        }
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let (compression, alpha_depth, alpha_type) = (data[8], data[9], data[10]);
+        let width = u32_at(12);
+        let height = u32_at(16);
+        let offset = u32_at(20) as usize;
+        let size = u32_at(20 + 16 * 4) as usize;
+
+        let encoding = match (compression, alpha_depth, alpha_type) {
+            (1, 0 | 1 | 4 | 8, _) => Encoding::Palette { alpha_depth },
+            (2, 0 | 1, _) => Encoding::Dxt1,
+            (2, 8, 7) => Encoding::Dxt5,
+            // Alpha type 7 marks DXT5, the client reads any other as DXT3
+            (2, 4 | 8, _) => Encoding::Dxt3,
+            (3, _, _) => Encoding::Bgra,
+            _ => bail!(
+                "unsupported encoding: compression {compression}, alpha depth {alpha_depth}, \
+                 alpha type {alpha_type}"
+            ),
+        };
+        if width == 0 || height == 0 {
+            bail!("empty {width}x{height} texture");
+        }
+        let Some(mip) = data.get(offset..offset + size) else {
+            bail!(
+                "mip level of {size} bytes at {offset} past the end of the {} byte file",
+                data.len()
+            );
+        };
+
+        let (w, h) = (width as usize, height as usize);
+        let pixels = match encoding {
+            Encoding::Palette { alpha_depth } => decode_palette(
+                mip,
+                &data[HEADER_SIZE - 1024..HEADER_SIZE],
+                alpha_depth,
+                w,
+                h,
+            )?,
+            Encoding::Bgra => {
+                if mip.len() < w * h * 4 {
+                    bail!("{size} bytes are too few for {width}x{height} BGRA pixels");
+                }
+                mip[..w * h * 4]
+                    .chunks_exact(4)
+                    .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                    .collect()
+            }
+            dxt => decode_dxt(mip, dxt, w, h)?,
+        };
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+fn decode_palette(
+    mip: &[u8],
+    palette: &[u8],
+    alpha_depth: u8,
+    w: usize,
+    h: usize,
+) -> Result<Vec<u8>> {
+    let count = w * h;
+    let alpha_size = (count * alpha_depth as usize).div_ceil(8);
+    if mip.len() < count + alpha_size {
+        bail!(
+            "{} bytes are too few for {count} palette indices with {alpha_depth}-bit alpha",
+            mip.len()
+        );
+    }
+    let (indices, alpha) = mip.split_at(count);
+    let mut pixels = Vec::with_capacity(count * 4);
+    for (pixel, &index) in indices.iter().enumerate() {
+        let bgra = &palette[index as usize * 4..index as usize * 4 + 4];
+        let a = match alpha_depth {
+            0 => 0xff,
+            1 => ((alpha[pixel / 8] >> (pixel % 8)) & 1) * 0xff,
+            4 => ((alpha[pixel / 2] >> ((pixel % 2) * 4)) & 0xf) * 0x11,
+            _ => alpha[pixel],
+        };
+        pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], a]);
+    }
+    Ok(pixels)
+}
+
+fn decode_dxt(mip: &[u8], encoding: Encoding, w: usize, h: usize) -> Result<Vec<u8>> {
+    let block_size = if encoding == Encoding::Dxt1 { 8 } else { 16 };
+    let (blocks_x, blocks_y) = (w.div_ceil(4), h.div_ceil(4));
+    if mip.len() < blocks_x * blocks_y * block_size {
+        bail!(
+            "{} bytes are too few for {} {encoding:?} blocks",
+            mip.len(),
+            blocks_x * blocks_y
+        );
+    }
+
+    let mut pixels = vec![0; w * h * 4];
+    for (index, block) in mip
+        .chunks_exact(block_size)
+        .take(blocks_x * blocks_y)
+        .enumerate()
+    {
+        let (alpha, color) = block.split_at(block_size - 8);
+        let mut texels = color_block(color, encoding == Encoding::Dxt1);
+        match encoding {
+            Encoding::Dxt3 => {
+                for (texel, rgba) in texels.iter_mut().enumerate() {
+                    rgba[3] = ((alpha[texel / 2] >> ((texel % 2) * 4)) & 0xf) * 0x11;
+                }
+            }
+            Encoding::Dxt5 => {
+                for (texel, a) in alpha_block(alpha).into_iter().enumerate() {
+                    texels[texel][3] = a;
+                }
+            }
+            _ => {}
+        }
+
+        // Skip texels past the edge of sizes not divisible by 4
+        let (bx, by) = (index % blocks_x * 4, index / blocks_x * 4);
+        for (texel, rgba) in texels.iter().enumerate() {
+            let (x, y) = (bx + texel % 4, by + texel / 4);
+            if x < w && y < h {
+                let start = (y * w + x) * 4;
+                pixels[start..start + 4].copy_from_slice(rgba);
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+/// The 16 texels of a DXT color block. DXT1 blocks whose first color isn't
+/// the larger one have a transparent fourth color.
+fn color_block(block: &[u8], dxt1: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (rgb565(c0), rgb565(c1));
+    let mix = |wa: u16, wb: u16, total: u16| -> [u8; 4] {
+        let channel = |i: usize| ((a[i] as u16 * wa + b[i] as u16 * wb) / total) as u8;
+        [channel(0), channel(1), channel(2), 0xff]
+    };
+    let colors = if c0 > c1 || !dxt1 {
+        [a, b, mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [a, b, mix(1, 1, 2), [0; 4]]
+    };
+
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    std::array::from_fn(|texel| colors[(indices >> (texel * 2)) as usize & 0b11])
+}
+
+/// The 16 alpha values of a DXT5 block, interpolated between two endpoints.
+fn alpha_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u16, block[1] as u16);
+    let alphas: [u8; 8] = std::array::from_fn(|index| match index {
+        0 => a0 as u8,
+        1 => a1 as u8,
+        _ if a0 > a1 => ((a0 * (8 - index as u16) + a1 * (index as u16 - 1)) / 7) as u8,
+        6 => 0,
+        7 => 0xff,
+        _ => ((a0 * (6 - index as u16) + a1 * (index as u16 - 1)) / 5) as u8,
+    });
+
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|texel| alphas[(indices >> (texel * 3)) as usize & 0b111])
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let r = (color >> 11) & 0x1f;
+    let g = (color >> 5) & 0x3f;
+    let b = color & 0x1f;
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+        0xff,
+    ]
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// BLP2 file of one mip level, `palette` at the start of the palette.
+    pub(crate) fn blp(
+        encoding: (u8, u8, u8),
+        size: (u32, u32),
+        palette: &[[u8; 4]],
+        mip: &[u8],
+    ) -> Vec<u8> {
+        let (compression, alpha_depth, alpha_type) = encoding;
+        let mut data = BLP2_MAGIC.to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[compression, alpha_depth, alpha_type, 0]);
+        data.extend_from_slice(&size.0.to_le_bytes());
+        data.extend_from_slice(&size.1.to_le_bytes());
+        for value in [HEADER_SIZE, mip.len()] {
+            data.extend_from_slice(&(value as u32).to_le_bytes());
+            data.extend_from_slice(&[0; 15 * 4]);
+        }
+        let mut colors = [0; 256 * 4];
+        colors[..palette.len() * 4].copy_from_slice(palette.as_flattened());
+        data.extend_from_slice(&colors);
+        data.extend_from_slice(mip);
+        data
+    }
+
+    fn pixels(image: &BlpImage) -> Vec<[u8; 4]> {
+        image
+            .pixels
+            .chunks_exact(4)
+            .map(|rgba| rgba.try_into().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn decodes_palette_alpha_depths() {
+        // BGRA palette entries
+        let palette = [[0x30, 0x20, 0x10, 0], [0, 0, 0xff, 0]];
+        let indices = [0, 1, 0, 1];
+        for (alpha_depth, alpha, expected) in [
+            (0, vec![], [0xff; 4]),
+            (1, vec![0b0101], [0xff, 0, 0xff, 0]),
+            (4, vec![0xf0, 0x3a], [0, 0xff, 0xaa, 0x33]),
+            (8, vec![1, 2, 3, 4], [1, 2, 3, 4]),
+        ] {
+            let mip = [&indices[..], &alpha].concat();
+            let image =
+                BlpImage::decode(&blp((1, alpha_depth, 0), (2, 2), &palette, &mip)).unwrap();
+            assert_eq!((image.width, image.height), (2, 2));
+            let colors = [[0x10, 0x20, 0x30], [0xff, 0, 0]];
+            let expected: Vec<[u8; 4]> = (0..4)
+                .map(|pixel| {
+                    let [r, g, b] = colors[pixel % 2];
+                    [r, g, b, expected[pixel]]
+                })
+                .collect();
+            assert_eq!(pixels(&image), expected, "{alpha_depth}-bit alpha");
+        }
+
+        let err = BlpImage::decode(&blp((1, 8, 0), (2, 2), &palette, &indices)).unwrap_err();
+        assert!(err.to_string().contains("too few"), "{err}");
+    }
+
+    #[test]
+    fn decodes_bgra() {
+        let image =
+            BlpImage::decode(&blp((3, 8, 0), (1, 2), &[], &[1, 2, 3, 4, 5, 6, 7, 8])).unwrap();
+        assert_eq!(pixels(&image), [[3, 2, 1, 4], [7, 6, 5, 8]]);
+    }
+
+    /// DXT color block of red and blue with texels 0..4 using colors 0..4.
+    fn color_block(red_first: bool) -> [u8; 8] {
+        let (c0, c1): (u16, u16) = if red_first {
+            (0xf800, 0x001f)
+        } else {
+            (0x001f, 0xf800)
+        };
+        let [a, b] = c0.to_le_bytes();
+        let [c, d] = c1.to_le_bytes();
+        [a, b, c, d, 0b1110_0100, 0, 0, 0]
+    }
+
+    #[test]
+    fn decodes_dxt1() {
+        let image = BlpImage::decode(&blp((2, 0, 0), (4, 4), &[], &color_block(true))).unwrap();
+        let texels = pixels(&image);
+        assert_eq!(texels.len(), 16);
+        assert_eq!(
+            texels[..4],
+            [
+                [0xff, 0, 0, 0xff],
+                [0, 0, 0xff, 0xff],
+                [170, 0, 85, 0xff],
+                [85, 0, 170, 0xff],
+            ]
+        );
+        assert_eq!(texels[15], [0xff, 0, 0, 0xff]);
+
+        // With the smaller color first the fourth is transparent
+        let image = BlpImage::decode(&blp((2, 1, 0), (4, 4), &[], &color_block(false))).unwrap();
+        let texels = pixels(&image);
+        assert_eq!(texels[2], [127, 0, 127, 0xff]);
+        assert_eq!(texels[3], [0; 4]);
+
+        // Texels past the edge of a 2x2 texture are dropped
+        let image = BlpImage::decode(&blp((2, 0, 0), (2, 2), &[], &color_block(true))).unwrap();
+        assert_eq!(
+            pixels(&image)[..2],
+            [[0xff, 0, 0, 0xff], [0, 0, 0xff, 0xff]]
+        );
+        assert_eq!(image.pixels.len(), 2 * 2 * 4);
+    }
+
+    #[test]
+    fn decodes_dxt3() {
+        let mut alpha = [0xff; 8];
+        alpha[0] = 0x1f;
+        let block = [&alpha[..], &color_block(true)].concat();
+        for encoding in [(2, 4, 1), (2, 8, 1), (2, 8, 0)] {
+            let image = BlpImage::decode(&blp(encoding, (4, 4), &[], &block)).unwrap();
+            let texels = pixels(&image);
+            assert_eq!(texels[0], [0xff, 0, 0, 0xff], "{encoding:?}");
+            assert_eq!(texels[1], [0, 0, 0xff, 0x11], "{encoding:?}");
+            // DXT3 colors never have a transparent fourth color
+            assert_eq!(texels[3], [85, 0, 170, 0xff], "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn decodes_dxt5() {
+        // Texel 1 takes the second endpoint, texel 2 the first interpolated
+        let alpha = [0xff, 0, 0x88, 0, 0, 0, 0, 0];
+        let block = [&alpha[..], &color_block(true)].concat();
+        let image = BlpImage::decode(&blp((2, 8, 7), (4, 4), &[], &block)).unwrap();
+        let alphas: Vec<u8> = pixels(&image).iter().map(|rgba| rgba[3]).collect();
+        assert_eq!(alphas[..4], [0xff, 0, 218, 0xff]);
+
+        let err = BlpImage::decode(&blp((2, 8, 7), (8, 8), &[], &block)).unwrap_err();
+        assert!(err.to_string().contains("too few"), "{err}");
+    }
+
+    #[test]
+    fn rejects_unsupported_files() {
+        assert!(BlpImage::decode(b"BLP1").is_err());
+        let err = BlpImage::decode(&blp((2, 2, 0), (4, 4), &[], &[0; 16])).unwrap_err();
+        assert!(err.to_string().contains("unsupported encoding"), "{err}");
+        let err = BlpImage::decode(&blp((3, 8, 0), (0, 4), &[], &[])).unwrap_err();
+        assert!(err.to_string().contains("empty"), "{err}");
+    }
+}
@@ -0,0 +1,423 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    filter::ItemFilter,
+    layout::Layouts,
+    merge::SupplementalSources,
+    mpq::{self, DEFAULT_LOCALE},
+    random::EnchantmentPools,
+};
+
+/// Icon URL used when a project doesn't configure one.
+const DEFAULT_ICON_TEMPLATE: &str =
+    "https://wotlk.evowow.com/static/images/wow/icons/large/{icon}.jpg";
+
+/// Everything needed to build the exports of a project, usually read from an
+/// `epoch.toml`. Relative paths are resolved against the directory of the
+/// config file.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// WoW `Data` directories, each scanned together with its directory of
+    /// the client locale. Later directories take priority.
+    pub data_dirs: Vec<PathBuf>,
+    /// Client locale of the item text, such as `deDE`. `enUS` if the data
+    /// directories have it, else the first locale they have.
+    pub locale: Option<String>,
+    /// Locales whose text items also carry, every one the data directories
+    /// have if unset.
+    pub locales: Option<Vec<String>>,
+    /// Supplemental item sources and the merge policy.
+    pub merge: SupplementalSources,
+    pub reference: ReferenceConfig,
+    pub icons: IconConfig,
+    pub layouts: LayoutConfig,
+    /// Filter applied to every output.
+    pub filter: ItemFilter,
+    pub outputs: Vec<OutputConfig>,
+    /// Record every build in a history store.
+    pub history: Option<HistoryConfig>,
+    /// Write the outcome of every table and source to this JSON file.
+    pub diagnostics: Option<PathBuf>,
+    /// Fail the build, before writing any output, if a table is missing,
+    /// empty or failed to read, or a source failed to load or yielded no
+    /// items.
+    pub strict: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// SQLite file, created if missing.
+    pub path: PathBuf,
+    /// Client build number the data dirs contain, such as `3466`.
+    pub build: u32,
+}
+
+/// Table layouts of a client that modified its DBCs.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Directory of `.dbd` files, such as `Item.dbd`, replacing the wrath
+    /// layout of their table.
+    pub dir: Option<PathBuf>,
+    /// Build whose layout is used from each file, the last one listed if
+    /// unset.
+    pub build: Option<u32>,
+}
+
+impl LayoutConfig {
+    pub fn load(&self) -> Result<Layouts> {
+        match &self.dir {
+            Some(dir) => Layouts::load(dir, self.build),
+            None => Ok(Layouts::default()),
+        }
+    }
+}
+
+/// Item lists the build is compared against.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReferenceConfig {
+    /// Stock 3.3.5 `item_template` CSV, needed to filter by item status.
+    pub stock_items: Option<PathBuf>,
+    /// Server `item_enchantment_template` CSV mapping random property and
+    /// suffix pools to their entries, needed to list item variants.
+    pub enchantment_pools: Option<PathBuf>,
+}
+
+impl ReferenceConfig {
+    pub fn load_enchantment_pools(&self) -> Result<EnchantmentPools> {
+        match &self.enchantment_pools {
+            Some(path) => EnchantmentPools::load(path),
+            None => Ok(EnchantmentPools::default()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconConfig {
+    /// URL or path of item icons. `{icon}` is replaced with the lowercase
+    /// icon name from ItemDisplayInfo, such as `inv_sword_04`.
+    pub template: String,
+    /// Decode the icons out of the archives and link to the exported files
+    /// instead of `template`.
+    pub export: Option<IconExport>,
+}
+
+/// Icons decoded from `Interface\Icons\*.blp` and written as images.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconExport {
+    /// Directory the `<size>/<icon>.<ext>` files are written to.
+    pub dir: PathBuf,
+    /// Path of `dir` as the site sees it, put in front of the icon paths of
+    /// items.
+    pub url: String,
+    pub format: IconFormat,
+    /// Sizes in pixels to write every icon at. Items link to the first.
+    pub sizes: Vec<u32>,
+    /// Also pack the icons into sprite atlases.
+    pub atlas: Option<AtlasConfig>,
+}
+
+/// Sprite sheets of every exported icon at one size, with a manifest of
+/// where each icon is.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AtlasConfig {
+    /// Icon size in pixels to pack, the first of `sizes` if unset.
+    pub size: Option<u32>,
+    /// Width and height limit of each atlas in pixels.
+    pub max_side: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IconFormat {
+    #[default]
+    Png,
+    Webp,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Also write `<path>.provenance.json` with the source of every field.
+    #[serde(default)]
+    pub provenance: bool,
+    /// Also write `<path>.sets.json` with the full sets of the items.
+    #[serde(default)]
+    pub sets: bool,
+    /// Criteria replacing those of the project filter for this output.
+    #[serde(default)]
+    pub filter: ItemFilter,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Ron,
+}
+
+impl Default for IconConfig {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_ICON_TEMPLATE.to_string(),
+            export: None,
+        }
+    }
+}
+
+impl Default for IconExport {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("icons"),
+            url: "icons".to_string(),
+            format: IconFormat::default(),
+            sizes: vec![64],
+            atlas: None,
+        }
+    }
+}
+
+impl IconConfig {
+    /// Icon URL or path of an ItemDisplayInfo icon name.
+    pub fn render(&self, icon: &str) -> String {
+        match &self.export {
+            Some(export) => export.path(export.sizes[0], icon),
+            None => self.template.replace("{icon}", &icon.to_lowercase()),
+        }
+    }
+}
+
+impl Default for AtlasConfig {
+    fn default() -> Self {
+        Self {
+            size: None,
+            max_side: 2048,
+        }
+    }
+}
+
+impl IconExport {
+    /// Path of `icon` at `size` below `dir` or `url`.
+    pub fn path(&self, size: u32, icon: &str) -> String {
+        format!(
+            "{}/{size}/{}.{}",
+            self.url.trim_end_matches('/'),
+            icon.to_lowercase(),
+            self.format.extension()
+        )
+    }
+
+    /// Icon size the atlases are packed at.
+    pub fn atlas_size(&self) -> Option<u32> {
+        let atlas = self.atlas.as_ref()?;
+        Some(atlas.size.unwrap_or(self.sizes[0]))
+    }
+
+    /// Directory the atlases of `size` and their manifest are written to.
+    pub fn atlas_dir(&self, size: u32) -> PathBuf {
+        self.dir.join("atlas").join(size.to_string())
+    }
+
+    /// File `icon` at `size` is written to.
+    pub fn file(&self, size: u32, icon: &str) -> PathBuf {
+        self.dir.join(size.to_string()).join(format!(
+            "{}.{}",
+            icon.to_lowercase(),
+            self.format.extension()
+        ))
+    }
+}
+
+impl IconFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            IconFormat::Png => "png",
+            IconFormat::Webp => "webp",
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Ron => "ron",
+        }
+    }
+}
+
+impl OutputConfig {
+    /// Path of a file written next to the output, such as
+    /// `items.provenance.json` for `items.json`.
+    pub fn sidecar(&self, name: &str) -> PathBuf {
+        self.path.with_extension(format!("{name}.json"))
+    }
+}
+
+impl ProjectConfig {
+    /// Read a config without validating it, so command line options can
+    /// fill in what it lacks first.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut config: Self =
+            toml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?;
+        config.resolve_paths(path.parent().unwrap_or(Path::new("")));
+        Ok(config)
+    }
+
+    /// Make every relative path relative to `base` instead.
+    pub fn resolve_paths(&mut self, base: &Path) {
+        for dir in &mut self.data_dirs {
+            *dir = base.join(&*dir);
+        }
+        self.merge.resolve_paths(base);
+        if let Some(dir) = &mut self.layouts.dir {
+            *dir = base.join(&*dir);
+        }
+        if let Some(export) = &mut self.icons.export {
+            export.dir = base.join(&export.dir);
+        }
+        if let Some(path) = &mut self.reference.stock_items {
+            *path = base.join(&*path);
+        }
+        if let Some(path) = &mut self.reference.enchantment_pools {
+            *path = base.join(&*path);
+        }
+        for output in &mut self.outputs {
+            output.path = base.join(&output.path);
+        }
+        if let Some(history) = &mut self.history {
+            history.path = base.join(&history.path);
+        }
+        if let Some(path) = &mut self.diagnostics {
+            *path = base.join(&*path);
+        }
+    }
+
+    /// Check everything `build` relies on.
+    pub fn validate(&self) -> Result<()> {
+        if self.data_dirs.is_empty() {
+            bail!("no data directories configured");
+        }
+        self.validate_locales()?;
+        if self.outputs.is_empty() {
+            bail!("no outputs configured");
+        }
+        if let Some(export) = &self.icons.export
+            && (export.sizes.is_empty() || export.sizes.contains(&0))
+        {
+            bail!("icon export needs one or more non-zero sizes");
+        }
+        if let Some(export) = &self.icons.export
+            && let Some(atlas) = &export.atlas
+        {
+            let size = export.atlas_size().unwrap_or_default();
+            if !export.sizes.contains(&size) {
+                bail!("atlas size {size} isn't one of the exported icon sizes");
+            }
+            if atlas.max_side < size {
+                bail!(
+                    "atlases of at most {} pixels can't fit a {size} pixel icon",
+                    atlas.max_side
+                );
+            }
+        }
+        self.merge.validate()
+    }
+
+    /// Check the configured locales, which every command reading the
+    /// archives relies on.
+    pub fn validate_locales(&self) -> Result<()> {
+        for locale in self.locale.iter().chain(self.locales.iter().flatten()) {
+            if mpq::client_locale(locale).is_none() {
+                bail!(
+                    "unknown client locale `{locale}`, expected one of {}",
+                    mpq::CLIENT_LOCALES.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Locales to build items for, the one of the item text first.
+    pub fn locales(&self) -> Result<Vec<String>> {
+        let found = mpq::data_locales(&self.data_dirs)?;
+        let locale = match &self.locale {
+            Some(locale) => mpq::client_locale(locale).unwrap_or(DEFAULT_LOCALE),
+            None if found.is_empty() || found.contains(&DEFAULT_LOCALE) => DEFAULT_LOCALE,
+            None => found[0],
+        };
+        let others: Vec<&str> = match &self.locales {
+            Some(locales) => locales
+                .iter()
+                .filter_map(|locale| mpq::client_locale(locale))
+                .collect(),
+            None => found,
+        };
+
+        let mut locales = vec![locale.to_string()];
+        for other in others {
+            if !locales.iter().any(|known| known == other) {
+                locales.push(other.to_string());
+            }
+        }
+        Ok(locales)
+    }
+
+    /// Whether any output filters by item status.
+    pub fn needs_stock_items(&self) -> bool {
+        self.outputs
+            .iter()
+            .any(|output| self.output_filter(output).needs_stock_items())
+    }
+
+    /// Project filter with the criteria of `output` applied on top.
+    pub fn output_filter(&self, output: &OutputConfig) -> ItemFilter {
+        self.filter.clone().overlay(output.filter.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_configs_without_outputs() {
+        let path = std::env::temp_dir().join(format!("project-{}.toml", std::process::id()));
+        fs::write(&path, "data_dirs = [\"Data\"]\nlocale = \"deDE\"\n").unwrap();
+        let loaded = ProjectConfig::load(&path);
+        fs::remove_file(&path).unwrap();
+        let mut config = loaded.unwrap();
+
+        config.validate_locales().unwrap();
+        let err = config.validate().unwrap_err();
+        assert_eq!(err.to_string(), "no outputs configured");
+
+        config.outputs.push(OutputConfig {
+            path: PathBuf::from("items.json"),
+            format: OutputFormat::Json,
+            provenance: false,
+            sets: false,
+            filter: ItemFilter::default(),
+        });
+        config.validate().unwrap();
+        config.locale = Some("xxXX".to_string());
+        assert!(config.validate().is_err());
+    }
+}
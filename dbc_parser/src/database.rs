@@ -0,0 +1,499 @@
+use anyhow::{Result, bail};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+use wow_dbc::wrath_tables::{item::ItemRow, item_set::ItemSetRow, spell::SpellKey};
+
+use crate::{
+    config::IconConfig,
+    diagnostics::Diagnostics,
+    layout::Layouts,
+    locale::LocalizedText,
+    merge::{FieldConflict, ItemPatch, SourceRank, SupplementalSources},
+    models::{InventoryType, Item, ItemClass, ItemSetDetails, SetBonus},
+    mpq::collect_data_dirs,
+    parse::{DbcTables, item, item_sets},
+    provenance::{ItemProvenance, Source},
+    random::EnchantmentPools,
+    requirements::Requirement,
+    stats, weapon,
+};
+
+/// Items built from the client `.dbc` tables and supplemental sources.
+#[derive(Debug, Clone, Default)]
+pub struct ItemDatabase {
+    items: BTreeMap<i32, Item>,
+    provenance: BTreeMap<i32, ItemProvenance>,
+    conflicts: BTreeMap<i32, Vec<FieldConflict>>,
+    /// MPQ archives the tables were read from, in priority order.
+    archives: Vec<PathBuf>,
+    /// Lowercase inventory icon name of every ItemDisplayInfo entry, by the
+    /// link items carry to it.
+    icons: BTreeMap<String, String>,
+    /// Sets of the items, keyed by set id.
+    sets: BTreeMap<i32, ItemSetDetails>,
+    diagnostics: Diagnostics,
+}
+
+impl ItemDatabase {
+    /// Parse every MPQ in `data_dirs` (and their directories of the first of
+    /// `locales`) and merge `sources` into the resulting items. Items also
+    /// get their text in each further locale, read from the text columns of
+    /// its own directories and merged with its sources. Tables with an
+    /// override in `layouts` are read through it. Items with random
+    /// enchantments list the variants of their pools in `pools`.
+    pub fn build(
+        data_dirs: &[PathBuf],
+        sources: &SupplementalSources,
+        icons: &IconConfig,
+        layouts: &Layouts,
+        locales: &[String],
+        pools: &EnchantmentPools,
+    ) -> Result<Self> {
+        let Some((locale, others)) = locales.split_first() else {
+            bail!("no client locale to build items for");
+        };
+        // Load MPQs in priority order
+        let mpq_paths = collect_data_dirs(data_dirs, locale)?;
+        let mut tables = DbcTables::load(&mpq_paths, layouts)?;
+        let mut database = Self::from_tables(&tables, sources, icons, locale, pools)?;
+        database.archives = mpq_paths;
+        if others.is_empty() {
+            return Ok(database);
+        }
+
+        database.diagnostics.set_locale(locale);
+        for other in others {
+            let mpq_paths = collect_data_dirs(data_dirs, other)?;
+            let mut diagnostics = Diagnostics {
+                tables: tables.localize(&mpq_paths, layouts)?,
+                sources: Vec::new(),
+            };
+            let merged = merge_items(&tables, sources, icons, other, pools, &mut diagnostics)?;
+            diagnostics.set_locale(other);
+            database.add_locale(other, &merged.items, diagnostics);
+        }
+        Ok(database)
+    }
+
+    /// Merge `sources` of `locale` into the items of `tables`.
+    pub(crate) fn from_tables(
+        tables: &DbcTables,
+        sources: &SupplementalSources,
+        icons: &IconConfig,
+        locale: &str,
+        pools: &EnchantmentPools,
+    ) -> Result<Self> {
+        let mut diagnostics = Diagnostics {
+            tables: tables.reports.clone(),
+            sources: Vec::new(),
+        };
+        let MergedItems {
+            items,
+            provenance,
+            conflicts,
+        } = merge_items(tables, sources, icons, locale, pools, &mut diagnostics)?;
+
+        if !conflicts.is_empty() {
+            println!("Found conflicting values in {} items", conflicts.len());
+        }
+
+        let mismatches = items
+            .values()
+            .filter(|item| item.weapon.as_ref().is_some_and(|w| w.dps_mismatch))
+            .count();
+        if mismatches > 0 {
+            println!(
+                "Found {mismatches} weapons where the stated DPS doesn't match damage and speed"
+            );
+        }
+
+        Ok(Self {
+            items,
+            provenance,
+            conflicts,
+            archives: Vec::new(),
+            icons: tables
+                .item_display_infos
+                .icons()
+                .map(|icon| (icons.render(icon), icon.to_lowercase()))
+                .collect(),
+            sets: tables
+                .item_sets
+                .iter()
+                .map(|row| (row.id.id, build_set(tables, row)))
+                .collect(),
+            diagnostics,
+        })
+    }
+
+    /// Keep the translated text of `items`, built for `locale`, and the
+    /// diagnostics of reading it.
+    fn add_locale(&mut self, locale: &str, items: &BTreeMap<i32, Item>, diagnostics: Diagnostics) {
+        for (id, item) in &mut self.items {
+            if let Some(other) = items.get(id) {
+                item.localized
+                    .insert(locale.to_string(), other.localized_text());
+            }
+        }
+        self.diagnostics.tables.extend(diagnostics.tables);
+        self.diagnostics.sources.extend(diagnostics.sources);
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Item> {
+        self.items.get(&id)
+    }
+
+    /// Where each field of item `id` came from.
+    pub fn provenance(&self, id: i32) -> Option<&ItemProvenance> {
+        self.provenance.get(&id)
+    }
+
+    /// Provenance of every remaining item, keyed by item id.
+    pub fn provenance_map(&self) -> &BTreeMap<i32, ItemProvenance> {
+        &self.provenance
+    }
+
+    /// Fields that sources disagreed on, keyed by item id. Only recorded
+    /// under the `keep_both` conflict strategy.
+    pub fn conflicts(&self) -> &BTreeMap<i32, Vec<FieldConflict>> {
+        &self.conflicts
+    }
+
+    /// MPQ archives the items were built from, in priority order.
+    pub fn archives(&self) -> &[PathBuf] {
+        &self.archives
+    }
+
+    /// Icons the items link to, for exporting them out of the archives.
+    pub fn icon_names(&self) -> BTreeSet<String> {
+        self.items
+            .values()
+            .filter_map(|item| self.icons.get(&item.inventory_icon))
+            .cloned()
+            .collect()
+    }
+
+    /// Outcome of every table and supplemental source of the build.
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// All items ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        self.items.values()
+    }
+
+    pub fn by_class(&self, class: ItemClass) -> impl Iterator<Item = &Item> {
+        self.iter().filter(move |item| item.class == class)
+    }
+
+    pub fn by_inventory_type(&self, slot: InventoryType) -> impl Iterator<Item = &Item> {
+        self.iter().filter(move |item| item.inventory_type == slot)
+    }
+
+    /// Items a character of `level` and ChrClasses `class_id` can use.
+    pub fn usable_by(&self, level: u32, class_id: i32) -> impl Iterator<Item = &Item> {
+        self.iter()
+            .filter(move |item| item.usable_by(level, class_id))
+    }
+
+    /// Sets of the remaining items, ordered by id.
+    pub fn sets(&self) -> impl Iterator<Item = &ItemSetDetails> {
+        self.sets.values()
+    }
+
+    pub fn set(&self, set_id: i32) -> Option<&ItemSetDetails> {
+        self.sets.get(&set_id)
+    }
+
+    pub fn by_set(&self, set_id: i32) -> impl Iterator<Item = &Item> {
+        self.iter()
+            .filter(move |item| item.set.as_ref().is_some_and(|set| set.id == set_id))
+    }
+
+    /// Keep only the items matching `f`.
+    pub fn retain(&mut self, mut f: impl FnMut(&Item) -> bool) {
+        self.items.retain(|_, item| f(item));
+        self.provenance.retain(|id, _| self.items.contains_key(id));
+        self.conflicts.retain(|id, _| self.items.contains_key(id));
+        let set_ids: BTreeSet<i32> = self
+            .items
+            .values()
+            .filter_map(|item| Some(item.set.as_ref()?.id))
+            .collect();
+        self.sets.retain(|id, _| set_ids.contains(id));
+    }
+
+    /// Consume the database, returning the items ordered by id.
+    pub fn into_items(self) -> Vec<Item> {
+        self.items.into_values().collect()
+    }
+}
+
+/// An ItemSet.dbc row with its members checked against Item.dbc and its
+/// bonuses and skill resolved.
+fn build_set(tables: &DbcTables, row: &ItemSetRow) -> ItemSetDetails {
+    let items: Vec<i32> = item_sets::members(row).collect();
+    let missing_items = items
+        .iter()
+        .copied()
+        .filter(|&id| !tables.items.contains(id))
+        .collect();
+
+    let spell_text = tables.spell_text();
+    let mut bonuses: Vec<SetBonus> = row
+        .set_spell_id
+        .iter()
+        .zip(row.set_threshold)
+        .filter(|&(&spell_id, _)| spell_id > 0)
+        .map(|(&spell_id, pieces)| SetBonus {
+            pieces: pieces as u32,
+            spell_id,
+            text: tables
+                .spells
+                .get(SpellKey::new(spell_id))
+                .map(|spell| spell_text.render_description(spell))
+                .unwrap_or_default(),
+        })
+        .collect();
+    bonuses.sort_by_key(|bonus| bonus.pieces);
+
+    let required_skill = (row.required_skill.id > 0).then(|| Requirement::Skill {
+        id: Some(row.required_skill.id),
+        name: tables
+            .skill_lines
+            .get(row.required_skill)
+            .map(|skill| skill.display_name_lang.text().to_string())
+            .unwrap_or_default(),
+        rank: row.required_skill_rank as u32,
+    });
+
+    ItemSetDetails {
+        id: row.id.id,
+        name: row.name_lang.text().to_string(),
+        items,
+        missing_items,
+        bonuses,
+        required_skill,
+    }
+}
+
+/// Items merged from the tables and sources of one locale.
+struct MergedItems {
+    items: BTreeMap<i32, Item>,
+    provenance: BTreeMap<i32, ItemProvenance>,
+    conflicts: BTreeMap<i32, Vec<FieldConflict>>,
+}
+
+/// Merge the sources of `locale` into every item of `tables`, reporting the
+/// outcome of each source in `diagnostics`.
+fn merge_items(
+    tables: &DbcTables,
+    sources: &SupplementalSources,
+    icons: &IconConfig,
+    locale: &str,
+    pools: &EnchantmentPools,
+    diagnostics: &mut Diagnostics,
+) -> Result<MergedItems> {
+    let loaded = sources.load_patches(tables, icons, locale, &mut diagnostics.sources)?;
+    let dbc_rank = SourceRank::dbc(&sources.policy);
+    let random_enchantments = tables.random_enchantments();
+
+    let mut merged = MergedItems {
+        items: BTreeMap::new(),
+        provenance: BTreeMap::new(),
+        conflicts: BTreeMap::new(),
+    };
+    for item_row in tables.items.iter_rows() {
+        let mut item_provenance = ItemProvenance::default();
+        let mut item = build_item(tables, item_row, &mut item_provenance);
+
+        let dbc_patch = ItemPatch::from_dbc(tables, icons, item_row);
+        let mut candidates = vec![(dbc_rank, &dbc_patch)];
+        candidates.extend(
+            loaded
+                .iter()
+                .filter_map(|source| Some((source.rank, source.patches.get(&item.id)?))),
+        );
+        let item_conflicts = sources
+            .policy
+            .apply(&mut item, &candidates, &mut item_provenance);
+        if !item_conflicts.is_empty() {
+            merged.conflicts.insert(item.id, item_conflicts);
+        }
+
+        derive_fields(&mut item, &mut item_provenance);
+        if let Some(random) = &mut item.random {
+            random.variants =
+                random_enchantments.variants(random, item.rarity, item.inventory_type, pools);
+        }
+        merged.provenance.insert(item.id, item_provenance);
+        merged.items.insert(item.id, item);
+    }
+    Ok(merged)
+}
+
+/// Item with placeholder values for every field but its id.
+fn build_item(tables: &DbcTables, item_row: &ItemRow, provenance: &mut ItemProvenance) -> Item {
+    for field in Item::FIELDS {
+        provenance.set(field, Source::Default);
+    }
+    provenance.set("id", tables.sources.source(item::FILE_NAME));
+    Item::from(item_row)
+}
+
+/// Fill the typed fields parsed from display strings, which inherit the
+/// source of the strings they were parsed from.
+fn derive_fields(item: &mut Item, provenance: &mut ItemProvenance) {
+    let source_of = |provenance: &ItemProvenance, field| {
+        provenance.get(field).cloned().unwrap_or(Source::Default)
+    };
+
+    if item.required_level > 0 {
+        item.requirements
+            .insert(0, Requirement::Level(item.required_level));
+    }
+    item.stat_modifiers = stats::parse_item_stats(&item.stats, &item.spells);
+    item.weapon = weapon::parse_weapon(&item.damage, &item.added_damage, &item.speed, &item.dps);
+
+    provenance.set("stat_modifiers", source_of(provenance, "stats"));
+    provenance.set("weapon", source_of(provenance, "damage"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dbc::Value,
+        merge::{SourceConfig, SourceKind},
+        parse::tests::{blank_row, loc_string, parse_records, parse_table, sub_class},
+    };
+    use wow_dbc::wrath_tables::item_display_info::{ItemDisplayInfo, ItemDisplayInfoKey};
+
+    /// Tables of a deDE client with one sword in a set.
+    fn german_tables() -> DbcTables {
+        let mut tables = DbcTables::default();
+        let int = Value::Int;
+        parse_records(
+            &mut tables.items,
+            &[vec![
+                int(100),
+                int(2),
+                int(7),
+                int(-1),
+                int(1),
+                int(0),
+                int(13),
+                int(3),
+            ]],
+        );
+        parse_records(
+            &mut tables.item_sub_classes,
+            &[sub_class(2, 7, "Schwerter")],
+        );
+        let mut members = vec![int(0); 17];
+        members[0] = int(100);
+        parse_records(
+            &mut tables.item_sets,
+            &[vec![
+                int(5),
+                loc_string(3, "Schlachtrüstung"),
+                Value::Array(members),
+                Value::Array(vec![int(0); 8]),
+                Value::Array(vec![int(0); 8]),
+                int(0),
+                int(0),
+            ]],
+        );
+        tables
+    }
+
+    #[test]
+    fn german_build_keeps_dbc_text_over_untagged_item_cache() {
+        let path = std::env::temp_dir().join(format!("item-cache-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"100": {"name": "Sword of English", "spells": ["Equip: English text."]}}"#,
+        )
+        .unwrap();
+        let sources = SupplementalSources {
+            sources: vec![SourceConfig::from_path(SourceKind::ItemCache, path.clone())],
+            ..Default::default()
+        };
+
+        let tables = german_tables();
+        let pools = EnchantmentPools::default();
+        let icons = IconConfig::default();
+        let german = ItemDatabase::from_tables(&tables, &sources, &icons, "deDE", &pools);
+        let english = ItemDatabase::from_tables(&tables, &sources, &icons, "enUS", &pools);
+        std::fs::remove_file(&path).unwrap();
+
+        let item = german.unwrap().get(100).unwrap().clone();
+        assert_eq!(item.subclass, "Schwerter");
+        assert_eq!(item.set.unwrap().name, "Schlachtrüstung");
+        assert_eq!(item.name, Item::UNKNOWN_NAME);
+        assert!(item.spells.is_empty());
+
+        let item = english.unwrap().get(100).unwrap().clone();
+        assert_eq!(item.name, "Sword of English");
+        assert_eq!(item.spells, ["Equip: English text."]);
+    }
+
+    #[test]
+    fn exports_only_the_icons_of_its_items() {
+        let mut tables = german_tables();
+        let int = Value::Int;
+        parse_records(
+            &mut tables.items,
+            &[vec![
+                int(101),
+                int(2),
+                int(7),
+                int(-1),
+                int(1),
+                int(1),
+                int(13),
+                int(3),
+            ]],
+        );
+        let rows = [(1, "INV_Sword_04"), (2, "INV_Axe_01")].map(|(id, icon)| {
+            let mut row = blank_row::<ItemDisplayInfo>(25);
+            row.id = ItemDisplayInfoKey::new(id);
+            row.inventory_icon[0] = icon.to_string();
+            row
+        });
+        parse_table(
+            &mut tables.item_display_infos,
+            &ItemDisplayInfo {
+                rows: rows.to_vec(),
+            },
+        );
+
+        let mut database = ItemDatabase::from_tables(
+            &tables,
+            &SupplementalSources::default(),
+            &IconConfig::default(),
+            "enUS",
+            &EnchantmentPools::default(),
+        )
+        .unwrap();
+        assert_eq!(database.len(), 2);
+        assert_eq!(
+            database.icon_names(),
+            BTreeSet::from(["inv_sword_04".to_string()])
+        );
+
+        database.retain(|item| item.id != 101);
+        assert!(database.icon_names().is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::{collections::HashMap, fmt};
+
+use crate::layout::{Field, FieldType, LOCALES, Layout};
+
+/// Magic of WotLK client tables.
+pub const WDBC_MAGIC: &[u8; 4] = b"WDBC";
+
+const HEADER_SIZE: usize = 20;
+
+/// A `.dbc` file split into records and its string block, readable with any
+/// [`Layout`].
+#[derive(Debug, Clone)]
+pub struct DbcFile {
+    pub record_count: usize,
+    pub field_count: usize,
+    pub record_size: usize,
+    records: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+/// One record, with a value per field of the layout it was read with.
+pub type Record = Vec<Value>;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i64),
+    Float(f32),
+    String(String),
+    LocString(LocString),
+    /// Fields with a `[n]` array length.
+    Array(Vec<Value>),
+}
+
+/// A localized string, one entry per client locale (`enUS` first).
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct LocString {
+    pub strings: Vec<String>,
+    pub flags: u32,
+}
+
+impl fmt::Display for Value {
+    /// Localized strings show their first non-empty locale.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "{value:?}"),
+            Value::LocString(value) => write!(f, "{:?}", value.text()),
+            Value::Array(values) => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+impl LocString {
+    /// First non-empty string, the only one a client's own tables fill.
+    pub fn text(&self) -> &str {
+        self.strings
+            .iter()
+            .find(|s| !s.is_empty())
+            .map_or("", String::as_str)
+    }
+}
+
+impl DbcFile {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE || &data[..4] != WDBC_MAGIC {
+            bail!("not a WDBC file");
+        }
+        let header = |index: usize| {
+            let start = 4 + index * 4;
+            u32::from_le_bytes(data[start..start + 4].try_into().unwrap()) as usize
+        };
+        let record_count = header(0);
+        let field_count = header(1);
+        let record_size = header(2);
+        let string_size = header(3);
+
+        let records_end = HEADER_SIZE + record_count * record_size;
+        if data.len() < records_end + string_size {
+            bail!(
+                "truncated: {record_count} records of {record_size} bytes and {string_size} \
+                 bytes of strings need {} bytes, file has {}",
+                records_end + string_size,
+                data.len()
+            );
+        }
+        Ok(Self {
+            record_count,
+            field_count,
+            record_size,
+            records: data[HEADER_SIZE..records_end].to_vec(),
+            strings: data[records_end..records_end + string_size].to_vec(),
+        })
+    }
+
+    pub fn record(&self, index: usize) -> &[u8] {
+        &self.records[index * self.record_size..(index + 1) * self.record_size]
+    }
+
+    /// Raw value of the 4-byte field `field` of record `index`.
+    pub fn cell(&self, index: usize, field: usize) -> u32 {
+        let start = field * 4;
+        u32::from_le_bytes(self.record(index)[start..start + 4].try_into().unwrap())
+    }
+
+    pub fn string_block_size(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether `offset` is where a string of the string block starts.
+    pub fn is_string_start(&self, offset: u32) -> bool {
+        let offset = offset as usize;
+        offset == 0 || (offset < self.strings.len() && self.strings[offset - 1] == 0)
+    }
+
+    /// String starting at `offset` of the string block.
+    pub fn string(&self, offset: u32) -> Result<String> {
+        let start = offset as usize;
+        let Some(rest) = self.strings.get(start..) else {
+            bail!("string offset {offset} past the string block");
+        };
+        let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+
+    /// Every record read with `layout`, which must match the file's record
+    /// size and field count.
+    pub fn read(&self, layout: &Layout) -> Result<Vec<Record>> {
+        self.check_layout(layout)?;
+        (0..self.record_count)
+            .map(|index| {
+                let mut reader = RecordReader {
+                    file: self,
+                    data: self.record(index),
+                };
+                layout
+                    .fields
+                    .iter()
+                    .map(|field| reader.field(field))
+                    .collect::<Result<Record>>()
+                    .with_context(|| format!("record {index}"))
+            })
+            .collect()
+    }
+
+    /// Field `name` of every record read with `layout`, skipping the other
+    /// fields.
+    pub fn column(&self, layout: &Layout, name: &str) -> Result<Vec<Value>> {
+        self.check_layout(layout)?;
+        let Some((index, field)) = layout.field(name) else {
+            bail!("{} has no field {name}", layout.table);
+        };
+        let offset: usize = layout.fields[..index].iter().map(Field::byte_size).sum();
+        (0..self.record_count)
+            .map(|index| {
+                let mut reader = RecordReader {
+                    file: self,
+                    data: &self.record(index)[offset..],
+                };
+                reader
+                    .field(field)
+                    .with_context(|| format!("record {index}"))
+            })
+            .collect()
+    }
+
+    fn check_layout(&self, layout: &Layout) -> Result<()> {
+        if layout.record_size() != self.record_size || layout.field_count() != self.field_count {
+            bail!(
+                "layout of {} has {} fields in {} bytes, file has {} fields in {} bytes",
+                layout.table,
+                layout.field_count(),
+                layout.record_size(),
+                self.field_count,
+                self.record_size
+            );
+        }
+        Ok(())
+    }
+}
+
+struct RecordReader<'a> {
+    file: &'a DbcFile,
+    data: &'a [u8],
+}
+
+impl RecordReader<'_> {
+    fn field(&mut self, field: &Field) -> Result<Value> {
+        if field.count == 1 {
+            return self.value(field);
+        }
+        (0..field.count)
+            .map(|_| self.value(field))
+            .collect::<Result<_>>()
+            .map(Value::Array)
+    }
+
+    fn value(&mut self, field: &Field) -> Result<Value> {
+        Ok(match field.ty {
+            FieldType::Int => {
+                let bytes = self.take(field.size as usize / 8);
+                let mut buf = [0; 8];
+                buf[..bytes.len()].copy_from_slice(bytes);
+                let value = u64::from_le_bytes(buf);
+                let value = if field.signed {
+                    // Sign extend from the field size
+                    ((value << (64 - field.size)) as i64) >> (64 - field.size)
+                } else {
+                    value as i64
+                };
+                Value::Int(value)
+            }
+            FieldType::Float => Value::Float(f32::from_le_bytes(self.u32_bytes())),
+            FieldType::String => Value::String(self.string()?),
+            FieldType::LocString => {
+                let strings = (0..LOCALES).map(|_| self.string()).collect::<Result<_>>()?;
+                let flags = u32::from_le_bytes(self.u32_bytes());
+                Value::LocString(LocString { strings, flags })
+            }
+        })
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let offset = u32::from_le_bytes(self.u32_bytes());
+        self.file.string(offset)
+    }
+
+    fn u32_bytes(&mut self) -> [u8; 4] {
+        self.take(4).try_into().unwrap()
+    }
+
+    fn take(&mut self, len: usize) -> &[u8] {
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        bytes
+    }
+}
+
+/// Write `records` as a `.dbc` file with `layout`. Values of the wrong type
+/// are written as zero or an empty string.
+pub fn write(layout: &Layout, records: &[Record]) -> Vec<u8> {
+    let mut strings = StringBlock::default();
+    let mut body = Vec::with_capacity(records.len() * layout.record_size());
+    for record in records {
+        for (field, value) in layout.fields.iter().zip(record) {
+            let values = match value {
+                Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            // Pad or cut arrays to the field's length
+            for value in values.iter().take(field.count) {
+                write_value(field, value, &mut strings, &mut body);
+            }
+            for _ in values.len()..field.count {
+                write_value(field, &field.default_value(), &mut strings, &mut body);
+            }
+        }
+    }
+
+    let mut data = Vec::with_capacity(HEADER_SIZE + body.len() + strings.data.len());
+    data.extend_from_slice(WDBC_MAGIC);
+    for value in [
+        records.len(),
+        layout.field_count(),
+        layout.record_size(),
+        strings.data.len(),
+    ] {
+        data.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+    data.extend_from_slice(&body);
+    data.extend_from_slice(&strings.data);
+    data
+}
+
+fn write_value(field: &Field, value: &Value, strings: &mut StringBlock, out: &mut Vec<u8>) {
+    match field.ty {
+        FieldType::Int => {
+            let value = match value {
+                Value::Int(value) => *value,
+                Value::Float(value) => *value as i64,
+                _ => 0,
+            };
+            out.extend_from_slice(&value.to_le_bytes()[..field.size as usize / 8]);
+        }
+        FieldType::Float => {
+            let value = match value {
+                Value::Float(value) => *value,
+                Value::Int(value) => *value as f32,
+                _ => 0.0,
+            };
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        FieldType::String => {
+            let value = match value {
+                Value::String(value) => value.as_str(),
+                _ => "",
+            };
+            out.extend_from_slice(&strings.insert(value).to_le_bytes());
+        }
+        FieldType::LocString => {
+            let empty = LocString::default();
+            let value = match value {
+                Value::LocString(value) => value,
+                _ => &empty,
+            };
+            for locale in 0..LOCALES {
+                let string = value.strings.get(locale).map_or("", String::as_str);
+                out.extend_from_slice(&strings.insert(string).to_le_bytes());
+            }
+            out.extend_from_slice(&value.flags.to_le_bytes());
+        }
+    }
+}
+
+/// String block being written, each string stored once. Offset `0` is the
+/// empty string.
+struct StringBlock {
+    data: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl Default for StringBlock {
+    fn default() -> Self {
+        Self {
+            data: vec![0],
+            offsets: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringBlock {
+    fn insert(&mut self, string: &str) -> u32 {
+        if let Some(&offset) = self.offsets.get(string) {
+            return offset;
+        }
+        let offset = self.data.len() as u32;
+        self.data.extend_from_slice(string.as_bytes());
+        self.data.push(0);
+        self.offsets.insert(string.to_string(), offset);
+        offset
+    }
+}
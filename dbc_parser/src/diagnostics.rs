@@ -0,0 +1,199 @@
+use serde::Serialize;
+use std::{any::Any, path::PathBuf};
+
+/// What happened to every table and supplemental source of a build.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Diagnostics {
+    pub tables: Vec<TableReport>,
+    pub sources: Vec<SourceReport>,
+}
+
+/// Outcome of one registered DBC table.
+#[derive(Serialize, Debug, Clone)]
+pub struct TableReport {
+    /// Table such as `DBFilesClient\Item.dbc`.
+    pub table: String,
+    /// Handler registered for the table, such as `Items`.
+    pub handler: &'static str,
+    /// Archive the client loads the table from, `None` if no archive has it.
+    pub archive: Option<PathBuf>,
+    /// Layout definition the table was read through instead of the built-in
+    /// one.
+    pub layout: Option<PathBuf>,
+    /// Columns of `layout` the built-in layout has no place for, left out of
+    /// the parsed rows.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped_fields: Vec<String>,
+    /// Client locale whose archives were searched, when items are built for
+    /// more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(flatten)]
+    pub status: TableStatus,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum TableStatus {
+    Parsed {
+        rows: usize,
+    },
+    /// No archive contains the table, or the last one to list it deletes it.
+    Missing,
+    /// The handler returned an error.
+    Failed {
+        error: String,
+    },
+    Panicked {
+        message: String,
+    },
+}
+
+/// Outcome of one supplemental source.
+#[derive(Serialize, Debug, Clone)]
+pub struct SourceReport {
+    pub name: String,
+    pub path: PathBuf,
+    /// Client locale of the items the source was merged into, when items
+    /// are built for more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(flatten)]
+    pub status: SourceStatus,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SourceStatus {
+    Loaded {
+        items: usize,
+    },
+    /// Left out by the `min_trust` merge policy.
+    BelowTrust,
+    /// Text of another client locale than the items being built.
+    OtherLocale {
+        locale: String,
+    },
+    /// The source couldn't be read or parsed.
+    Failed {
+        error: String,
+    },
+}
+
+impl TableReport {
+    /// Whether items built without this table would be incomplete.
+    pub fn is_problem(&self) -> bool {
+        !matches!(self.status, TableStatus::Parsed { rows } if rows > 0)
+    }
+}
+
+impl SourceReport {
+    pub fn is_problem(&self) -> bool {
+        matches!(
+            self.status,
+            SourceStatus::Loaded { items: 0 } | SourceStatus::Failed { .. }
+        )
+    }
+}
+
+impl TableStatus {
+    /// Status of a handler that panicked with `payload`.
+    pub fn panicked(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map_or("unknown panic", |message| message)
+                .to_string(),
+        };
+        TableStatus::Panicked { message }
+    }
+}
+
+impl Diagnostics {
+    /// Mark every report as one of `locale`.
+    pub fn set_locale(&mut self, locale: &str) {
+        for table in &mut self.tables {
+            table.locale = Some(locale.to_string());
+        }
+        for source in &mut self.sources {
+            source.locale = Some(locale.to_string());
+        }
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.tables
+            .iter()
+            .filter(|table| table.is_problem())
+            .count()
+            + self
+                .sources
+                .iter()
+                .filter(|source| source.is_problem())
+                .count()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.problem_count() == 0
+    }
+
+    /// Print one line per table and source, then the number of problems.
+    pub fn print_summary(&self) {
+        println!("Tables:");
+        for table in &self.tables {
+            let status = match &table.status {
+                TableStatus::Parsed { rows } => format!("{rows} rows"),
+                TableStatus::Missing => "MISSING".to_string(),
+                TableStatus::Failed { error } => format!("FAILED: {error}"),
+                TableStatus::Panicked { message } => format!("PANICKED: {message}"),
+            };
+            let archive = table
+                .archive
+                .as_ref()
+                .map(|archive| format!(" <- {}", archive.display()))
+                .unwrap_or_default();
+            let layout = table
+                .layout
+                .as_ref()
+                .map(|layout| format!(" via {}", layout.display()))
+                .unwrap_or_default();
+            let dropped = match table.dropped_fields.as_slice() {
+                [] => String::new(),
+                fields => format!(", dropped {}", fields.join(", ")),
+            };
+            println!(
+                "  {}{} ({}): {status}{archive}{layout}{dropped}",
+                locale_prefix(table.locale.as_deref()),
+                table.table,
+                table.handler
+            );
+        }
+        if !self.sources.is_empty() {
+            println!("Sources:");
+            for source in &self.sources {
+                let status = match &source.status {
+                    SourceStatus::Loaded { items } => format!("{items} items"),
+                    SourceStatus::BelowTrust => "skipped, below minimum trust".to_string(),
+                    SourceStatus::OtherLocale { locale } => format!("skipped, {locale} text"),
+                    SourceStatus::Failed { error } => format!("FAILED: {error}"),
+                };
+                println!(
+                    "  {}{} ({}): {status}",
+                    locale_prefix(source.locale.as_deref()),
+                    source.name,
+                    source.path.display()
+                );
+            }
+        }
+        match self.problem_count() {
+            0 => println!("No problems found"),
+            count => println!("Found {count} problems"),
+        }
+    }
+}
+
+fn locale_prefix(locale: Option<&str>) -> String {
+    locale
+        .map(|locale| format!("[{locale}] "))
+        .unwrap_or_default()
+}
@@ -0,0 +1,406 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::models::Item;
+
+/// Fields parsed from other fields, left out so a change is only listed once.
+const DERIVED_FIELDS: &[&str] = &["requirements", "stat_modifiers", "weapon"];
+
+/// Items of one export as JSON values keyed by item id. Using values instead
+/// of [`Item`] lets exports made with older field layouts be compared too.
+pub type ExportItems = BTreeMap<i32, Value>;
+
+/// Differences between two exports.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ItemDiff {
+    pub added: Vec<ItemSummary>,
+    pub removed: Vec<ItemSummary>,
+    pub changed: Vec<ItemChange>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ItemSummary {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ItemChange {
+    pub id: i32,
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// A changed field, with nested fields written as `set.spells`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Read a JSON or RON export. RON exports have to match the current
+/// [`Item`] layout.
+pub fn load_export(path: &Path) -> Result<ExportItems> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let items: Vec<Value> = match path.extension().and_then(|ext| ext.to_str()) {
+        // RON only reads back into the types it was written from
+        Some("ron") => ron::from_str::<Vec<Item>>(&content)
+            .with_context(|| format!("parsing {}", path.display()))?
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?,
+        _ => serde_json::from_str(&content)?,
+    };
+
+    let mut by_id = BTreeMap::new();
+    for item in items {
+        let Some(id) = item.get("id").and_then(Value::as_i64) else {
+            bail!("item without id in {}", path.display());
+        };
+        by_id.insert(id as i32, item);
+    }
+    Ok(by_id)
+}
+
+/// Items of a freshly built database, in the same form as a loaded export.
+pub fn export_items<'a>(items: impl IntoIterator<Item = &'a Item>) -> Result<ExportItems> {
+    items
+        .into_iter()
+        .map(|item| Ok((item.id, serde_json::to_value(item)?)))
+        .collect()
+}
+
+impl ItemDiff {
+    pub fn between(before: &ExportItems, after: &ExportItems) -> Self {
+        let mut diff = ItemDiff::default();
+
+        for (id, item) in after {
+            match before.get(id) {
+                None => diff.added.push(ItemSummary::new(*id, item)),
+                Some(old) => {
+                    let mut fields = Vec::new();
+                    diff_values("", old, item, &mut fields);
+                    if !fields.is_empty() {
+                        diff.changed.push(ItemChange {
+                            id: *id,
+                            name: item_name(item),
+                            fields,
+                        });
+                    }
+                }
+            }
+        }
+        diff.removed = before
+            .iter()
+            .filter(|(id, _)| !after.contains_key(id))
+            .map(|(id, item)| ItemSummary::new(*id, item))
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Patch notes listing every added, removed and changed item.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![
+            "# Item changes".to_string(),
+            String::new(),
+            format!(
+                "{} added, {} removed, {} changed",
+                self.added.len(),
+                self.removed.len(),
+                self.changed.len()
+            ),
+            String::new(),
+        ];
+
+        for (title, items) in [("Added", &self.added), ("Removed", &self.removed)] {
+            if items.is_empty() {
+                continue;
+            }
+            lines.push(format!("## {title} items"));
+            lines.push(String::new());
+            lines.extend(
+                items
+                    .iter()
+                    .map(|item| format!("- [{}] {}", item.id, item.name)),
+            );
+            lines.push(String::new());
+        }
+
+        if !self.changed.is_empty() {
+            lines.push("## Changed items".to_string());
+            lines.push(String::new());
+            for item in &self.changed {
+                lines.push(format!("### [{}] {}", item.id, item.name));
+                lines.push(String::new());
+                lines.extend(
+                    item.fields.iter().map(|change| {
+                        format!("- **{}**: {}", change.field, describe_change(change))
+                    }),
+                );
+                lines.push(String::new());
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl ItemSummary {
+    fn new(id: i32, item: &Value) -> Self {
+        Self {
+            id,
+            name: item_name(item),
+        }
+    }
+}
+
+fn item_name(item: &Value) -> String {
+    item.get("name")
+        .and_then(Value::as_str)
+        .unwrap_or(Item::UNKNOWN_NAME)
+        .to_string()
+}
+
+/// Collect changed fields, descending into objects such as `set`.
+fn diff_values(path: &str, before: &Value, after: &Value, changes: &mut Vec<FieldChange>) {
+    if before == after {
+        return;
+    }
+    if let (Value::Object(before), Value::Object(after)) = (before, after) {
+        let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            if path.is_empty() && DERIVED_FIELDS.contains(&key.as_str()) {
+                continue;
+            }
+            let field = match path {
+                "" => key.clone(),
+                _ => format!("{path}.{key}"),
+            };
+            diff_values(
+                &field,
+                before.get(key).unwrap_or(&Value::Null),
+                after.get(key).unwrap_or(&Value::Null),
+                changes,
+            );
+        }
+        return;
+    }
+    changes.push(FieldChange {
+        field: path.to_string(),
+        before: before.clone(),
+        after: after.clone(),
+    });
+}
+
+/// `before → after` for single values, added and removed entries for lists
+/// such as stats, spells and set bonuses.
+fn describe_change(change: &FieldChange) -> String {
+    if let (Value::Array(before), Value::Array(after)) = (&change.before, &change.after) {
+        let removed: Vec<String> = before
+            .iter()
+            .filter(|value| !after.contains(value))
+            .map(|value| format!("`{}`", describe(value)))
+            .collect();
+        let added: Vec<String> = after
+            .iter()
+            .filter(|value| !before.contains(value))
+            .map(|value| format!("`{}`", describe(value)))
+            .collect();
+        let mut parts = Vec::new();
+        if !removed.is_empty() {
+            parts.push(format!("removed {}", removed.join(", ")));
+        }
+        if !added.is_empty() {
+            parts.push(format!("added {}", added.join(", ")));
+        }
+        if parts.is_empty() {
+            return "reordered".to_string();
+        }
+        return parts.join("; ");
+    }
+    format!(
+        "`{}` → `{}`",
+        describe(&change.before),
+        describe(&change.after)
+    )
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::String(s) => s.clone(),
+        // Set bonuses are `[pieces, text]`
+        Value::Array(pair) if pair.len() == 2 && pair[0].is_number() && pair[1].is_string() => {
+            format!("({}) {}", pair[0], pair[1].as_str().unwrap_or_default())
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn loads_ron_exports() {
+        let item = json!({
+            "id": 19019,
+            "name": "Thunderfury, Blessed Blade of the Windseeker",
+            "class": "Weapon",
+            "subclass": "Sword",
+            "inventory_icon": "inv_sword_39",
+            "inventory_type": "OneHand",
+            "set": null,
+            "required_level": 60,
+            "stats": ["+5 Agility"],
+            "stat_modifiers": [{"Modifier": {"stat": "Agility", "value": 5}}],
+            "spells": [],
+            "requires": [],
+            "requirements": [{"Level": 60}],
+            "rarity": "Legendary",
+            "damage": "44 - 115 Damage",
+            "added_damage": "",
+            "armor": "",
+            "speed": "1.90",
+            "dps": "",
+            "weapon": null,
+            "bonding": "Binds when picked up",
+            "hands": "One-Hand",
+            "sockets": [],
+            "socket_bonus": "",
+            "random": null,
+        });
+        let items: Vec<Item> = vec![serde_json::from_value(item.clone()).unwrap()];
+        let path = std::env::temp_dir().join(format!("items-{}.ron", std::process::id()));
+        fs::write(&path, ron::to_string(&items).unwrap()).unwrap();
+
+        let loaded = load_export(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), BTreeMap::from([(19019, item)]));
+    }
+
+    fn export(items: &[Value]) -> ExportItems {
+        items
+            .iter()
+            .map(|item| (item["id"].as_i64().unwrap() as i32, item.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_added_removed_and_changed_items() {
+        let before = export(&[
+            json!({"id": 1, "name": "Old Sword", "required_level": 10}),
+            json!({
+                "id": 2,
+                "name": "Helm",
+                "required_level": 20,
+                "stats": ["+5 Stamina", "+3 Agility"],
+                "requirements": [{"Level": 20}],
+                "set": {"id": 7, "spells": [[2, "Bonus"]]},
+            }),
+            json!({"id": 3, "name": "Ring"}),
+        ]);
+        let after = export(&[
+            json!({
+                "id": 2,
+                "name": "Helm",
+                "required_level": 25,
+                "stats": ["+5 Stamina", "+4 Agility"],
+                "requirements": [{"Level": 25}],
+                "set": {"id": 7, "spells": [[2, "Bonus"], [4, "More"]]},
+            }),
+            json!({"id": 3, "name": "Ring"}),
+            json!({"id": 4}),
+        ]);
+
+        let diff = ItemDiff::between(&before, &after);
+        assert_eq!(
+            diff.added,
+            [ItemSummary {
+                id: 4,
+                name: Item::UNKNOWN_NAME.to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            [ItemSummary {
+                id: 1,
+                name: "Old Sword".to_string(),
+            }]
+        );
+        let [change] = &diff.changed[..] else {
+            panic!("expected one change, got {:?}", diff.changed);
+        };
+        let fields: Vec<&str> = change.fields.iter().map(|f| f.field.as_str()).collect();
+        // `requirements` follows from `required_level` and isn't listed twice
+        assert_eq!(fields, ["required_level", "set.spells", "stats"]);
+        assert!(ItemDiff::between(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn renders_patch_notes() {
+        let change = |field: &str, before, after| FieldChange {
+            field: field.to_string(),
+            before,
+            after,
+        };
+        let diff = ItemDiff {
+            added: vec![ItemSummary {
+                id: 4,
+                name: "Axe".to_string(),
+            }],
+            removed: Vec::new(),
+            changed: vec![ItemChange {
+                id: 2,
+                name: "Helm".to_string(),
+                fields: vec![
+                    change("required_level", json!(20), json!(25)),
+                    change("set", json!(null), json!({"id": 7})),
+                    change(
+                        "stats",
+                        json!(["+5 Stamina", "+3 Agility"]),
+                        json!(["+5 Stamina", "+4 Agility"]),
+                    ),
+                    change("set.spells", json!([[2, "Bonus"]]), json!([[4, "More"]])),
+                    change("spells", json!(["A", "B"]), json!(["B", "A"])),
+                ],
+            }],
+        };
+        assert_eq!(
+            diff.to_markdown(),
+            [
+                "# Item changes",
+                "",
+                "1 added, 0 removed, 1 changed",
+                "",
+                "## Added items",
+                "",
+                "- [4] Axe",
+                "",
+                "## Changed items",
+                "",
+                "### [2] Helm",
+                "",
+                "- **required_level**: `20` → `25`",
+                "- **set**: `none` → `{\"id\":7}`",
+                "- **stats**: removed `+3 Agility`; added `+4 Agility`",
+                "- **set.spells**: removed `(2) Bonus`; added `(4) More`",
+                "- **spells**: reordered",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params_from_iter, types::Value as SqlValue};
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use serde_json::Value as JsonValue;
+use std::{fs, path::Path};
+
+use crate::{
+    dbc::{DbcFile, Record, Value},
+    infer::{self, InferredSchema},
+    layout::{self, FieldType, LOCALE_NAMES, Layout, Layouts},
+};
+
+/// A table with arrays and localized strings spread over one column per
+/// element and locale, as CSV and SQL need.
+#[derive(Debug, Clone)]
+pub struct FlatTable {
+    /// Table name such as `Spell`.
+    pub name: String,
+    pub columns: Vec<FlatColumn>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlatColumn {
+    /// `ItemID_3` for the fourth element of `ItemID`, `Name_lang_deDE` for a
+    /// locale of `Name_lang`.
+    pub name: String,
+    /// `Int`, `Float` or `String`.
+    pub ty: FieldType,
+}
+
+/// Layout a table is known by: its override, else its built-in layout, if
+/// it fits the file.
+pub fn known_layout(layouts: &Layouts, file_name: &str, dbc: &DbcFile) -> Option<Layout> {
+    layouts
+        .get(file_name)
+        .cloned()
+        .or_else(|| layout::builtin(layout::table_name(file_name)))
+        .filter(|known| {
+            known.record_size() == dbc.record_size && known.field_count() == dbc.field_count
+        })
+}
+
+/// Layout to read a table with: its [`known_layout`], else the guessed one.
+pub fn table_layout(
+    layouts: &Layouts,
+    file_name: &str,
+    dbc: &DbcFile,
+    force_infer: bool,
+) -> Result<(Layout, Option<InferredSchema>)> {
+    let table = layout::table_name(file_name);
+    if !force_infer {
+        if let Some(known) = known_layout(layouts, file_name, dbc) {
+            return Ok((known, None));
+        }
+        if layouts.get(file_name).is_some() || layout::builtin(table).is_some() {
+            println!("{file_name} doesn't match its layout, guessing its columns");
+        }
+    }
+    let schema = infer::infer(table, dbc)?;
+    Ok((schema.layout(), Some(schema)))
+}
+
+impl FlatTable {
+    pub fn new(layout: &Layout, records: &[Record]) -> Self {
+        let mut columns = Vec::new();
+        for field in &layout.fields {
+            let elements: Vec<String> = match field.count {
+                1 => vec![field.name.clone()],
+                count => (0..count)
+                    .map(|index| format!("{}_{index}", field.name))
+                    .collect(),
+            };
+            for element in elements {
+                match field.ty {
+                    FieldType::LocString => {
+                        columns.extend(LOCALE_NAMES.iter().map(|locale| FlatColumn {
+                            name: format!("{element}_{locale}"),
+                            ty: FieldType::String,
+                        }));
+                        columns.push(FlatColumn {
+                            name: format!("{element}_flags"),
+                            ty: FieldType::Int,
+                        });
+                    }
+                    ty => columns.push(FlatColumn { name: element, ty }),
+                }
+            }
+        }
+
+        let rows = records
+            .iter()
+            .map(|record| {
+                let mut row = Vec::with_capacity(columns.len());
+                for value in record {
+                    flatten(value, &mut row);
+                }
+                row
+            })
+            .collect();
+
+        Self {
+            name: layout.table.clone(),
+            columns,
+            rows,
+        }
+    }
+
+    /// Write the rows as an array of JSON objects.
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let rows: Vec<JsonRow> = self
+            .rows
+            .iter()
+            .map(|row| JsonRow {
+                columns: &self.columns,
+                values: row,
+            })
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&rows)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub fn write_csv(&self, path: &Path) -> Result<()> {
+        let mut writer =
+            csv::Writer::from_path(path).with_context(|| format!("writing {}", path.display()))?;
+        writer.write_record(self.columns.iter().map(|column| &column.name))?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(text_value))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Replace the SQLite table named after this table with its rows.
+    pub fn write_sqlite(&self, conn: &mut Connection) -> Result<()> {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let ty = match column.ty {
+                    FieldType::Int => "INTEGER",
+                    FieldType::Float => "REAL",
+                    FieldType::String | FieldType::LocString => "TEXT",
+                };
+                format!("{} {ty}", quote(&column.name))
+            })
+            .collect();
+        let placeholders = vec!["?"; self.columns.len()].join(", ");
+
+        let tx = conn.transaction()?;
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS {table}; CREATE TABLE {table} ({});",
+            columns.join(", "),
+            table = quote(&self.name)
+        ))?;
+        {
+            let mut insert = tx.prepare(&format!(
+                "INSERT INTO {} VALUES ({placeholders})",
+                quote(&self.name)
+            ))?;
+            for row in &self.rows {
+                insert.execute(params_from_iter(row.iter().map(sql_value)))?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// A row as a JSON object, keeping the column order.
+struct JsonRow<'a> {
+    columns: &'a [FlatColumn],
+    values: &'a [Value],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.values) {
+            map.serialize_entry(&column.name, &json_value(value))?;
+        }
+        map.end()
+    }
+}
+
+fn flatten(value: &Value, row: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                flatten(value, row);
+            }
+        }
+        Value::LocString(value) => {
+            for locale in 0..LOCALE_NAMES.len() {
+                let string = value.strings.get(locale).cloned().unwrap_or_default();
+                row.push(Value::String(string));
+            }
+            row.push(Value::Int(value.flags as i64));
+        }
+        value => row.push(value.clone()),
+    }
+}
+
+fn json_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Int(value) => JsonValue::from(*value),
+        // Through the shortest decimal form, so 0.1 doesn't become 0.10000000149
+        Value::Float(value) => {
+            JsonValue::from(value.to_string().parse::<f64>().unwrap_or_default())
+        }
+        value => JsonValue::from(text_value(value)),
+    }
+}
+
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Int(value) => SqlValue::Integer(*value),
+        Value::Float(value) => SqlValue::Real(value.to_string().parse().unwrap_or_default()),
+        value => SqlValue::Text(text_value(value)),
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dbc, parse::tests::loc_string};
+
+    /// ItemRandomSuffix file of a deDE client, through its built-in layout.
+    fn suffixes() -> DbcFile {
+        let layout = layout::builtin("ItemRandomSuffix").unwrap();
+        let record = vec![
+            Value::Int(5),
+            loc_string(3, "des Bären"),
+            Value::String("Bear".to_string()),
+            Value::Array(vec![Value::Int(2802), Value::Int(2803)]),
+            Value::Array(vec![Value::Int(6666), Value::Int(4444)]),
+        ];
+        DbcFile::parse(&dbc::write(&layout, &[record])).unwrap()
+    }
+
+    fn flat_suffixes() -> FlatTable {
+        let dbc = suffixes();
+        let (layout, schema) = table_layout(
+            &Layouts::default(),
+            "DBFilesClient\\ItemRandomSuffix.dbc",
+            &dbc,
+            false,
+        )
+        .unwrap();
+        assert!(schema.is_none());
+        FlatTable::new(&layout, &dbc.read(&layout).unwrap())
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn guesses_only_tables_without_a_fitting_layout() {
+        let dbc = suffixes();
+        let layouts = Layouts::default();
+        let (_, schema) =
+            table_layout(&layouts, "DBFilesClient\\ItemRandomSuffix.dbc", &dbc, true).unwrap();
+        assert!(schema.is_some());
+        // Neither a built-in layout nor an override
+        let (_, schema) =
+            table_layout(&layouts, "DBFilesClient\\EpochTransmog.dbc", &dbc, false).unwrap();
+        assert!(schema.is_some());
+        assert!(known_layout(&layouts, "DBFilesClient\\EpochTransmog.dbc", &dbc).is_none());
+        // A built-in layout of another size
+        assert!(known_layout(&layouts, "DBFilesClient\\ItemSet.dbc", &dbc).is_none());
+    }
+
+    #[test]
+    fn spreads_arrays_and_locales_over_columns() {
+        let table = flat_suffixes();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(table.name, "ItemRandomSuffix");
+        assert_eq!(names.len(), 1 + LOCALE_NAMES.len() + 1 + 1 + 5 + 5);
+        assert_eq!(names[..2], ["ID", "Name_lang_enUS"]);
+        assert_eq!(names[4], "Name_lang_deDE");
+        assert_eq!(
+            names[17..20],
+            ["Name_lang_flags", "InternalName", "Enchantment_0"]
+        );
+        assert_eq!(names[28], "AllocationPct_4");
+
+        let row = &table.rows[0];
+        assert_eq!(row.len(), names.len());
+        assert_eq!(row[1], Value::String(String::new()));
+        assert_eq!(row[4], Value::String("des Bären".to_string()));
+        // Arrays are padded to their length
+        assert_eq!(row[19..24], [2802, 2803, 0, 0, 0].map(Value::Int));
+    }
+
+    #[test]
+    fn writes_json_and_csv() {
+        let table = flat_suffixes();
+
+        let path = temp_path("dump.json");
+        table.write_json(&path).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let rows: Vec<serde_json::Map<String, JsonValue>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["ID"], 5);
+        assert_eq!(rows[0]["Name_lang_deDE"], "des Bären");
+        assert_eq!(rows[0]["Name_lang_flags"], 0);
+        assert_eq!(rows[0]["AllocationPct_1"], 4444);
+        // Keys keep the column order
+        assert!(json.find("\"ID\"").unwrap() < json.find("\"Name_lang_enUS\"").unwrap());
+
+        let path = temp_path("dump.csv");
+        table.write_csv(&path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[0].starts_with("ID,Name_lang_enUS,Name_lang_koKR,Name_lang_frFR,Name_lang_deDE,")
+        );
+        assert!(lines[0].ends_with(",AllocationPct_4"));
+        assert!(lines[1].starts_with("5,,,,des Bären,"));
+        assert!(lines[1].ends_with(",Bear,2802,2803,0,0,0,6666,4444,0,0,0"));
+    }
+
+    #[test]
+    fn writes_sqlite_tables() {
+        let table = flat_suffixes();
+        let mut conn = Connection::open_in_memory().unwrap();
+        // Replaces a table of an earlier dump
+        conn.execute_batch("CREATE TABLE ItemRandomSuffix (Old INTEGER)")
+            .unwrap();
+        table.write_sqlite(&mut conn).unwrap();
+
+        let columns: Vec<(String, String)> = conn
+            .prepare("SELECT name, type FROM pragma_table_info('ItemRandomSuffix')")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(columns.len(), table.columns.len());
+        assert_eq!(columns[0], ("ID".to_string(), "INTEGER".to_string()));
+        assert_eq!(
+            columns[4],
+            ("Name_lang_deDE".to_string(), "TEXT".to_string())
+        );
+
+        let (name, enchantment): (String, i64) = conn
+            .query_row(
+                "SELECT Name_lang_deDE, Enchantment_1 FROM ItemRandomSuffix WHERE ID = 5",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(name, "des Bären");
+        assert_eq!(enchantment, 2803);
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{
+    Deserialize,
+    de::{DeserializeOwned, IntoDeserializer, value},
+};
+use std::{fs, path::Path, str::FromStr};
+
+use crate::{
+    database::ItemDatabase,
+    models::{InventoryType, Item, ItemClass, Rarity},
+    utils::OriginalItemChecker,
+};
+
+/// Whether an item differs from the stock 3.3.5 `item_template`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemStatus {
+    /// Not in the stock item table.
+    New,
+    /// In the stock item table with different values.
+    Modified,
+    /// Same as the stock item.
+    Stock,
+}
+
+/// Inclusive item id range, written `100-200` or `100`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct IdRange {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl IdRange {
+    pub fn contains(&self, id: i32) -> bool {
+        (self.start..=self.end).contains(&id)
+    }
+}
+
+impl FromStr for IdRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let start = start
+            .trim()
+            .parse()
+            .with_context(|| format!("bad id range `{s}`"))?;
+        let end = end
+            .trim()
+            .parse()
+            .with_context(|| format!("bad id range `{s}`"))?;
+        if end < start {
+            bail!("id range `{s}` ends before it starts");
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl TryFrom<String> for IdRange {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+/// Criteria an item must all meet to be exported. Empty lists and `None`
+/// values don't restrict anything.
+///
+/// Names are written as in the exported JSON, e.g. `classes = ["Weapon"]`,
+/// `inventory_types = ["TwoHand"]` or `rarities = ["Epic"]`.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ItemFilter {
+    pub min_level: Option<u32>,
+    pub max_level: Option<u32>,
+    pub classes: Vec<ItemClass>,
+    /// Subclass names such as `Sword`, matched ignoring case.
+    pub subclasses: Vec<String>,
+    pub inventory_types: Vec<InventoryType>,
+    pub rarities: Vec<Rarity>,
+    pub ids: Vec<IdRange>,
+    /// `true` keeps only items with a real name, `false` only those without.
+    pub has_name: Option<bool>,
+    pub has_icon: Option<bool>,
+    pub has_set: Option<bool>,
+    /// Needs the stock `item_template` CSV.
+    pub status: Vec<ItemStatus>,
+}
+
+impl ItemFilter {
+    /// The filter used when nothing else is configured: new items up to
+    /// level 60.
+    pub fn epoch_default() -> Self {
+        Self {
+            max_level: Some(60),
+            status: vec![ItemStatus::New],
+            ..Default::default()
+        }
+    }
+
+    /// Read a filter from a TOML file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Replace every criterion that `other` sets.
+    pub fn overlay(mut self, other: ItemFilter) -> Self {
+        fn replace<T>(current: &mut Vec<T>, other: Vec<T>) {
+            if !other.is_empty() {
+                *current = other;
+            }
+        }
+
+        self.min_level = other.min_level.or(self.min_level);
+        self.max_level = other.max_level.or(self.max_level);
+        replace(&mut self.classes, other.classes);
+        replace(&mut self.subclasses, other.subclasses);
+        replace(&mut self.inventory_types, other.inventory_types);
+        replace(&mut self.rarities, other.rarities);
+        replace(&mut self.ids, other.ids);
+        self.has_name = other.has_name.or(self.has_name);
+        self.has_icon = other.has_icon.or(self.has_icon);
+        self.has_set = other.has_set.or(self.has_set);
+        replace(&mut self.status, other.status);
+        self
+    }
+
+    /// Whether the filter needs stock item data to decide.
+    pub fn needs_stock_items(&self) -> bool {
+        !self.status.is_empty()
+    }
+
+    /// Whether `item` meets all criteria. `status` is only consulted when the
+    /// filter restricts it.
+    pub fn matches(&self, item: &Item, status: Option<ItemStatus>) -> bool {
+        fn allowed<T: PartialEq>(list: &[T], value: &T) -> bool {
+            list.is_empty() || list.contains(value)
+        }
+        fn flag(wanted: Option<bool>, actual: bool) -> bool {
+            wanted.is_none_or(|wanted| wanted == actual)
+        }
+
+        self.min_level.is_none_or(|min| item.required_level >= min)
+            && self.max_level.is_none_or(|max| item.required_level <= max)
+            && allowed(&self.classes, &item.class)
+            && (self.subclasses.is_empty()
+                || self
+                    .subclasses
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(&item.subclass)))
+            && allowed(&self.inventory_types, &item.inventory_type)
+            && allowed(&self.rarities, &item.rarity)
+            && (self.ids.is_empty() || self.ids.iter().any(|range| range.contains(item.id)))
+            && flag(self.has_name, item.has_name())
+            && flag(self.has_icon, !item.inventory_icon.is_empty())
+            && flag(self.has_set, item.set.is_some())
+            && (self.status.is_empty() || status.is_some_and(|s| self.status.contains(&s)))
+    }
+
+    /// Drop every item of `database` that doesn't match.
+    pub fn apply(
+        &self,
+        database: &mut ItemDatabase,
+        stock: Option<&OriginalItemChecker>,
+    ) -> Result<()> {
+        if self.needs_stock_items() && stock.is_none() {
+            bail!("filtering by status needs the stock item_template CSV");
+        }
+        database.retain(|item| {
+            let status = stock.map(|stock| stock.status(item));
+            self.matches(item, status)
+        });
+        Ok(())
+    }
+}
+
+/// Parse a model enum by the name it's exported under, such as `Weapon`.
+pub fn parse_name<T: DeserializeOwned>(name: &str) -> Result<T> {
+    let deserializer: value::StrDeserializer<value::Error> = name.into_deserializer();
+    T::deserialize(deserializer).map_err(|err| anyhow!("{err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::tests::blank_row;
+
+    fn item(id: i32, name: &str, required_level: u32) -> Item {
+        let mut item = Item::from(&blank_row::<wow_dbc::wrath_tables::item::Item>(8));
+        item.id = id;
+        item.name = name.to_string();
+        item.required_level = required_level;
+        item
+    }
+
+    #[test]
+    fn overlay_replaces_only_the_criteria_it_sets() {
+        let base = ItemFilter {
+            min_level: Some(10),
+            rarities: vec![Rarity::Epic],
+            has_icon: Some(true),
+            ..ItemFilter::epoch_default()
+        };
+        let filter = base.clone().overlay(ItemFilter {
+            max_level: Some(80),
+            rarities: vec![Rarity::Rare, Rarity::Legendary],
+            has_icon: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(
+            filter,
+            ItemFilter {
+                min_level: Some(10),
+                max_level: Some(80),
+                rarities: vec![Rarity::Rare, Rarity::Legendary],
+                has_icon: Some(false),
+                status: vec![ItemStatus::New],
+                ..Default::default()
+            }
+        );
+        assert_eq!(base.clone().overlay(ItemFilter::default()), base);
+    }
+
+    #[test]
+    fn filters_by_stock_status() {
+        let path = std::env::temp_dir().join(format!("item_template-{}.csv", std::process::id()));
+        fs::write(
+            &path,
+            "entry,name,Quality,RequiredLevel\n1,Worn Sword,0,0\n2,Old Axe,1,5\n",
+        )
+        .unwrap();
+        let stock = OriginalItemChecker::new(&path);
+        fs::remove_file(&path).unwrap();
+        let stock = stock.unwrap();
+
+        let (unchanged, modified, new) = (
+            item(1, "Worn Sword", 0),
+            item(2, "Old Axe", 10),
+            item(3, "Shiny Mace", 0),
+        );
+        assert_eq!(stock.status(&unchanged), ItemStatus::Stock);
+        assert_eq!(stock.status(&modified), ItemStatus::Modified);
+        assert_eq!(stock.status(&new), ItemStatus::New);
+
+        let filter = ItemFilter {
+            status: vec![ItemStatus::New, ItemStatus::Modified],
+            ..Default::default()
+        };
+        let kept: Vec<i32> = [&unchanged, &modified, &new]
+            .into_iter()
+            .filter(|item| filter.matches(item, Some(stock.status(item))))
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(kept, [2, 3]);
+        assert!(!filter.matches(&new, None));
+        assert!(ItemFilter::default().matches(&new, None));
+
+        let err = filter
+            .apply(&mut ItemDatabase::default(), None)
+            .unwrap_err();
+        assert!(err.to_string().contains("stock item_template"), "{err}");
+    }
+}
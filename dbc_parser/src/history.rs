@@ -0,0 +1,330 @@
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::IconConfig,
+    diff::{ExportItems, ItemDiff},
+    layout::Layouts,
+    merge::MergePolicy,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS exports (
+    id INTEGER PRIMARY KEY,
+    build INTEGER NOT NULL,
+    source_hash TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    item_count INTEGER NOT NULL,
+    UNIQUE (build, source_hash)
+);
+-- Item JSON, stored once however many exports contain it
+CREATE TABLE IF NOT EXISTS item_data (
+    hash TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS export_items (
+    export_id INTEGER NOT NULL REFERENCES exports (id),
+    item_id INTEGER NOT NULL,
+    hash TEXT NOT NULL REFERENCES item_data (hash),
+    PRIMARY KEY (export_id, item_id)
+);
+CREATE INDEX IF NOT EXISTS export_items_item ON export_items (item_id);
+";
+
+/// Every recorded export, kept in an SQLite file.
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+/// One recorded export.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportRecord {
+    pub id: i64,
+    /// Client build number, such as `3466`.
+    pub build: u32,
+    /// SHA-256 of the MPQ archives, supplemental sources, merge policy,
+    /// table layouts, locales and icon links.
+    pub source_hash: String,
+    /// Unix time the export was recorded.
+    pub created_at: i64,
+    pub item_count: usize,
+}
+
+impl HistoryStore {
+    /// Open or create a history file.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("opening {}", path.display()))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Record the items of an export. Returns `None` if an export of `build`
+    /// with the same sources was already recorded.
+    pub fn record(
+        &mut self,
+        build: u32,
+        source_hash: &str,
+        items: &ExportItems,
+    ) -> Result<Option<ExportRecord>> {
+        let existing = self
+            .conn
+            .query_row(
+                "SELECT id FROM exports WHERE build = ?1 AND source_hash = ?2",
+                params![build, source_hash],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        if existing.is_some() {
+            return Ok(None);
+        }
+
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs() as i64)
+            .unwrap_or_default();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO exports (build, source_hash, created_at, item_count)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![build, source_hash, created_at, items.len()],
+        )?;
+        let id = tx.last_insert_rowid();
+        {
+            let mut insert_data =
+                tx.prepare("INSERT OR IGNORE INTO item_data (hash, data) VALUES (?1, ?2)")?;
+            let mut insert_item = tx.prepare(
+                "INSERT INTO export_items (export_id, item_id, hash) VALUES (?1, ?2, ?3)",
+            )?;
+            for (item_id, item) in items {
+                let data = serde_json::to_string(item)?;
+                let hash = hex_digest(Sha256::digest(data.as_bytes()).as_slice());
+                insert_data.execute(params![hash, data])?;
+                insert_item.execute(params![id, item_id, hash])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(Some(ExportRecord {
+            id,
+            build,
+            source_hash: source_hash.to_string(),
+            created_at,
+            item_count: items.len(),
+        }))
+    }
+
+    /// All exports, oldest build first.
+    pub fn exports(&self) -> Result<Vec<ExportRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, build, source_hash, created_at, item_count FROM exports
+             ORDER BY build, id",
+        )?;
+        let exports = stmt
+            .query_map([], export_record)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(exports)
+    }
+
+    /// The first export that contained item `item_id`.
+    pub fn first_seen(&self, item_id: i32) -> Result<Option<ExportRecord>> {
+        let export = self
+            .conn
+            .query_row(
+                "SELECT e.id, e.build, e.source_hash, e.created_at, e.item_count
+                 FROM exports e JOIN export_items i ON i.export_id = e.id
+                 WHERE i.item_id = ?1
+                 ORDER BY e.build, e.id LIMIT 1",
+                params![item_id],
+                export_record,
+            )
+            .optional()?;
+        Ok(export)
+    }
+
+    /// The latest export of `build` or an earlier build.
+    pub fn export_at(&self, build: u32) -> Result<Option<ExportRecord>> {
+        let export = self
+            .conn
+            .query_row(
+                "SELECT id, build, source_hash, created_at, item_count FROM exports
+                 WHERE build <= ?1 ORDER BY build DESC, id DESC LIMIT 1",
+                params![build],
+                export_record,
+            )
+            .optional()?;
+        Ok(export)
+    }
+
+    /// The most recent export.
+    pub fn latest(&self) -> Result<Option<ExportRecord>> {
+        self.export_at(u32::MAX)
+    }
+
+    /// Item `item_id` as it was exported in `build`, or the latest build
+    /// before it.
+    pub fn item_at(&self, item_id: i32, build: u32) -> Result<Option<Value>> {
+        let Some(export) = self.export_at(build)? else {
+            return Ok(None);
+        };
+        let data = self
+            .conn
+            .query_row(
+                "SELECT d.data FROM export_items i JOIN item_data d ON d.hash = i.hash
+                 WHERE i.export_id = ?1 AND i.item_id = ?2",
+                params![export.id, item_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        data.map(|data| Ok(serde_json::from_str(&data)?))
+            .transpose()
+    }
+
+    /// Every item of an export.
+    pub fn snapshot(&self, export_id: i64) -> Result<ExportItems> {
+        let mut stmt = self.conn.prepare(
+            "SELECT i.item_id, d.data FROM export_items i JOIN item_data d ON d.hash = i.hash
+             WHERE i.export_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![export_id], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut items = ExportItems::new();
+        for row in rows {
+            let (item_id, data) = row?;
+            items.insert(item_id, serde_json::from_str(&data)?);
+        }
+        Ok(items)
+    }
+
+    /// Items added, removed or changed between `build` and the most recent
+    /// export.
+    pub fn changed_since(&self, build: u32) -> Result<ItemDiff> {
+        let before = match self.export_at(build)? {
+            Some(export) => self.snapshot(export.id)?,
+            None => ExportItems::new(),
+        };
+        let after = match self.latest()? {
+            Some(export) => self.snapshot(export.id)?,
+            None => ExportItems::new(),
+        };
+        Ok(ItemDiff::between(&before, &after))
+    }
+}
+
+fn export_record(row: &rusqlite::Row) -> rusqlite::Result<ExportRecord> {
+    Ok(ExportRecord {
+        id: row.get(0)?,
+        build: row.get(1)?,
+        source_hash: row.get(2)?,
+        created_at: row.get(3)?,
+        item_count: row.get(4)?,
+    })
+}
+
+/// SHA-256 over the contents of `paths`, in order, and the merge policy,
+/// layout overrides, locales and icon links the items were built with.
+pub fn source_hash(
+    paths: &[PathBuf],
+    policy: &MergePolicy,
+    layouts: &Layouts,
+    locales: &[String],
+    icons: &IconConfig,
+) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for path in paths {
+        hasher.update(&*map_file(path)?);
+    }
+    hasher.update(serde_json::to_vec(policy)?);
+    for layout in layouts.iter() {
+        // Where a layout was read from doesn't change the items.
+        hasher.update(serde_json::to_vec(&(&layout.table, &layout.fields))?);
+    }
+    hasher.update(serde_json::to_vec(locales)?);
+    // Icon links as items get them, from the template or the exported files
+    hasher.update(icons.render("{icon}"));
+    Ok(hex_digest(hasher.finalize().as_slice()))
+}
+
+/// Map a file into memory, letting large archives be hashed without reading
+/// them into buffers first.
+fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    // SAFETY: client data is only read, a file changing underneath us could
+    // at worst produce a wrong hash.
+    let map = unsafe { Mmap::map(&file) }.with_context(|| format!("mapping {}", path.display()))?;
+    Ok(map)
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::IconExport, layout, merge::ConflictStrategy};
+
+    #[test]
+    fn source_hash_covers_everything_items_are_built_from() {
+        let policy = MergePolicy::default();
+        let layouts = Layouts::default();
+        let locales = ["enUS".to_string()];
+        let icons = IconConfig::default();
+        let hash = |policy, layouts, locales: &[String], icons| {
+            source_hash(&[], policy, layouts, locales, icons).unwrap()
+        };
+        let base = hash(&policy, &layouts, &locales, &icons);
+        assert_eq!(hash(&policy, &layouts, &locales, &icons), base);
+
+        let keep_both = MergePolicy {
+            conflict: ConflictStrategy::KeepBoth,
+            ..MergePolicy::default()
+        };
+        assert_ne!(hash(&keep_both, &layouts, &locales, &icons), base);
+
+        let mut overridden = Layouts::default();
+        let mut item = layout::builtin("Item").unwrap();
+        item.fields.pop();
+        overridden.insert(item);
+        assert_ne!(hash(&policy, &overridden, &locales, &icons), base);
+
+        let with_german = ["enUS".to_string(), "deDE".to_string()];
+        assert_ne!(hash(&policy, &layouts, &with_german, &icons), base);
+
+        let other_template = IconConfig {
+            template: "/icons/{icon}.png".to_string(),
+            ..IconConfig::default()
+        };
+        assert_ne!(hash(&policy, &layouts, &locales, &other_template), base);
+        let exported = IconConfig {
+            export: Some(IconExport::default()),
+            ..IconConfig::default()
+        };
+        assert_ne!(hash(&policy, &layouts, &locales, &exported), base);
+
+        // A build with another locale is recorded next to the first
+        let path = std::env::temp_dir().join(format!("history-{}.sqlite", std::process::id()));
+        let mut store = HistoryStore::open(&path).unwrap();
+        let items = ExportItems::new();
+        let recorded = [
+            store.record(3466, &base, &items).unwrap(),
+            store
+                .record(3466, &hash(&policy, &layouts, &with_german, &icons), &items)
+                .unwrap(),
+            store.record(3466, &base, &items).unwrap(),
+        ];
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+        assert!(recorded[0].is_some() && recorded[1].is_some());
+        assert_eq!(recorded[2], None);
+    }
+}
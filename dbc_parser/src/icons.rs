@@ -0,0 +1,379 @@
+use anyhow::{Context, Result};
+use image::{ImageFormat, RgbaImage, imageops::FilterType};
+use rayon::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+use crate::{
+    blp::BlpImage,
+    config::{IconExport, IconFormat},
+    mpq::ArchiveChain,
+};
+
+/// Directory of the icon textures inside the archives.
+const ICON_DIR: &str = "interface\\icons\\";
+
+/// What an icon export wrote and which icons it couldn't.
+#[derive(Debug, Clone, Default)]
+pub struct IconReport {
+    /// Icons written at every size.
+    pub written: usize,
+    /// Icons no archive contains.
+    pub missing: Vec<String>,
+    /// Icons that failed to read, decode or write, with the error.
+    pub failed: Vec<(String, String)>,
+    /// Atlases written, if packing them was configured.
+    pub atlas: Option<AtlasManifest>,
+}
+
+/// Where every icon of the sprite atlases of one size is, written as
+/// `manifest.json` next to them.
+#[derive(Serialize, Debug, Clone)]
+pub struct AtlasManifest {
+    /// Width and height of each icon.
+    pub size: u32,
+    /// Atlas paths as the site sees them, indexed by [`AtlasSlot::atlas`].
+    pub atlases: Vec<String>,
+    /// Slot of every icon by lowercase name. Icons with identical pixels
+    /// share a slot.
+    pub icons: BTreeMap<String, AtlasSlot>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AtlasSlot {
+    pub atlas: usize,
+    /// Offset of the icon's top left corner in pixels.
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Decode the `.blp` file of every icon in `names` out of `archives` and
+/// write it at each size of `export`, then pack the atlases if configured.
+pub fn export_icons(
+    archives: &[PathBuf],
+    names: &BTreeSet<String>,
+    export: &IconExport,
+) -> Result<IconReport> {
+    let mut chain = ArchiveChain::open(archives)?;
+    let files = chain.resolve(|name| {
+        let name = name.to_lowercase();
+        name.starts_with(ICON_DIR) && name.ends_with(".blp")
+    })?;
+
+    let mut report = IconReport::default();
+    let mut found = Vec::new();
+    for name in names {
+        match files.get(&format!("{ICON_DIR}{name}.blp")) {
+            Some(file) => found.push((name, file)),
+            None => report.missing.push(name.clone()),
+        }
+    }
+    for size in &export.sizes {
+        let dir = export.dir.join(size.to_string());
+        fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+
+    let files: Vec<_> = found.iter().map(|(_, file)| *file).collect();
+    let data = chain.read_each(&files);
+    let results: Vec<_> = found
+        .par_iter()
+        .zip(data)
+        .map(|((name, _), data)| (*name, data.and_then(|data| write_icon(name, &data, export))))
+        .collect();
+    let mut packed = Vec::new();
+    for (name, result) in results {
+        match result {
+            Ok(image) => {
+                report.written += 1;
+                packed.extend(image.map(|image| (name, image)));
+            }
+            Err(err) => report.failed.push((name.clone(), format!("{err:#}"))),
+        }
+    }
+
+    if let (Some(atlas), Some(size)) = (&export.atlas, export.atlas_size()) {
+        report.atlas = Some(write_atlases(&packed, size, atlas.max_side, export)?);
+    }
+    Ok(report)
+}
+
+/// Write `name` at every size, returning it at the atlas size if atlases
+/// are packed.
+fn write_icon(name: &str, data: &[u8], export: &IconExport) -> Result<Option<RgbaImage>> {
+    let blp = BlpImage::decode(data)?;
+    let image = RgbaImage::from_raw(blp.width, blp.height, blp.pixels)
+        .context("pixel data doesn't match the texture size")?;
+    let mut packed = None;
+    for &size in &export.sizes {
+        let path = export.file(size, name);
+        let resized = if image.dimensions() == (size, size) {
+            image.clone()
+        } else {
+            image::imageops::resize(&image, size, size, FilterType::Lanczos3)
+        };
+        resized
+            .save_with_format(&path, image_format(export.format))
+            .with_context(|| format!("writing {}", path.display()))?;
+        if export.atlas_size() == Some(size) {
+            packed = Some(resized);
+        }
+    }
+    Ok(packed)
+}
+
+/// Pack `icons` row by row into atlases of at most `max_side` pixels a side
+/// and write them with their manifest.
+fn write_atlases(
+    icons: &[(&String, RgbaImage)],
+    size: u32,
+    max_side: u32,
+    export: &IconExport,
+) -> Result<AtlasManifest> {
+    // One slot per distinct image
+    let mut slots: HashMap<[u8; 32], usize> = HashMap::new();
+    let mut unique: Vec<&RgbaImage> = Vec::new();
+    let mut names = Vec::with_capacity(icons.len());
+    for (name, image) in icons {
+        let hash: [u8; 32] = Sha256::digest(image.as_raw()).into();
+        let slot = *slots.entry(hash).or_insert_with(|| {
+            unique.push(image);
+            unique.len() - 1
+        });
+        names.push((*name, slot));
+    }
+
+    let columns = (max_side / size) as usize;
+    let per_atlas = columns * columns;
+    let slot_of = |slot: usize| AtlasSlot {
+        atlas: slot / per_atlas,
+        x: (slot % per_atlas % columns) as u32 * size,
+        y: (slot % per_atlas / columns) as u32 * size,
+    };
+
+    let dir = export.atlas_dir(size);
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let ext = export.format.extension();
+    let mut atlases = Vec::new();
+    for (index, chunk) in unique.chunks(per_atlas).enumerate() {
+        // The last atlas only as tall as its rows
+        let width = chunk.len().min(columns) as u32 * size;
+        let height = chunk.len().div_ceil(columns) as u32 * size;
+        let mut atlas = RgbaImage::new(width, height);
+        for (offset, image) in chunk.iter().enumerate() {
+            let slot = slot_of(index * per_atlas + offset);
+            image::imageops::replace(&mut atlas, *image, slot.x as i64, slot.y as i64);
+        }
+        let path = dir.join(format!("{index}.{ext}"));
+        atlas
+            .save_with_format(&path, image_format(export.format))
+            .with_context(|| format!("writing {}", path.display()))?;
+        atlases.push(format!(
+            "{}/atlas/{size}/{index}.{ext}",
+            export.url.trim_end_matches('/')
+        ));
+    }
+
+    let manifest = AtlasManifest {
+        size,
+        atlases,
+        icons: names
+            .into_iter()
+            .map(|(name, slot)| (name.clone(), slot_of(slot)))
+            .collect(),
+    };
+    let path = dir.join("manifest.json");
+    fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(manifest)
+}
+
+fn image_format(format: IconFormat) -> ImageFormat {
+    match format {
+        IconFormat::Png => ImageFormat::Png,
+        IconFormat::Webp => ImageFormat::WebP,
+    }
+}
+
+impl IconReport {
+    pub fn print_summary(&self) {
+        println!("Exported {} icons", self.written);
+        if !self.missing.is_empty() {
+            println!(
+                "  {} icons not found in any archive: {}",
+                self.missing.len(),
+                self.missing.join(", ")
+            );
+        }
+        for (name, error) in &self.failed {
+            println!("  {name}: {error}");
+        }
+        if let Some(atlas) = &self.atlas {
+            let distinct: BTreeSet<_> = atlas.icons.values().collect();
+            println!(
+                "Packed {} icons ({} distinct) into {} atlases of {}px icons",
+                atlas.icons.len(),
+                distinct.len(),
+                atlas.atlases.len(),
+                atlas.size
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blp::tests::blp;
+
+    #[test]
+    fn unreadable_icons_fail_alone() {
+        let dir = std::env::temp_dir().join(format!("icons-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("common.MPQ");
+        let pixels: Vec<u8> = [[0, 0, 0xff, 0xff]; 4].concat();
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(vec![7; 4096], "Interface\\Icons\\INV_Broken.blp")
+            .add_file_data(
+                blp((3, 8, 0), (2, 2), &[], &pixels),
+                "Interface\\Icons\\INV_Sword_04.blp",
+            )
+            .build(&path)
+            .unwrap();
+        // Break the compressed data of the first file
+        let mut archive = fs::read(&path).unwrap();
+        archive[0x20..0x30].fill(0xff);
+        fs::write(&path, archive).unwrap();
+
+        let names = ["inv_broken", "inv_missing", "inv_sword_04"]
+            .map(String::from)
+            .into();
+        let export = IconExport {
+            dir: dir.join("icons"),
+            sizes: vec![2],
+            ..Default::default()
+        };
+        let report = export_icons(std::slice::from_ref(&path), &names, &export);
+        let written = image::open(export.file(2, "inv_sword_04"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.written, 1);
+        assert_eq!(report.missing, ["inv_missing"]);
+        let [(name, error)] = &report.failed[..] else {
+            panic!("expected one failed icon, got {:?}", report.failed);
+        };
+        assert_eq!(name, "inv_broken");
+        assert!(error.starts_with("reading "), "{error}");
+        assert_eq!(
+            written.unwrap().to_rgba8().get_pixel(1, 1).0,
+            [0xff, 0, 0, 0xff]
+        );
+    }
+
+    #[test]
+    fn packs_distinct_icons_into_atlases() {
+        let dir = std::env::temp_dir().join(format!("atlases-{}", std::process::id()));
+        let export = IconExport {
+            dir: dir.clone(),
+            url: "/icons/".to_string(),
+            sizes: vec![2],
+            ..Default::default()
+        };
+        let solid = |shade: u8| RgbaImage::from_pixel(2, 2, image::Rgba([shade, 0, 0, 0xff]));
+        // 13 distinct images, the first two also under a second name
+        let names: Vec<String> = (0..13)
+            .map(|index| format!("icon_{index:02}"))
+            .chain(["copy_00".to_string(), "copy_01".to_string()])
+            .collect();
+        let icons: Vec<(&String, RgbaImage)> = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name, solid(index as u8 % 13 * 10)))
+            .collect();
+
+        // 3 icons to a row and 9 to an atlas
+        let manifest = write_atlases(&icons, 2, 7, &export);
+        let atlases: Vec<_> = (0..2)
+            .map(|index| {
+                image::open(dir.join(format!("atlas/2/{index}.png"))).map(|a| a.to_rgba8())
+            })
+            .collect();
+        let written = fs::read_to_string(dir.join("atlas/2/manifest.json"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let manifest = manifest.unwrap();
+        assert_eq!(manifest.size, 2);
+        assert_eq!(
+            manifest.atlases,
+            ["/icons/atlas/2/0.png", "/icons/atlas/2/1.png"]
+        );
+        assert_eq!(manifest.icons.len(), 15);
+        let slot = |name: &str| manifest.icons[name];
+        assert_eq!(
+            slot("icon_00"),
+            AtlasSlot {
+                atlas: 0,
+                x: 0,
+                y: 0
+            }
+        );
+        assert_eq!(
+            slot("icon_04"),
+            AtlasSlot {
+                atlas: 0,
+                x: 2,
+                y: 2
+            }
+        );
+        assert_eq!(
+            slot("icon_08"),
+            AtlasSlot {
+                atlas: 0,
+                x: 4,
+                y: 4
+            }
+        );
+        assert_eq!(
+            slot("icon_09"),
+            AtlasSlot {
+                atlas: 1,
+                x: 0,
+                y: 0
+            }
+        );
+        assert_eq!(
+            slot("icon_12"),
+            AtlasSlot {
+                atlas: 1,
+                x: 0,
+                y: 2
+            }
+        );
+        // Identical pixels share a slot
+        assert_eq!(slot("copy_00"), slot("icon_00"));
+        assert_eq!(slot("copy_01"), slot("icon_01"));
+
+        let [first, last] = &atlases[..] else {
+            unreachable!()
+        };
+        let (first, last) = (first.as_ref().unwrap(), last.as_ref().unwrap());
+        assert_eq!(first.dimensions(), (6, 6));
+        // The last atlas is only as tall as its two rows
+        assert_eq!(last.dimensions(), (6, 4));
+        assert_eq!(first.get_pixel(3, 3).0, [40, 0, 0, 0xff]);
+        assert_eq!(last.get_pixel(1, 3).0, [120, 0, 0, 0xff]);
+        assert_eq!(last.get_pixel(3, 3).0, [0; 4]);
+
+        let written: serde_json::Value = serde_json::from_str(&written.unwrap()).unwrap();
+        assert_eq!(
+            written["icons"]["icon_12"],
+            serde_json::json!({"atlas": 1, "x": 0, "y": 2})
+        );
+        assert_eq!(written["atlases"][1], "/icons/atlas/2/1.png");
+    }
+}
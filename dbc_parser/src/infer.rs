@@ -0,0 +1,331 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::{
+    dbc::DbcFile,
+    layout::{Field, FieldType, LOCALES, Layout},
+};
+
+/// Share of a column's non-zero values that must look like floats.
+const FLOAT_SHARE: f32 = 0.9;
+
+/// Distinct single bits a column needs before it's taken for a bitmask, as
+/// `1` and `2` are as likely an enum.
+const MIN_BITMASK_BITS: usize = 3;
+
+/// Best guess at the type of a column of a table without a known layout.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnGuess {
+    Int,
+    Float,
+    /// Offset into the string block.
+    String,
+    /// 16 string offsets and a flags field.
+    LocString,
+    /// Integer whose values are single bits.
+    Bitmask,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct InferredColumn {
+    pub name: String,
+    pub guess: ColumnGuess,
+    /// First field of the column, a localized string spans 17.
+    pub field: usize,
+    /// Distinct values of the (first) field.
+    pub distinct: usize,
+    /// Zero in every record, so the guess says nothing.
+    pub all_zero: bool,
+}
+
+/// Columns guessed from the header and records of a `.dbc` file.
+#[derive(Serialize, Debug, Clone)]
+pub struct InferredSchema {
+    pub table: String,
+    pub columns: Vec<InferredColumn>,
+}
+
+/// What the values of one 4-byte field look like.
+struct FieldStats {
+    guess: ColumnGuess,
+    distinct: usize,
+    all_zero: bool,
+}
+
+/// Guess the columns of `file`. Only tables of 4-byte fields, as every 3.3.5
+/// table is, can be inferred.
+pub fn infer(table: &str, file: &DbcFile) -> Result<InferredSchema> {
+    if file.record_size != file.field_count * 4 {
+        bail!(
+            "{table} has {} fields in {} byte records, only 4-byte fields can be inferred",
+            file.field_count,
+            file.record_size
+        );
+    }
+
+    let stats: Vec<FieldStats> = (0..file.field_count)
+        .map(|field| field_stats(file, field))
+        .collect();
+
+    let mut columns = Vec::new();
+    let mut field = 0;
+    while field < stats.len() {
+        let stat = &stats[field];
+        let name = match field {
+            0 if stat.guess == ColumnGuess::Int && stat.distinct == file.record_count => {
+                "ID".to_string()
+            }
+            _ => format!("field_{field}"),
+        };
+        if is_loc_string(&stats[field..]) {
+            columns.push(InferredColumn {
+                name: format!("{name}_lang"),
+                guess: ColumnGuess::LocString,
+                field,
+                distinct: stat.distinct,
+                all_zero: false,
+            });
+            field += LOCALES + 1;
+            continue;
+        }
+        columns.push(InferredColumn {
+            name,
+            guess: stat.guess,
+            field,
+            distinct: stat.distinct,
+            all_zero: stat.all_zero,
+        });
+        field += 1;
+    }
+
+    Ok(InferredSchema {
+        table: table.to_string(),
+        columns,
+    })
+}
+
+fn field_stats(file: &DbcFile, field: usize) -> FieldStats {
+    let values: Vec<u32> = (0..file.record_count)
+        .map(|record| file.cell(record, field))
+        .collect();
+    let distinct = values.iter().collect::<HashSet<_>>().len();
+    let non_zero: Vec<u32> = values.iter().copied().filter(|&v| v != 0).collect();
+    let distinct_non_zero = non_zero.iter().collect::<HashSet<_>>().len();
+
+    let guess = if non_zero.is_empty() {
+        ColumnGuess::Int
+    } else if looks_like_strings(file, &non_zero, distinct_non_zero) {
+        ColumnGuess::String
+    } else if distinct_non_zero >= MIN_BITMASK_BITS && non_zero.iter().all(|v| v.is_power_of_two())
+    {
+        ColumnGuess::Bitmask
+    } else if non_zero.iter().filter(|&&v| looks_like_float(v)).count() as f32
+        >= non_zero.len() as f32 * FLOAT_SHARE
+    {
+        ColumnGuess::Float
+    } else {
+        ColumnGuess::Int
+    };
+
+    FieldStats {
+        guess,
+        distinct,
+        all_zero: non_zero.is_empty(),
+    }
+}
+
+/// Every value starts a string of the string block. Offset `1` always does,
+/// so a column of only `0` and `1` is taken for a flag instead.
+fn looks_like_strings(file: &DbcFile, non_zero: &[u32], distinct: usize) -> bool {
+    file.string_block_size() > 1
+        && (distinct >= 2 || non_zero.iter().all(|&v| v > 1))
+        && non_zero.iter().all(|&offset| {
+            file.is_string_start(offset)
+                && file
+                    .string(offset)
+                    .is_ok_and(|s| !s.is_empty() && !s.chars().any(char::is_control))
+        })
+}
+
+/// A float of a plausible game value, whose bits as an integer would be
+/// implausibly large.
+fn looks_like_float(bits: u32) -> bool {
+    let value = f32::from_bits(bits).abs();
+    value.is_finite() && (1e-5..1e7).contains(&value) && (bits as i32).unsigned_abs() > 1 << 20
+}
+
+/// A string field, 15 more string or empty fields, then a flags field.
+fn is_loc_string(stats: &[FieldStats]) -> bool {
+    stats.len() > LOCALES
+        && stats[0].guess == ColumnGuess::String
+        && stats[1..LOCALES]
+            .iter()
+            .all(|stat| stat.guess == ColumnGuess::String || stat.all_zero)
+        && stats[LOCALES].guess != ColumnGuess::String
+}
+
+impl InferredSchema {
+    /// Layout reading the table with the guessed types.
+    pub fn layout(&self) -> Layout {
+        let fields = self
+            .columns
+            .iter()
+            .map(|column| Field {
+                name: column.name.clone(),
+                ty: match column.guess {
+                    ColumnGuess::Int | ColumnGuess::Bitmask => FieldType::Int,
+                    ColumnGuess::Float => FieldType::Float,
+                    ColumnGuess::String => FieldType::String,
+                    ColumnGuess::LocString => FieldType::LocString,
+                },
+                size: 32,
+                signed: column.guess != ColumnGuess::Bitmask,
+                count: 1,
+                is_id: column.name == "ID",
+            })
+            .collect();
+        Layout {
+            table: self.table.clone(),
+            fields,
+            path: None,
+        }
+    }
+
+    /// The guessed layout as a `.dbd` definition for `build`, to be renamed
+    /// and corrected by hand.
+    pub fn to_dbd(&self, build: &str) -> String {
+        let mut lines = vec!["COLUMNS".to_string()];
+        for column in &self.columns {
+            let ty = match column.guess {
+                ColumnGuess::Int | ColumnGuess::Bitmask => "int",
+                ColumnGuess::Float => "float",
+                ColumnGuess::String => "string",
+                ColumnGuess::LocString => "locstring",
+            };
+            lines.push(format!("{ty} {}?", column.name));
+        }
+        lines.push(String::new());
+        lines.push(format!("BUILD {build}"));
+        for column in &self.columns {
+            let mut line = match column.guess {
+                ColumnGuess::Int if column.name == "ID" => "$id$ID<32>".to_string(),
+                ColumnGuess::Int => format!("{}<32>", column.name),
+                ColumnGuess::Bitmask => format!("{}<u32>", column.name),
+                _ => column.name.clone(),
+            };
+            if column.guess == ColumnGuess::Bitmask {
+                line.push_str(" // bitmask");
+            } else if column.all_zero {
+                line.push_str(" // always 0");
+            }
+            lines.push(line);
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dbc::{self, LocString, Value},
+        layout::Definition,
+    };
+
+    const DEFINITION: &str = "
+COLUMNS
+int ID
+float Speed
+string Icon
+int Flags
+locstring Name_lang
+int Count
+int Kind
+
+BUILD 3.3.5.12340
+$id$ID<32>
+Speed
+Icon
+Flags<u32>
+Name_lang
+Count<32>
+Kind<32>
+";
+
+    fn sample(layout: &Layout) -> DbcFile {
+        let records: Vec<_> = [
+            (1.5, "INV_Sword_01", "Sword", 1, 3, 1),
+            (2.25, "INV_Axe_01", "Axe", 2, 7, 2),
+            (0.75, "INV_Mace_01", "Mace", 4, 12, 1),
+            (3.0, "", "Dagger", 8, 100, 2),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (speed, icon, name, flags, count, kind))| {
+            let mut strings = vec![String::new(); LOCALES];
+            strings[0] = name.to_string();
+            vec![
+                Value::Int(id as i64 + 1),
+                Value::Float(speed),
+                Value::String(icon.to_string()),
+                Value::Int(flags),
+                Value::LocString(LocString { strings, flags: 0 }),
+                Value::Int(count),
+                Value::Int(kind),
+            ]
+        })
+        .collect();
+        DbcFile::parse(&dbc::write(layout, &records)).unwrap()
+    }
+
+    #[test]
+    fn guesses_column_types() {
+        let layout = Definition::parse("Sample", DEFINITION)
+            .unwrap()
+            .layout(None)
+            .unwrap();
+        let dbc = sample(&layout);
+        let schema = infer("Sample", &dbc).unwrap();
+        let columns: Vec<_> = schema
+            .columns
+            .iter()
+            .map(|column| (column.field, column.name.as_str(), column.guess))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                (0, "ID", ColumnGuess::Int),
+                (1, "field_1", ColumnGuess::Float),
+                (2, "field_2", ColumnGuess::String),
+                (3, "field_3", ColumnGuess::Bitmask),
+                (4, "field_4_lang", ColumnGuess::LocString),
+                (21, "field_21", ColumnGuess::Int),
+                // Two distinct bits are as likely an enum
+                (22, "field_22", ColumnGuess::Int),
+            ]
+        );
+
+        // The guessed layout reads the same values back
+        let guessed = schema.layout();
+        assert_eq!(guessed.field_count(), layout.field_count());
+        assert_eq!(dbc.read(&guessed).unwrap(), dbc.read(&layout).unwrap());
+    }
+
+    #[test]
+    fn only_infers_four_byte_fields() {
+        let layout = Definition::parse(
+            "Packed",
+            "COLUMNS\nint ID\nint Kind\n\nBUILD 3.3.5.12340\n$id$ID<32>\nKind<u8>",
+        )
+        .unwrap()
+        .layout(None)
+        .unwrap();
+        let dbc =
+            DbcFile::parse(&dbc::write(&layout, &[vec![Value::Int(1), Value::Int(2)]])).unwrap();
+        let err = infer("Packed", &dbc).unwrap_err();
+        assert!(err.to_string().contains("only 4-byte fields"), "{err}");
+    }
+}
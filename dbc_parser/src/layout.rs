@@ -0,0 +1,1416 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::dbc::{self, DbcFile, LocString, Record, Value};
+
+/// Strings per localized string in 3.3.5 tables, followed by a flags field.
+pub const LOCALES: usize = 16;
+
+/// Build of the layouts `wow_dbc` reads.
+pub const WRATH_BUILD: u32 = 12340;
+
+/// Layouts of every table `wow_dbc` reads, written from its definitions.
+/// They are the target when a table is read through an override and what
+/// tables are dumped with.
+const BUILTIN: &[(&str, &str)] = &[
+    ("Achievement", include_str!("../layouts/Achievement.dbd")),
+    (
+        "Achievement_Category",
+        include_str!("../layouts/Achievement_Category.dbd"),
+    ),
+    (
+        "Achievement_Criteria",
+        include_str!("../layouts/Achievement_Criteria.dbd"),
+    ),
+    (
+        "AnimationData",
+        include_str!("../layouts/AnimationData.dbd"),
+    ),
+    ("AreaGroup", include_str!("../layouts/AreaGroup.dbd")),
+    ("AreaPOI", include_str!("../layouts/AreaPOI.dbd")),
+    ("AreaTable", include_str!("../layouts/AreaTable.dbd")),
+    ("AreaTrigger", include_str!("../layouts/AreaTrigger.dbd")),
+    (
+        "AttackAnimKits",
+        include_str!("../layouts/AttackAnimKits.dbd"),
+    ),
+    (
+        "AttackAnimTypes",
+        include_str!("../layouts/AttackAnimTypes.dbd"),
+    ),
+    ("AuctionHouse", include_str!("../layouts/AuctionHouse.dbd")),
+    (
+        "BankBagSlotPrices",
+        include_str!("../layouts/BankBagSlotPrices.dbd"),
+    ),
+    ("BannedAddOns", include_str!("../layouts/BannedAddOns.dbd")),
+    (
+        "BarberShopStyle",
+        include_str!("../layouts/BarberShopStyle.dbd"),
+    ),
+    (
+        "BattlemasterList",
+        include_str!("../layouts/BattlemasterList.dbd"),
+    ),
+    ("CameraShakes", include_str!("../layouts/CameraShakes.dbd")),
+    (
+        "Cfg_Categories",
+        include_str!("../layouts/Cfg_Categories.dbd"),
+    ),
+    ("Cfg_Configs", include_str!("../layouts/Cfg_Configs.dbd")),
+    (
+        "CharacterFacialHairStyles",
+        include_str!("../layouts/CharacterFacialHairStyles.dbd"),
+    ),
+    ("CharBaseInfo", include_str!("../layouts/CharBaseInfo.dbd")),
+    (
+        "CharHairGeosets",
+        include_str!("../layouts/CharHairGeosets.dbd"),
+    ),
+    (
+        "CharHairTextures",
+        include_str!("../layouts/CharHairTextures.dbd"),
+    ),
+    ("CharSections", include_str!("../layouts/CharSections.dbd")),
+    (
+        "CharStartOutfit",
+        include_str!("../layouts/CharStartOutfit.dbd"),
+    ),
+    ("CharTitles", include_str!("../layouts/CharTitles.dbd")),
+    (
+        "CharVariations",
+        include_str!("../layouts/CharVariations.dbd"),
+    ),
+    ("ChatChannels", include_str!("../layouts/ChatChannels.dbd")),
+    (
+        "ChatProfanity",
+        include_str!("../layouts/ChatProfanity.dbd"),
+    ),
+    ("ChrClasses", include_str!("../layouts/ChrClasses.dbd")),
+    ("ChrRaces", include_str!("../layouts/ChrRaces.dbd")),
+    (
+        "CinematicCamera",
+        include_str!("../layouts/CinematicCamera.dbd"),
+    ),
+    (
+        "CinematicSequences",
+        include_str!("../layouts/CinematicSequences.dbd"),
+    ),
+    (
+        "CreatureDisplayInfo",
+        include_str!("../layouts/CreatureDisplayInfo.dbd"),
+    ),
+    (
+        "CreatureDisplayInfoExtra",
+        include_str!("../layouts/CreatureDisplayInfoExtra.dbd"),
+    ),
+    (
+        "CreatureFamily",
+        include_str!("../layouts/CreatureFamily.dbd"),
+    ),
+    (
+        "CreatureModelData",
+        include_str!("../layouts/CreatureModelData.dbd"),
+    ),
+    (
+        "CreatureMovementInfo",
+        include_str!("../layouts/CreatureMovementInfo.dbd"),
+    ),
+    (
+        "CreatureSoundData",
+        include_str!("../layouts/CreatureSoundData.dbd"),
+    ),
+    (
+        "CreatureSpellData",
+        include_str!("../layouts/CreatureSpellData.dbd"),
+    ),
+    ("CreatureType", include_str!("../layouts/CreatureType.dbd")),
+    (
+        "CurrencyCategory",
+        include_str!("../layouts/CurrencyCategory.dbd"),
+    ),
+    (
+        "CurrencyTypes",
+        include_str!("../layouts/CurrencyTypes.dbd"),
+    ),
+    ("DanceMoves", include_str!("../layouts/DanceMoves.dbd")),
+    (
+        "DeathThudLookups",
+        include_str!("../layouts/DeathThudLookups.dbd"),
+    ),
+    ("DeclinedWord", include_str!("../layouts/DeclinedWord.dbd")),
+    (
+        "DeclinedWordCases",
+        include_str!("../layouts/DeclinedWordCases.dbd"),
+    ),
+    (
+        "DestructibleModelData",
+        include_str!("../layouts/DestructibleModelData.dbd"),
+    ),
+    (
+        "DungeonEncounter",
+        include_str!("../layouts/DungeonEncounter.dbd"),
+    ),
+    ("DungeonMap", include_str!("../layouts/DungeonMap.dbd")),
+    (
+        "DungeonMapChunk",
+        include_str!("../layouts/DungeonMapChunk.dbd"),
+    ),
+    (
+        "DurabilityCosts",
+        include_str!("../layouts/DurabilityCosts.dbd"),
+    ),
+    (
+        "DurabilityQuality",
+        include_str!("../layouts/DurabilityQuality.dbd"),
+    ),
+    ("Emotes", include_str!("../layouts/Emotes.dbd")),
+    ("EmotesText", include_str!("../layouts/EmotesText.dbd")),
+    (
+        "EmotesTextData",
+        include_str!("../layouts/EmotesTextData.dbd"),
+    ),
+    (
+        "EmotesTextSound",
+        include_str!("../layouts/EmotesTextSound.dbd"),
+    ),
+    (
+        "EnvironmentalDamage",
+        include_str!("../layouts/EnvironmentalDamage.dbd"),
+    ),
+    ("Exhaustion", include_str!("../layouts/Exhaustion.dbd")),
+    ("Faction", include_str!("../layouts/Faction.dbd")),
+    ("FactionGroup", include_str!("../layouts/FactionGroup.dbd")),
+    (
+        "FactionTemplate",
+        include_str!("../layouts/FactionTemplate.dbd"),
+    ),
+    ("FileData", include_str!("../layouts/FileData.dbd")),
+    (
+        "FootprintTextures",
+        include_str!("../layouts/FootprintTextures.dbd"),
+    ),
+    (
+        "FootstepTerrainLookup",
+        include_str!("../layouts/FootstepTerrainLookup.dbd"),
+    ),
+    (
+        "GameObjectArtKit",
+        include_str!("../layouts/GameObjectArtKit.dbd"),
+    ),
+    (
+        "GameObjectDisplayInfo",
+        include_str!("../layouts/GameObjectDisplayInfo.dbd"),
+    ),
+    ("GameTables", include_str!("../layouts/GameTables.dbd")),
+    ("GameTips", include_str!("../layouts/GameTips.dbd")),
+    (
+        "GemProperties",
+        include_str!("../layouts/GemProperties.dbd"),
+    ),
+    (
+        "GlyphProperties",
+        include_str!("../layouts/GlyphProperties.dbd"),
+    ),
+    ("GlyphSlot", include_str!("../layouts/GlyphSlot.dbd")),
+    (
+        "GMSurveyAnswers",
+        include_str!("../layouts/GMSurveyAnswers.dbd"),
+    ),
+    (
+        "GMSurveyCurrentSurvey",
+        include_str!("../layouts/GMSurveyCurrentSurvey.dbd"),
+    ),
+    (
+        "GMSurveyQuestions",
+        include_str!("../layouts/GMSurveyQuestions.dbd"),
+    ),
+    (
+        "GMSurveySurveys",
+        include_str!("../layouts/GMSurveySurveys.dbd"),
+    ),
+    (
+        "GMTicketCategory",
+        include_str!("../layouts/GMTicketCategory.dbd"),
+    ),
+    (
+        "GroundEffectDoodad",
+        include_str!("../layouts/GroundEffectDoodad.dbd"),
+    ),
+    (
+        "GroundEffectTexture",
+        include_str!("../layouts/GroundEffectTexture.dbd"),
+    ),
+    (
+        "gtBarberShopCostBase",
+        include_str!("../layouts/gtBarberShopCostBase.dbd"),
+    ),
+    (
+        "gtChanceToMeleeCrit",
+        include_str!("../layouts/gtChanceToMeleeCrit.dbd"),
+    ),
+    (
+        "gtChanceToMeleeCritBase",
+        include_str!("../layouts/gtChanceToMeleeCritBase.dbd"),
+    ),
+    (
+        "gtChanceToSpellCrit",
+        include_str!("../layouts/gtChanceToSpellCrit.dbd"),
+    ),
+    (
+        "gtChanceToSpellCritBase",
+        include_str!("../layouts/gtChanceToSpellCritBase.dbd"),
+    ),
+    (
+        "gtCombatRatings",
+        include_str!("../layouts/gtCombatRatings.dbd"),
+    ),
+    (
+        "gtNPCManaCostScaler",
+        include_str!("../layouts/gtNPCManaCostScaler.dbd"),
+    ),
+    (
+        "gtOCTClassCombatRatingScalar",
+        include_str!("../layouts/gtOCTClassCombatRatingScalar.dbd"),
+    ),
+    ("gtOCTRegenHP", include_str!("../layouts/gtOCTRegenHP.dbd")),
+    ("gtOCTRegenMP", include_str!("../layouts/gtOCTRegenMP.dbd")),
+    (
+        "gtRegenHPPerSpt",
+        include_str!("../layouts/gtRegenHPPerSpt.dbd"),
+    ),
+    (
+        "gtRegenMPPerSpt",
+        include_str!("../layouts/gtRegenMPPerSpt.dbd"),
+    ),
+    (
+        "HelmetGeosetVisData",
+        include_str!("../layouts/HelmetGeosetVisData.dbd"),
+    ),
+    (
+        "HolidayDescriptions",
+        include_str!("../layouts/HolidayDescriptions.dbd"),
+    ),
+    ("HolidayNames", include_str!("../layouts/HolidayNames.dbd")),
+    ("Holidays", include_str!("../layouts/Holidays.dbd")),
+    ("Item", include_str!("../layouts/Item.dbd")),
+    (
+        "ItemBagFamily",
+        include_str!("../layouts/ItemBagFamily.dbd"),
+    ),
+    ("ItemClass", include_str!("../layouts/ItemClass.dbd")),
+    (
+        "ItemCondExtCosts",
+        include_str!("../layouts/ItemCondExtCosts.dbd"),
+    ),
+    (
+        "ItemDisplayInfo",
+        include_str!("../layouts/ItemDisplayInfo.dbd"),
+    ),
+    (
+        "ItemExtendedCost",
+        include_str!("../layouts/ItemExtendedCost.dbd"),
+    ),
+    (
+        "ItemGroupSounds",
+        include_str!("../layouts/ItemGroupSounds.dbd"),
+    ),
+    (
+        "ItemLimitCategory",
+        include_str!("../layouts/ItemLimitCategory.dbd"),
+    ),
+    ("ItemPetFood", include_str!("../layouts/ItemPetFood.dbd")),
+    (
+        "ItemPurchaseGroup",
+        include_str!("../layouts/ItemPurchaseGroup.dbd"),
+    ),
+    (
+        "ItemRandomProperties",
+        include_str!("../layouts/ItemRandomProperties.dbd"),
+    ),
+    (
+        "ItemRandomSuffix",
+        include_str!("../layouts/ItemRandomSuffix.dbd"),
+    ),
+    ("ItemSet", include_str!("../layouts/ItemSet.dbd")),
+    ("ItemSubClass", include_str!("../layouts/ItemSubClass.dbd")),
+    (
+        "ItemSubClassMask",
+        include_str!("../layouts/ItemSubClassMask.dbd"),
+    ),
+    (
+        "ItemVisualEffects",
+        include_str!("../layouts/ItemVisualEffects.dbd"),
+    ),
+    ("ItemVisuals", include_str!("../layouts/ItemVisuals.dbd")),
+    ("Languages", include_str!("../layouts/Languages.dbd")),
+    (
+        "LanguageWords",
+        include_str!("../layouts/LanguageWords.dbd"),
+    ),
+    (
+        "LFGDungeonExpansion",
+        include_str!("../layouts/LFGDungeonExpansion.dbd"),
+    ),
+    (
+        "LFGDungeonGroup",
+        include_str!("../layouts/LFGDungeonGroup.dbd"),
+    ),
+    ("LFGDungeons", include_str!("../layouts/LFGDungeons.dbd")),
+    ("Light", include_str!("../layouts/Light.dbd")),
+    (
+        "LightFloatBand",
+        include_str!("../layouts/LightFloatBand.dbd"),
+    ),
+    ("LightIntBand", include_str!("../layouts/LightIntBand.dbd")),
+    ("LightParams", include_str!("../layouts/LightParams.dbd")),
+    ("LightSkybox", include_str!("../layouts/LightSkybox.dbd")),
+    (
+        "LiquidMaterial",
+        include_str!("../layouts/LiquidMaterial.dbd"),
+    ),
+    ("LiquidType", include_str!("../layouts/LiquidType.dbd")),
+    (
+        "LoadingScreens",
+        include_str!("../layouts/LoadingScreens.dbd"),
+    ),
+    (
+        "LoadingScreenTaxiSplines",
+        include_str!("../layouts/LoadingScreenTaxiSplines.dbd"),
+    ),
+    ("Lock", include_str!("../layouts/Lock.dbd")),
+    ("LockType", include_str!("../layouts/LockType.dbd")),
+    ("MailTemplate", include_str!("../layouts/MailTemplate.dbd")),
+    ("Map", include_str!("../layouts/Map.dbd")),
+    (
+        "MapDifficulty",
+        include_str!("../layouts/MapDifficulty.dbd"),
+    ),
+    ("Material", include_str!("../layouts/Material.dbd")),
+    ("Movie", include_str!("../layouts/Movie.dbd")),
+    (
+        "MovieFileData",
+        include_str!("../layouts/MovieFileData.dbd"),
+    ),
+    (
+        "MovieVariation",
+        include_str!("../layouts/MovieVariation.dbd"),
+    ),
+    ("NameGen", include_str!("../layouts/NameGen.dbd")),
+    (
+        "NamesProfanity",
+        include_str!("../layouts/NamesProfanity.dbd"),
+    ),
+    (
+        "NamesReserved",
+        include_str!("../layouts/NamesReserved.dbd"),
+    ),
+    ("NPCSounds", include_str!("../layouts/NPCSounds.dbd")),
+    ("ObjectEffect", include_str!("../layouts/ObjectEffect.dbd")),
+    (
+        "ObjectEffectGroup",
+        include_str!("../layouts/ObjectEffectGroup.dbd"),
+    ),
+    (
+        "ObjectEffectModifier",
+        include_str!("../layouts/ObjectEffectModifier.dbd"),
+    ),
+    (
+        "ObjectEffectPackage",
+        include_str!("../layouts/ObjectEffectPackage.dbd"),
+    ),
+    (
+        "ObjectEffectPackageElem",
+        include_str!("../layouts/ObjectEffectPackageElem.dbd"),
+    ),
+    (
+        "OverrideSpellData",
+        include_str!("../layouts/OverrideSpellData.dbd"),
+    ),
+    ("Package", include_str!("../layouts/Package.dbd")),
+    (
+        "PageTextMaterial",
+        include_str!("../layouts/PageTextMaterial.dbd"),
+    ),
+    (
+        "PaperDollItemFrame",
+        include_str!("../layouts/PaperDollItemFrame.dbd"),
+    ),
+    (
+        "ParticleColor",
+        include_str!("../layouts/ParticleColor.dbd"),
+    ),
+    ("PetitionType", include_str!("../layouts/PetitionType.dbd")),
+    (
+        "PetPersonality",
+        include_str!("../layouts/PetPersonality.dbd"),
+    ),
+    ("PowerDisplay", include_str!("../layouts/PowerDisplay.dbd")),
+    (
+        "PvpDifficulty",
+        include_str!("../layouts/PvpDifficulty.dbd"),
+    ),
+    (
+        "QuestFactionReward",
+        include_str!("../layouts/QuestFactionReward.dbd"),
+    ),
+    ("QuestInfo", include_str!("../layouts/QuestInfo.dbd")),
+    ("QuestSort", include_str!("../layouts/QuestSort.dbd")),
+    ("QuestXP", include_str!("../layouts/QuestXP.dbd")),
+    (
+        "RandPropPoints",
+        include_str!("../layouts/RandPropPoints.dbd"),
+    ),
+    ("Resistances", include_str!("../layouts/Resistances.dbd")),
+    (
+        "ScalingStatDistribution",
+        include_str!("../layouts/ScalingStatDistribution.dbd"),
+    ),
+    (
+        "ScalingStatValues",
+        include_str!("../layouts/ScalingStatValues.dbd"),
+    ),
+    ("ScreenEffect", include_str!("../layouts/ScreenEffect.dbd")),
+    (
+        "ServerMessages",
+        include_str!("../layouts/ServerMessages.dbd"),
+    ),
+    (
+        "SheatheSoundLookups",
+        include_str!("../layouts/SheatheSoundLookups.dbd"),
+    ),
+    (
+        "SkillCostsData",
+        include_str!("../layouts/SkillCostsData.dbd"),
+    ),
+    ("SkillLine", include_str!("../layouts/SkillLine.dbd")),
+    (
+        "SkillLineAbility",
+        include_str!("../layouts/SkillLineAbility.dbd"),
+    ),
+    (
+        "SkillLineCategory",
+        include_str!("../layouts/SkillLineCategory.dbd"),
+    ),
+    (
+        "SkillRaceClassInfo",
+        include_str!("../layouts/SkillRaceClassInfo.dbd"),
+    ),
+    ("SkillTiers", include_str!("../layouts/SkillTiers.dbd")),
+    (
+        "SoundAmbience",
+        include_str!("../layouts/SoundAmbience.dbd"),
+    ),
+    (
+        "SoundEmitters",
+        include_str!("../layouts/SoundEmitters.dbd"),
+    ),
+    ("SoundEntries", include_str!("../layouts/SoundEntries.dbd")),
+    (
+        "SoundEntriesAdvanced",
+        include_str!("../layouts/SoundEntriesAdvanced.dbd"),
+    ),
+    ("SoundFilter", include_str!("../layouts/SoundFilter.dbd")),
+    (
+        "SoundFilterElem",
+        include_str!("../layouts/SoundFilterElem.dbd"),
+    ),
+    (
+        "SoundProviderPreferences",
+        include_str!("../layouts/SoundProviderPreferences.dbd"),
+    ),
+    (
+        "SoundSamplePreferences",
+        include_str!("../layouts/SoundSamplePreferences.dbd"),
+    ),
+    (
+        "SoundWaterType",
+        include_str!("../layouts/SoundWaterType.dbd"),
+    ),
+    ("SpamMessages", include_str!("../layouts/SpamMessages.dbd")),
+    ("Spell", include_str!("../layouts/Spell.dbd")),
+    (
+        "SpellCastTimes",
+        include_str!("../layouts/SpellCastTimes.dbd"),
+    ),
+    (
+        "SpellCategory",
+        include_str!("../layouts/SpellCategory.dbd"),
+    ),
+    (
+        "SpellChainEffects",
+        include_str!("../layouts/SpellChainEffects.dbd"),
+    ),
+    (
+        "SpellDescriptionVariables",
+        include_str!("../layouts/SpellDescriptionVariables.dbd"),
+    ),
+    (
+        "SpellDifficulty",
+        include_str!("../layouts/SpellDifficulty.dbd"),
+    ),
+    (
+        "SpellDispelType",
+        include_str!("../layouts/SpellDispelType.dbd"),
+    ),
+    (
+        "SpellDuration",
+        include_str!("../layouts/SpellDuration.dbd"),
+    ),
+    (
+        "SpellEffectCameraShakes",
+        include_str!("../layouts/SpellEffectCameraShakes.dbd"),
+    ),
+    (
+        "SpellFocusObject",
+        include_str!("../layouts/SpellFocusObject.dbd"),
+    ),
+    ("SpellIcon", include_str!("../layouts/SpellIcon.dbd")),
+    (
+        "SpellItemEnchantment",
+        include_str!("../layouts/SpellItemEnchantment.dbd"),
+    ),
+    (
+        "SpellItemEnchantmentCondition",
+        include_str!("../layouts/SpellItemEnchantmentCondition.dbd"),
+    ),
+    (
+        "SpellMechanic",
+        include_str!("../layouts/SpellMechanic.dbd"),
+    ),
+    ("SpellMissile", include_str!("../layouts/SpellMissile.dbd")),
+    (
+        "SpellMissileMotion",
+        include_str!("../layouts/SpellMissileMotion.dbd"),
+    ),
+    ("SpellRadius", include_str!("../layouts/SpellRadius.dbd")),
+    ("SpellRange", include_str!("../layouts/SpellRange.dbd")),
+    (
+        "SpellRuneCost",
+        include_str!("../layouts/SpellRuneCost.dbd"),
+    ),
+    (
+        "SpellShapeshiftForm",
+        include_str!("../layouts/SpellShapeshiftForm.dbd"),
+    ),
+    ("SpellVisual", include_str!("../layouts/SpellVisual.dbd")),
+    (
+        "SpellVisualEffectName",
+        include_str!("../layouts/SpellVisualEffectName.dbd"),
+    ),
+    (
+        "SpellVisualKit",
+        include_str!("../layouts/SpellVisualKit.dbd"),
+    ),
+    (
+        "SpellVisualKitAreaModel",
+        include_str!("../layouts/SpellVisualKitAreaModel.dbd"),
+    ),
+    (
+        "SpellVisualKitModelAttach",
+        include_str!("../layouts/SpellVisualKitModelAttach.dbd"),
+    ),
+    (
+        "SpellVisualPrecastTransitions",
+        include_str!("../layouts/SpellVisualPrecastTransitions.dbd"),
+    ),
+    (
+        "StableSlotPrices",
+        include_str!("../layouts/StableSlotPrices.dbd"),
+    ),
+    (
+        "Startup_Strings",
+        include_str!("../layouts/Startup_Strings.dbd"),
+    ),
+    ("Stationery", include_str!("../layouts/Stationery.dbd")),
+    (
+        "StringLookups",
+        include_str!("../layouts/StringLookups.dbd"),
+    ),
+    (
+        "SummonProperties",
+        include_str!("../layouts/SummonProperties.dbd"),
+    ),
+    ("Talent", include_str!("../layouts/Talent.dbd")),
+    ("TalentTab", include_str!("../layouts/TalentTab.dbd")),
+    ("TaxiNodes", include_str!("../layouts/TaxiNodes.dbd")),
+    ("TaxiPath", include_str!("../layouts/TaxiPath.dbd")),
+    ("TaxiPathNode", include_str!("../layouts/TaxiPathNode.dbd")),
+    (
+        "TeamContributionPoints",
+        include_str!("../layouts/TeamContributionPoints.dbd"),
+    ),
+    ("TerrainType", include_str!("../layouts/TerrainType.dbd")),
+    (
+        "TerrainTypeSounds",
+        include_str!("../layouts/TerrainTypeSounds.dbd"),
+    ),
+    (
+        "TotemCategory",
+        include_str!("../layouts/TotemCategory.dbd"),
+    ),
+    (
+        "TransportAnimation",
+        include_str!("../layouts/TransportAnimation.dbd"),
+    ),
+    (
+        "TransportPhysics",
+        include_str!("../layouts/TransportPhysics.dbd"),
+    ),
+    (
+        "TransportRotation",
+        include_str!("../layouts/TransportRotation.dbd"),
+    ),
+    (
+        "UISoundLookups",
+        include_str!("../layouts/UISoundLookups.dbd"),
+    ),
+    ("UnitBlood", include_str!("../layouts/UnitBlood.dbd")),
+    (
+        "UnitBloodLevels",
+        include_str!("../layouts/UnitBloodLevels.dbd"),
+    ),
+    ("Vehicle", include_str!("../layouts/Vehicle.dbd")),
+    ("VehicleSeat", include_str!("../layouts/VehicleSeat.dbd")),
+    (
+        "VehicleUIIndicator",
+        include_str!("../layouts/VehicleUIIndicator.dbd"),
+    ),
+    (
+        "VehicleUIIndSeat",
+        include_str!("../layouts/VehicleUIIndSeat.dbd"),
+    ),
+    (
+        "VideoHardware",
+        include_str!("../layouts/VideoHardware.dbd"),
+    ),
+    (
+        "VocalUISounds",
+        include_str!("../layouts/VocalUISounds.dbd"),
+    ),
+    (
+        "WeaponImpactSounds",
+        include_str!("../layouts/WeaponImpactSounds.dbd"),
+    ),
+    (
+        "WeaponSwingSounds2",
+        include_str!("../layouts/WeaponSwingSounds2.dbd"),
+    ),
+    ("Weather", include_str!("../layouts/Weather.dbd")),
+    ("WMOAreaTable", include_str!("../layouts/WMOAreaTable.dbd")),
+    (
+        "WorldChunkSounds",
+        include_str!("../layouts/WorldChunkSounds.dbd"),
+    ),
+    ("WorldMapArea", include_str!("../layouts/WorldMapArea.dbd")),
+    (
+        "WorldMapContinent",
+        include_str!("../layouts/WorldMapContinent.dbd"),
+    ),
+    (
+        "WorldMapOverlay",
+        include_str!("../layouts/WorldMapOverlay.dbd"),
+    ),
+    (
+        "WorldMapTransforms",
+        include_str!("../layouts/WorldMapTransforms.dbd"),
+    ),
+    (
+        "WorldSafeLocs",
+        include_str!("../layouts/WorldSafeLocs.dbd"),
+    ),
+    ("WorldStateUI", include_str!("../layouts/WorldStateUI.dbd")),
+    (
+        "WorldStateZoneSounds",
+        include_str!("../layouts/WorldStateZoneSounds.dbd"),
+    ),
+    (
+        "WowError_Strings",
+        include_str!("../layouts/WowError_Strings.dbd"),
+    ),
+    (
+        "ZoneIntroMusicTable",
+        include_str!("../layouts/ZoneIntroMusicTable.dbd"),
+    ),
+    ("ZoneMusic", include_str!("../layouts/ZoneMusic.dbd")),
+];
+
+/// Client locales in the order of localized string fields.
+pub const LOCALE_NAMES: [&str; LOCALES] = [
+    "enUS",
+    "koKR",
+    "frFR",
+    "deDE",
+    "zhCN",
+    "zhTW",
+    "esES",
+    "esMX",
+    "ruRU",
+    "jaJP",
+    "ptPT",
+    "itIT",
+    "unknown12",
+    "unknown13",
+    "unknown14",
+    "unknown15",
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Int,
+    Float,
+    String,
+    LocString,
+}
+
+/// A field of one build's layout.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: FieldType,
+    /// Size in bits of integer fields, `32` for everything else.
+    pub size: u8,
+    pub signed: bool,
+    /// Array length, `1` for plain fields.
+    pub count: usize,
+    /// Marked `$id$`, the record's key.
+    pub is_id: bool,
+}
+
+/// Field order of a table in one build.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// Table name such as `Item`.
+    pub table: String,
+    pub fields: Vec<Field>,
+    /// File the layout was read from, `None` for built-in layouts.
+    pub path: Option<PathBuf>,
+}
+
+/// Every build of a table described by a WoWDBDefs style `.dbd` file:
+///
+/// ```text
+/// COLUMNS
+/// int ID
+/// locstring Name_lang
+/// int<Spell::ID> SetSpellID
+/// float Speed
+///
+/// BUILD 3.3.5.12340, 3.3.5.3466
+/// $id$ID<32>
+/// Name_lang
+/// SetSpellID<32>[8]
+/// Speed
+/// ```
+///
+/// Integer fields need a `<size>` such as `<32>` or `<u8>`. `LAYOUT` and
+/// `COMMENT` lines and `//` comments are ignored.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub table: String,
+    builds: Vec<(Vec<BuildRange>, Vec<Field>)>,
+}
+
+/// Client build numbers a layout applies to, `3.3.5.12340` being build
+/// `12340`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BuildRange {
+    min: u32,
+    max: u32,
+}
+
+impl Field {
+    /// Fields taken up in the file, counting every locale of a localized
+    /// string.
+    pub fn field_count(&self) -> usize {
+        match self.ty {
+            FieldType::LocString => self.count * (LOCALES + 1),
+            _ => self.count,
+        }
+    }
+
+    pub fn byte_size(&self) -> usize {
+        let size = match self.ty {
+            FieldType::Int => self.size as usize / 8,
+            FieldType::LocString => (LOCALES + 1) * 4,
+            FieldType::Float | FieldType::String => 4,
+        };
+        size * self.count
+    }
+
+    /// Zero or empty value of one element of this field.
+    pub fn default_value(&self) -> Value {
+        match self.ty {
+            FieldType::Int => Value::Int(0),
+            FieldType::Float => Value::Float(0.0),
+            FieldType::String => Value::String(String::new()),
+            FieldType::LocString => Value::LocString(LocString {
+                strings: vec![String::new(); LOCALES],
+                flags: 0,
+            }),
+        }
+    }
+}
+
+impl Layout {
+    pub fn field_count(&self) -> usize {
+        self.fields.iter().map(Field::field_count).sum()
+    }
+
+    pub fn record_size(&self) -> usize {
+        self.fields.iter().map(Field::byte_size).sum()
+    }
+
+    /// Field named `name`, ignoring case.
+    pub fn field(&self, name: &str) -> Option<(usize, &Field)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Move `records` read with `from` into this layout by field name.
+    /// Fields `from` doesn't have are zero, fields only `from` has are
+    /// dropped (see [`Layout::dropped_fields`]), and integers and floats are
+    /// converted between each other.
+    pub fn remap(&self, from: &Layout, records: Vec<Record>) -> Vec<Record> {
+        let sources: Vec<Option<usize>> = self
+            .fields
+            .iter()
+            .map(|field| from.field(&field.name).map(|(index, _)| index))
+            .collect();
+        records
+            .into_iter()
+            .map(|record| {
+                self.fields
+                    .iter()
+                    .zip(&sources)
+                    .map(|(field, source)| match source {
+                        Some(index) => record[*index].clone(),
+                        None if field.count > 1 => {
+                            Value::Array(vec![field.default_value(); field.count])
+                        }
+                        None => field.default_value(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Fields of `from` this layout has no place for, which
+    /// [`Layout::remap`] leaves out.
+    pub fn dropped_fields(&self, from: &Layout) -> Vec<String> {
+        from.fields
+            .iter()
+            .filter(|field| self.field(&field.name).is_none())
+            .map(|field| field.name.clone())
+            .collect()
+    }
+}
+
+impl Definition {
+    /// Read a `.dbd` file, named after the table it describes.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let table = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        Self::parse(table, &content).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn parse(table: &str, content: &str) -> Result<Self> {
+        let mut columns = HashMap::new();
+        let mut builds: Vec<(Vec<BuildRange>, Vec<Field>)> = Vec::new();
+        let mut in_columns = false;
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+            let context = || format!("line {}", number + 1);
+            if line.is_empty() {
+                in_columns = false;
+                continue;
+            }
+            if line == "COLUMNS" {
+                in_columns = true;
+                continue;
+            }
+            if in_columns {
+                let (ty, name) = parse_column(line).with_context(context)?;
+                columns.insert(name.to_lowercase(), ty);
+                continue;
+            }
+            if let Some(versions) = line.strip_prefix("BUILD ") {
+                let ranges = parse_builds(versions).with_context(context)?;
+                // Consecutive BUILD lines share the fields that follow
+                match builds.last_mut() {
+                    Some((last, fields)) if fields.is_empty() => last.extend(ranges),
+                    _ => builds.push((ranges, Vec::new())),
+                }
+                continue;
+            }
+            if line.starts_with("LAYOUT ") || line.starts_with("COMMENT ") {
+                continue;
+            }
+            let Some((_, fields)) = builds.last_mut() else {
+                bail!("{}: field before any BUILD line", context());
+            };
+            fields.push(parse_field(line, &columns).with_context(context)?);
+        }
+
+        if builds.is_empty() {
+            bail!("no BUILD sections");
+        }
+        Ok(Self {
+            table: table.to_string(),
+            builds,
+        })
+    }
+
+    /// Layout of `build`, or of the last build listed if `None`.
+    pub fn layout(&self, build: Option<u32>) -> Option<Layout> {
+        let (_, fields) = match build {
+            Some(build) => self.builds.iter().find(|(ranges, _)| {
+                ranges
+                    .iter()
+                    .any(|range| (range.min..=range.max).contains(&build))
+            })?,
+            None => self.builds.last()?,
+        };
+        Some(Layout {
+            table: self.table.clone(),
+            fields: fields.clone(),
+            path: None,
+        })
+    }
+}
+
+/// `int<Spell::ID> SetSpellID` into its type and name.
+fn parse_column(line: &str) -> Result<(FieldType, String)> {
+    let Some((ty, name)) = line.split_once(' ') else {
+        bail!("expected `<type> <name>`, got `{line}`");
+    };
+    let ty = match ty.split('<').next().unwrap_or_default() {
+        "int" | "uint" => FieldType::Int,
+        "float" => FieldType::Float,
+        "string" => FieldType::String,
+        "locstring" => FieldType::LocString,
+        other => bail!("unknown column type `{other}`"),
+    };
+    // Names WoWDBDefs hasn't verified end in `?`
+    Ok((ty, name.trim().trim_end_matches('?').to_string()))
+}
+
+/// `3.3.5.12340`, `3.3.5.12340-3.3.5.13623` or a comma separated list.
+fn parse_builds(versions: &str) -> Result<Vec<BuildRange>> {
+    let build = |version: &str| {
+        let number = version.trim().rsplit('.').next().unwrap_or_default();
+        number
+            .parse::<u32>()
+            .with_context(|| format!("invalid build `{version}`"))
+    };
+    versions
+        .split(',')
+        .map(|range| {
+            Ok(match range.split_once('-') {
+                Some((min, max)) => BuildRange {
+                    min: build(min)?,
+                    max: build(max)?,
+                },
+                None => {
+                    let build = build(range)?;
+                    BuildRange {
+                        min: build,
+                        max: build,
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// `$id$ID<32>`, `Name_lang` or `ItemID<u32>[17]`.
+fn parse_field(line: &str, columns: &HashMap<String, FieldType>) -> Result<Field> {
+    let mut rest = line;
+    let mut is_id = false;
+    if let Some(annotated) = rest.strip_prefix('$') {
+        let Some((annotations, field)) = annotated.split_once('$') else {
+            bail!("unterminated annotation in `{line}`");
+        };
+        is_id = annotations.split(',').any(|annotation| annotation == "id");
+        rest = field;
+    }
+
+    let mut count = 1;
+    if let Some((field, array)) = rest.split_once('[') {
+        count = array
+            .trim_end_matches(']')
+            .parse()
+            .with_context(|| format!("invalid array length in `{line}`"))?;
+        rest = field;
+    }
+
+    let mut size = None;
+    let mut signed = true;
+    if let Some((field, bits)) = rest.split_once('<') {
+        let bits = bits.trim_end_matches('>');
+        let bits = match bits.strip_prefix('u') {
+            Some(bits) => {
+                signed = false;
+                bits
+            }
+            None => bits,
+        };
+        size = Some(
+            bits.parse::<u8>()
+                .with_context(|| format!("invalid size in `{line}`"))?,
+        );
+        rest = field;
+    }
+
+    let name = rest.trim().to_string();
+    let Some(&ty) = columns.get(&name.to_lowercase()) else {
+        bail!("`{name}` is not in COLUMNS");
+    };
+    let size = match (ty, size) {
+        (FieldType::Int, Some(size @ (8 | 16 | 32 | 64))) => size,
+        (FieldType::Int, Some(size)) => bail!("unsupported size {size} of `{name}`"),
+        (FieldType::Int, None) => bail!("integer `{name}` needs a size such as <32>"),
+        (_, _) => 32,
+    };
+    Ok(Field {
+        name,
+        ty,
+        size,
+        signed,
+        count,
+        is_id,
+    })
+}
+
+/// Table layouts replacing the built-in wrath ones, such as the modified
+/// `Item.dbc` of a custom client.
+#[derive(Debug, Clone, Default)]
+pub struct Layouts {
+    overrides: HashMap<String, Layout>,
+}
+
+impl Layouts {
+    /// Read every `.dbd` file in `dir`, using the layout of `build` (the
+    /// last one listed if `None`). Tables without a built-in layout are only
+    /// read through theirs by `inspect` and `dump`.
+    pub fn load(dir: &Path, build: Option<u32>) -> Result<Self> {
+        let mut layouts = Self::default();
+        for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("dbd") {
+                continue;
+            }
+            let definition = Definition::load(&path)?;
+            let Some(mut layout) = definition.layout(build) else {
+                println!("Skipping {}: no layout for this build", path.display());
+                continue;
+            };
+            layout.path = Some(path);
+            layouts.insert(layout);
+        }
+        Ok(layouts)
+    }
+
+    pub fn insert(&mut self, layout: Layout) {
+        self.overrides.insert(layout.table.to_lowercase(), layout);
+    }
+
+    /// Every override, ordered by table.
+    pub fn iter(&self) -> impl Iterator<Item = &Layout> {
+        let mut layouts: Vec<&Layout> = self.overrides.values().collect();
+        layouts.sort_by_key(|layout| layout.table.to_lowercase());
+        layouts.into_iter()
+    }
+
+    /// Override of a table such as `DBFilesClient\Item.dbc`.
+    pub fn get(&self, file_name: &str) -> Option<&Layout> {
+        self.overrides.get(&table_name(file_name).to_lowercase())
+    }
+
+    /// Columns of the override of `file_name` its built-in layout has no
+    /// place for, so [`Layouts::to_wrath`] drops them.
+    pub fn dropped_fields(&self, file_name: &str) -> Vec<String> {
+        match self.get(file_name) {
+            Some(layout) => builtin(&layout.table)
+                .map(|wrath| wrath.dropped_fields(layout))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Rewrite a table that has an override into the built-in layout, so
+    /// `wow_dbc` can read it. `None` for tables without an override.
+    pub fn to_wrath(&self, file_name: &str, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        let Some(layout) = self.get(file_name) else {
+            return Ok(None);
+        };
+        let Some(wrath) = builtin(&layout.table) else {
+            bail!("{} has no built-in layout to map it to", layout.table);
+        };
+        let records = DbcFile::parse(data)?.read(layout)?;
+        Ok(Some(dbc::write(&wrath, &wrath.remap(layout, records))))
+    }
+}
+
+/// Built-in wrath layout of `table`, such as `Item`.
+pub fn builtin(table: &str) -> Option<Layout> {
+    let (name, content) = BUILTIN
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(table))?;
+    Definition::parse(name, content)
+        .expect("built-in layouts are valid")
+        .layout(Some(WRATH_BUILD))
+}
+
+/// `Item` for `DBFilesClient\Item.dbc`.
+pub fn table_name(file_name: &str) -> &str {
+    let name = file_name.rsplit(['\\', '/']).next().unwrap_or(file_name);
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if extension.eq_ignore_ascii_case("dbc") => stem,
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wow_dbc::DbcTable;
+
+    const DEFINITION: &str = "
+COLUMNS
+int ID
+int<Spell::ID> SpellID
+float Speed
+string InternalName
+locstring Name_lang
+int Quality? // unverified name
+
+LAYOUT 1A2B3C4D
+BUILD 3.3.5.11723-3.3.5.12213
+BUILD 3.3.5.3466
+$id$ID<32>
+Name_lang
+SpellID<u16>[2]
+
+BUILD 3.3.5.12340
+$id$ID<32>
+SpellID<32>[2]
+Speed
+InternalName
+Name_lang
+Quality<u8>
+";
+
+    fn definition() -> Definition {
+        Definition::parse("Sample", DEFINITION).unwrap()
+    }
+
+    /// Localized string with `text` in the slot of `locale`.
+    fn loc_string(locale: usize, text: &str) -> Value {
+        let mut strings = vec![String::new(); LOCALES];
+        strings[locale] = text.to_string();
+        Value::LocString(LocString { strings, flags: 0 })
+    }
+
+    #[test]
+    fn picks_the_layout_of_a_build() {
+        let definition = definition();
+        let old = definition.layout(Some(3466)).unwrap();
+        assert_eq!(definition.layout(Some(12000)), Some(old.clone()));
+        assert_eq!(definition.layout(Some(13623)), None);
+        assert_eq!(old.fields.len(), 3);
+        assert_eq!((old.fields[2].size, old.fields[2].signed), (16, false));
+        assert_eq!(old.fields[2].count, 2);
+
+        let wrath = definition.layout(None).unwrap();
+        assert_eq!(definition.layout(Some(WRATH_BUILD)), Some(wrath.clone()));
+        assert!(wrath.fields[0].is_id);
+        assert_eq!(wrath.fields[5].name, "Quality");
+        assert_eq!(wrath.field_count(), 1 + 2 + 1 + 1 + (LOCALES + 1) + 1);
+        assert_eq!(wrath.record_size(), 4 * (1 + 2 + 1 + 1 + LOCALES + 1) + 1);
+    }
+
+    #[test]
+    fn records_round_trip_through_a_layout() {
+        let layout = definition().layout(None).unwrap();
+        let records = vec![
+            vec![
+                Value::Int(1),
+                Value::Array(vec![Value::Int(18384), Value::Int(-1)]),
+                Value::Float(2.6),
+                Value::String("sword_1h".to_string()),
+                loc_string(0, "Arcanite Reaper"),
+                Value::Int(255),
+            ],
+            vec![
+                Value::Int(2),
+                Value::Array(vec![Value::Int(0), Value::Int(0)]),
+                Value::Float(0.0),
+                Value::String(String::new()),
+                loc_string(3, "Schwert"),
+                Value::Int(0),
+            ],
+        ];
+        let data = dbc::write(&layout, &records);
+        let dbc = DbcFile::parse(&data).unwrap();
+        assert_eq!(dbc.record_count, 2);
+        assert_eq!(dbc.read(&layout).unwrap(), records);
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        for (content, error) in [
+            ("COLUMNS\nint ID\n\nID<32>", "field before any BUILD line"),
+            ("COLUMNS\nint ID\n\nBUILD 3.3.5.12340\nID", "needs a size"),
+            (
+                "COLUMNS\nint ID\n\nBUILD 3.3.5.12340\nName",
+                "not in COLUMNS",
+            ),
+            ("COLUMNS\nbool ID", "unknown column type"),
+            ("COLUMNS\nint ID", "no BUILD sections"),
+        ] {
+            let err = Definition::parse("Sample", content).unwrap_err();
+            assert!(format!("{err:#}").contains(error), "{err:#}");
+        }
+    }
+
+    #[test]
+    fn overrides_are_rewritten_into_the_builtin_layout() {
+        // A client that moved the name to the end and dropped the spells
+        let content = "
+COLUMNS
+int ID
+locstring Name_lang
+int<SkillLine::ID> RequiredSkill
+int Flags
+
+BUILD 3.3.5.12340
+$id$ID<32>
+RequiredSkill<32>
+Flags<32>
+Name_lang
+";
+        let layout = Definition::parse("ItemSet", content)
+            .unwrap()
+            .layout(None)
+            .unwrap();
+        let mut layouts = Layouts::default();
+        layouts.insert(layout.clone());
+        let data = dbc::write(
+            &layout,
+            &[vec![
+                Value::Int(700),
+                Value::Int(164),
+                Value::Int(1),
+                loc_string(0, "Battlegear of Wrath"),
+            ]],
+        );
+
+        let wrath = builtin("ItemSet").unwrap();
+        let converted = layouts
+            .to_wrath("DBFilesClient\\ItemSet.dbc", &data)
+            .unwrap()
+            .unwrap();
+        let records = DbcFile::parse(&converted).unwrap().read(&wrath).unwrap();
+        let value = |name: &str| &records[0][wrath.field(name).unwrap().0];
+        assert_eq!(value("ID"), &Value::Int(700));
+        assert_eq!(value("RequiredSkill"), &Value::Int(164));
+        assert_eq!(value("Name_lang"), &loc_string(0, "Battlegear of Wrath"));
+        assert_eq!(value("ItemID"), &Value::Array(vec![Value::Int(0); 17]));
+        assert_eq!(
+            layouts.dropped_fields("DBFilesClient\\ItemSet.dbc"),
+            ["Flags"]
+        );
+        assert!(
+            layouts
+                .to_wrath("DBFilesClient\\Item.dbc", &data)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn loads_layouts_of_tables_without_a_builtin_one() {
+        let dir = std::env::temp_dir().join(format!("layouts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let content = "
+COLUMNS
+int ID
+int SkillLine
+
+BUILD 3.3.5.12340
+$id$ID<32>
+SkillLine<32>
+";
+        fs::write(dir.join("EpochTransmog.dbd"), content).unwrap();
+        let layouts = Layouts::load(&dir, None);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let layouts = layouts.unwrap();
+        let layout = layouts.get("DBFilesClient\\EpochTransmog.dbc").unwrap();
+        assert_eq!(layout.fields.len(), 2);
+        assert!(layouts.dropped_fields("EpochTransmog").is_empty());
+        let err = layouts
+            .to_wrath("DBFilesClient\\EpochTransmog.dbc", &[])
+            .unwrap_err();
+        assert!(err.to_string().contains("no built-in layout"), "{err}");
+    }
+
+    #[test]
+    fn builtin_layouts_parse() {
+        for (table, _) in BUILTIN {
+            let layout = builtin(table).unwrap();
+            assert_eq!(&layout.table, table);
+            assert!(!layout.fields.is_empty(), "{table}");
+        }
+    }
+
+    /// Header of an empty table written by `wow_dbc`.
+    fn wow_dbc_header(table: &impl DbcTable) -> DbcFile {
+        let mut data = Vec::new();
+        table.write(&mut data).unwrap();
+        DbcFile::parse(&data).unwrap()
+    }
+
+    #[test]
+    fn builtin_layouts_match_wow_dbc() {
+        macro_rules! check {
+            ($($table:ident::$ty:ident),* $(,)?) => {$(
+                let header = wow_dbc_header(&wow_dbc::wrath_tables::$table::$ty { rows: vec![] });
+                let layout = builtin(table_name(<wow_dbc::wrath_tables::$table::$ty as DbcTable>::FILENAME)).unwrap();
+                assert_eq!(
+                    (layout.record_size(), layout.field_count()),
+                    (header.record_size, header.field_count),
+                    "{}",
+                    layout.table
+                );
+            )*};
+        }
+        check!(
+            achievement::Achievement,
+            chr_classes::ChrClasses,
+            chr_races::ChrRaces,
+            faction::Faction,
+            item::Item,
+            item_random_suffix::ItemRandomSuffix,
+            item_set::ItemSet,
+            map::Map,
+            spell::Spell,
+            spell_item_enchantment::SpellItemEnchantment,
+            talent::Talent,
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Item data extraction for Project Epoch client files.
+//!
+//! The [`ItemDatabase`] reads the `.dbc` tables out of a WotLK 3.3.5 data
+//! directory, merges supplemental item data on top and exposes the result as
+//! [`Item`] models that can be queried or exported.
+
+pub mod blp;
+pub mod config;
+pub mod database;
+pub mod dbc;
+pub mod diagnostics;
+pub mod diff;
+pub mod dump;
+pub mod filter;
+pub mod history;
+pub mod icons;
+pub mod infer;
+pub mod layout;
+pub mod locale;
+pub mod merge;
+pub mod models;
+pub mod mpq;
+pub mod parse;
+pub mod provenance;
+pub mod random;
+pub mod requirements;
+pub mod spell_text;
+pub mod stats;
+pub mod utils;
+pub mod wdb;
+pub mod weapon;
+
+pub use config::{OutputConfig, OutputFormat, ProjectConfig};
+pub use database::ItemDatabase;
+pub use diagnostics::Diagnostics;
+pub use diff::ItemDiff;
+pub use filter::{ItemFilter, ItemStatus};
+pub use history::HistoryStore;
+pub use merge::{
+    ConflictStrategy, FieldConflict, MergePolicy, SourceConfig, SourceKind, SupplementalSources,
+};
+pub use models::{
+    InventoryType, Item, ItemClass, ItemSet, ItemSetDetails, LocalizedItem, Rarity, SetBonus,
+    SocketColor,
+};
+pub use provenance::{ItemProvenance, Source};
+pub use random::{EnchantmentPools, RandomEnchantments, RandomKind, RandomVariant};
+pub use requirements::{Requirement, Standing};
+pub use stats::{ItemStat, StatModifier, StatType};
+pub use weapon::{DamageRange, DamageSchool, WeaponStats};
@@ -0,0 +1,41 @@
+use wow_dbc::ExtendedLocalizedString;
+
+/// Text of a localized string of the client's own locale.
+///
+/// A 3.3.5 client only fills the slot of its locale (`enUS` and `enGB`
+/// share the first), so the first non-empty slot is the client's text
+/// whichever locale its archives were loaded for.
+pub trait LocalizedText {
+    fn text(&self) -> &str;
+}
+
+/// Localized string with `text` in the slot every client reads first, for
+/// text read from the tables of another locale.
+pub fn localized(text: &str) -> ExtendedLocalizedString {
+    ExtendedLocalizedString {
+        en_gb: text.to_string(),
+        ..Default::default()
+    }
+}
+
+impl LocalizedText for ExtendedLocalizedString {
+    fn text(&self) -> &str {
+        [
+            &self.en_gb,
+            &self.ko_kr,
+            &self.fr_fr,
+            &self.de_de,
+            &self.en_cn,
+            &self.en_tw,
+            &self.es_es,
+            &self.es_mx,
+            &self.ru_ru,
+            &self.ja_jp,
+            &self.pt_pt,
+            &self.it_it,
+        ]
+        .into_iter()
+        .find(|text| !text.is_empty())
+        .map_or("", String::as_str)
+    }
+}
@@ -1,249 +1,1006 @@
-use anyhow::Result;
-use clap::{Parser, ValueEnum};
-use std::{fs, path::PathBuf};
-use wow_dbc::wrath_tables::{item_display_info::ItemDisplayInfoKey, spell::SpellKey};
-
-mod models;
-mod parse;
-mod utils;
-
-use models::{Item, ItemCacheData, Rarity};
-use parse::{collect_mpqs, parse_dbcs};
-use utils::OriginalItemChecker;
-
-use crate::{
-    models::ItemSet,
-    parse::{
-        item::Items, item_class::ItemClasses, item_display_info::ItemDisplayInfos,
-        item_sets::ItemSets, item_sub_class::ItemSubClasses,
-        spell_description_vars::SpellDescriptionVars, spells::Spells,
-    },
+use anyhow::{Result, bail};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use dbc_parser::{
+    HistoryStore, InventoryType, ItemClass, ItemDatabase, ItemDiff, OutputConfig, OutputFormat,
+    ProjectConfig, Rarity, SourceConfig, SourceKind, SupplementalSources,
+    config::{HistoryConfig, ReferenceConfig},
+    dbc::{DbcFile, Value},
+    diff::{self, ExportItems},
+    dump::{self, FlatTable},
+    filter::{IdRange, ItemFilter, ItemStatus, parse_name},
+    history, icons,
+    infer::ColumnGuess,
+    layout::{self, WRATH_BUILD},
+    mpq::{self, ArchiveChain},
+    utils::OriginalItemChecker,
 };
 
 #[derive(Parser)]
 #[command(author, version, about = "WotLK 3.3.5 Item Data Parser & Exporter")]
-struct Args {
-    #[arg(long, help = "Path to WoW Data directory")]
-    data_dir: PathBuf,
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the item exports of a project
+    Build(Box<BuildArgs>),
+    /// Compare two exports or two WoW Data directories
+    Diff(DiffArgs),
+    /// Query the item history recorded by `build`
+    History(HistoryArgs),
+    /// List the DBC tables of the archives, guessing the columns of unknown ones
+    Inspect(InspectArgs),
+    /// Export DBC tables to JSON, CSV or SQLite
+    Dump(DumpArgs),
+    /// Look inside the MPQ archives
+    Mpq(MpqArgs),
+}
+
+#[derive(clap::Args)]
+struct BuildArgs {
+    #[arg(short, long, help = "Project config such as epoch.toml")]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Path to WoW Data directory, replacing those of the config"
+    )]
+    data_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Directory of .dbd table layouts for modified DBCs, such as Item.dbd"
+    )]
+    layouts: Option<PathBuf>,
+
+    #[arg(long, help = "Client locale of the item text, such as deDE")]
+    locale: Option<String>,
+
+    #[arg(
+        long,
+        help = "Locale whose text items also carry, replacing those of the config (repeatable)"
+    )]
+    with_locale: Vec<String>,
 
     #[arg(
         short,
         long,
-        default_value = "items_full",
-        help = "Output file name (without extension)"
+        help = "Output file name (without extension), replacing the configured outputs"
+    )]
+    output: Option<String>,
+
+    #[arg(short, long, help = "Output format")]
+    format: Option<OutputFormatArg>,
+
+    #[arg(long, help = "Client itemcache.wdb file to merge (repeatable)")]
+    wdb: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        help = "TOML file listing supplemental sources and the merge policy"
+    )]
+    merge_config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "item_enchantment_template CSV whose pools list the random variants of items"
+    )]
+    enchantment_pools: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Also write <output>.provenance.json with the source of every field"
+    )]
+    with_provenance: bool,
+
+    #[arg(
+        long,
+        help = "Also write <output>.sets.json with the member items and bonuses of every set"
+    )]
+    with_sets: bool,
+
+    #[arg(long, help = "History store to record this build in")]
+    history: Option<PathBuf>,
+
+    #[arg(long, help = "Client build number recorded in the history store")]
+    client_build: Option<u32>,
+
+    #[arg(long, help = "Write table and source diagnostics to this JSON file")]
+    diagnostics: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Fail if a table is missing, empty or unreadable, or a source is unreadable or has no items"
+    )]
+    strict: bool,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(clap::Args)]
+struct HistoryArgs {
+    #[arg(long, default_value = "history.sqlite", help = "History store")]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    query: HistoryQuery,
+}
+
+#[derive(Subcommand)]
+enum HistoryQuery {
+    /// List recorded exports
+    Builds,
+    /// Build an item was first exported in
+    FirstSeen { item_id: i32 },
+    /// An item as exported in a build (default: latest)
+    Show {
+        item_id: i32,
+        #[arg(long)]
+        build: Option<u32>,
+    },
+    /// Items changed since a build, written like `diff`
+    ChangedSince {
+        build: u32,
+        #[arg(short, long, default_value = "item_diff")]
+        output: String,
+    },
+}
+
+#[derive(clap::Args)]
+struct InspectArgs {
+    #[arg(help = "Table to show, such as ItemSet or DBFilesClient\\ItemSet.dbc")]
+    table: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Project config whose data directories and layouts are used"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "WoW Data directory (repeatable), replacing those of the config"
+    )]
+    data_dir: Vec<PathBuf>,
+
+    #[arg(long, default_value_t = 10, help = "Rows of the table to print")]
+    rows: usize,
+
+    #[arg(long, help = "Guess the columns even if the table has a layout")]
+    infer: bool,
+
+    #[arg(long, help = "Write the guessed columns as a .dbd layout to this file")]
+    dbd: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct DumpArgs {
+    #[arg(help = "Tables to export, such as Spell Faction Map")]
+    tables: Vec<String>,
+
+    #[arg(long, help = "Export every table of the archives")]
+    all: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Project config whose data directories and layouts are used"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "WoW Data directory (repeatable), replacing those of the config"
+    )]
+    data_dir: Vec<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t = DumpFormat::Json)]
+    format: DumpFormat,
+
+    #[arg(
+        short,
+        long,
+        default_value = "dbc",
+        help = "Directory of <Table>.json/.csv files, or the SQLite file"
+    )]
+    output: PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum DumpFormat {
+    Json,
+    Csv,
+    Sqlite,
+}
+
+#[derive(clap::Args)]
+struct MpqArgs {
+    #[arg(short, long, help = "Project config whose data directories are used")]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "WoW Data directory (repeatable), replacing those of the config"
+    )]
+    data_dir: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    command: MpqCommand,
+}
+
+#[derive(Subcommand)]
+enum MpqCommand {
+    /// List the files of each archive with their sizes and flags
+    List {
+        #[arg(long, help = "Only archives whose file name contains this")]
+        archive: Option<String>,
+    },
+    /// Show every archive containing a file and which one the client loads
+    Which { path: String },
+    /// Find the files the client loads matching a glob, such as "DBFilesClient/*.dbc"
+    Search { pattern: String },
+    /// Extract the files the client loads matching paths or globs
+    Extract {
+        #[arg(required = true)]
+        patterns: Vec<String>,
+        #[arg(short, long, default_value = "extracted")]
+        output: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+struct DiffArgs {
+    #[arg(help = "Older export (.json/.ron) or WoW Data directory")]
+    before: PathBuf,
+
+    #[arg(help = "Newer export (.json/.ron) or WoW Data directory")]
+    after: PathBuf,
+
+    #[arg(
+        short,
+        long,
+        help = "Project config used to build items of Data directories"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        default_value = "item_diff",
+        help = "Output file name (without extension), writes .json and .md"
     )]
     output: String,
+}
+
+#[derive(clap::Args)]
+struct FilterArgs {
+    #[arg(
+        long,
+        help = "Export every item, ignoring the default and configured filters"
+    )]
+    all: bool,
+
+    #[arg(
+        long,
+        help = "TOML file with item filters, replacing the defaults (level <= 60, new items)"
+    )]
+    filter_config: Option<PathBuf>,
+
+    #[arg(long, help = "Stock item_template CSV used by --status")]
+    stock_csv: Option<PathBuf>,
+
+    #[arg(long, help = "Minimum required level")]
+    min_level: Option<u32>,
+
+    #[arg(long, help = "Maximum required level")]
+    max_level: Option<u32>,
 
-    #[arg(short, long, default_value = "json", help = "Output format")]
-    format: OutputFormat,
+    #[arg(long, value_parser = parse_name::<ItemClass>, help = "Item class, e.g. Weapon (repeatable)")]
+    class: Vec<ItemClass>,
+
+    #[arg(long, help = "Item subclass, e.g. Sword (repeatable)")]
+    subclass: Vec<String>,
+
+    #[arg(long, value_parser = parse_name::<InventoryType>, help = "Inventory type, e.g. TwoHand (repeatable)")]
+    slot: Vec<InventoryType>,
+
+    #[arg(long, value_parser = parse_name::<Rarity>, help = "Rarity, e.g. Epic (repeatable)")]
+    rarity: Vec<Rarity>,
+
+    #[arg(long, help = "Item id or id range such as 100-200 (repeatable)")]
+    ids: Vec<IdRange>,
+
+    #[arg(long, help = "Keep only items with (true) or without (false) a name")]
+    has_name: Option<bool>,
+
+    #[arg(long, help = "Keep only items with (true) or without (false) an icon")]
+    has_icon: Option<bool>,
+
+    #[arg(
+        long,
+        help = "Keep only items with (true) or without (false) an item set"
+    )]
+    has_set: Option<bool>,
+
+    #[arg(long, value_parser = parse_name::<ItemStatus>, help = "new, modified or stock compared to the stock CSV (repeatable)")]
+    status: Vec<ItemStatus>,
+}
+
+impl FilterArgs {
+    /// Apply the filter flags to the project filters.
+    fn apply(self, config: &mut ProjectConfig) -> Result<()> {
+        if self.all {
+            config.filter = ItemFilter::default();
+            for output in &mut config.outputs {
+                output.filter = ItemFilter::default();
+            }
+        } else if let Some(path) = &self.filter_config {
+            config.filter = ItemFilter::load(path)?;
+        }
+        if let Some(path) = self.stock_csv {
+            config.reference.stock_items = Some(path);
+        }
+        config.filter = config.filter.clone().overlay(ItemFilter {
+            min_level: self.min_level,
+            max_level: self.max_level,
+            classes: self.class,
+            subclasses: self.subclass,
+            inventory_types: self.slot,
+            rarities: self.rarity,
+            ids: self.ids,
+            has_name: self.has_name,
+            has_icon: self.has_icon,
+            has_set: self.has_set,
+            status: self.status,
+        });
+        Ok(())
+    }
 }
 
-#[derive(ValueEnum, Clone)]
-enum OutputFormat {
+#[derive(ValueEnum, Clone, Copy)]
+enum OutputFormatArg {
     Json,
     Ron,
 }
 
-fn load_item_cache_data() -> Result<ItemCacheData> {
-    let content = fs::read_to_string("data/item_cache.beta.3.5.json")?;
-    let item_cache: ItemCacheData = serde_json::from_str(&content)?;
-    Ok(item_cache)
+impl From<OutputFormatArg> for OutputFormat {
+    fn from(format: OutputFormatArg) -> Self {
+        match format {
+            OutputFormatArg::Json => OutputFormat::Json,
+            OutputFormatArg::Ron => OutputFormat::Ron,
+        }
+    }
+}
+
+/// Config used without `--config`, matching the files shipped in `data/`
+/// of the working directory.
+fn builtin_config() -> Result<ProjectConfig> {
+    let data = PathBuf::from("data");
+    let item_cache = data.join("item_cache.beta.3.5.json");
+    if !item_cache.is_file() {
+        bail!(
+            "no {} in the working directory, run from dbc_parser/ or pass --config",
+            item_cache.display()
+        );
+    }
+    Ok(ProjectConfig {
+        merge: SupplementalSources {
+            sources: vec![SourceConfig::from_path(SourceKind::ItemCache, item_cache)],
+            ..Default::default()
+        },
+        reference: ReferenceConfig {
+            stock_items: Some(data.join("wotlk_item_template.csv")),
+            ..Default::default()
+        },
+        filter: ItemFilter::epoch_default(),
+        outputs: vec![OutputConfig {
+            path: PathBuf::from("items_full.json"),
+            format: OutputFormat::Json,
+            provenance: false,
+            sets: false,
+            filter: ItemFilter::default(),
+        }],
+        ..Default::default()
+    })
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Build(args) => build(*args),
+        Command::Diff(args) => diff(args),
+        Command::History(args) => history(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Dump(args) => dump(args),
+        Command::Mpq(args) => mpq_command(args),
+    }
+}
+
+fn build(args: BuildArgs) -> Result<()> {
+    let mut config = match &args.config {
+        Some(path) => ProjectConfig::load(path)?,
+        None => builtin_config()?,
+    };
+    if let Some(data_dir) = args.data_dir {
+        config.data_dirs = vec![data_dir];
+    }
+    if let Some(dir) = args.layouts {
+        config.layouts.dir = Some(dir);
+    }
+    if let Some(locale) = args.locale {
+        config.locale = Some(locale);
+    }
+    if !args.with_locale.is_empty() {
+        config.locales = Some(args.with_locale);
+    }
+    if let Some(path) = &args.merge_config {
+        config.merge = SupplementalSources::load(path)?;
+    }
+    if let Some(path) = args.enchantment_pools {
+        config.reference.enchantment_pools = Some(path);
+    }
+    config.merge.sources.extend(
+        args.wdb
+            .into_iter()
+            .map(|path| SourceConfig::from_path(SourceKind::Wdb, path)),
+    );
+    if args.output.is_some() || args.format.is_some() {
+        let format = args.format.map(OutputFormat::from).unwrap_or_default();
+        let name = args.output.unwrap_or_else(|| "items_full".to_string());
+        config.outputs = vec![OutputConfig {
+            path: PathBuf::from(format!("{name}.{}", format.extension())),
+            format,
+            provenance: false,
+            sets: false,
+            filter: ItemFilter::default(),
+        }];
+    }
+    for output in &mut config.outputs {
+        output.provenance |= args.with_provenance;
+        output.sets |= args.with_sets;
+    }
+    match (args.history, args.client_build, &mut config.history) {
+        (Some(path), Some(build), _) => config.history = Some(HistoryConfig { path, build }),
+        (Some(path), None, Some(history)) => history.path = path,
+        (None, Some(build), Some(history)) => history.build = build,
+        (Some(_), None, None) => bail!("--history needs --client-build"),
+        (None, Some(_), None) => bail!("--client-build needs --history or a [history] config"),
+        _ => {}
+    }
+    if let Some(path) = args.diagnostics {
+        config.diagnostics = Some(path);
+    }
+    config.strict |= args.strict;
+    args.filter.apply(&mut config)?;
+    config.validate()?;
+
+    let layouts = config.layouts.load()?;
+    let pools = config.reference.load_enchantment_pools()?;
+    let locales = config.locales()?;
+    let database = ItemDatabase::build(
+        &config.data_dirs,
+        &config.merge,
+        &config.icons,
+        &layouts,
+        &locales,
+        &pools,
+    )?;
+
+    let diagnostics = database.diagnostics();
+    diagnostics.print_summary();
+    if let Some(path) = &config.diagnostics {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(diagnostics)?)?;
+        println!("Wrote diagnostics to: {}", path.display());
+    }
+    if config.strict && !diagnostics.is_clean() {
+        bail!(
+            "{} problems found in strict mode, no outputs written",
+            diagnostics.problem_count()
+        );
+    }
+
+    if let Some(history) = &config.history {
+        let mut sources = database.archives().to_vec();
+        sources.extend(
+            config
+                .merge
+                .sources
+                .iter()
+                .map(|source| source.path.clone()),
+        );
+        sources.extend(config.reference.enchantment_pools.clone());
+        let source_hash = history::source_hash(
+            &sources,
+            &config.merge.policy,
+            &layouts,
+            &locales,
+            &config.icons,
+        )?;
+        let mut store = HistoryStore::open(&history.path)?;
+        match store.record(
+            history.build,
+            &source_hash,
+            &diff::export_items(database.iter())?,
+        )? {
+            Some(export) => println!(
+                "Recorded {} items of build {} in: {}",
+                export.item_count,
+                export.build,
+                history.path.display()
+            ),
+            None => println!(
+                "Build {} with these sources is already recorded",
+                history.build
+            ),
+        }
+    }
 
-    println!("Scanning for MPQ files in: {}", args.data_dir.display());
-    let enus_dir = args.data_dir.join("enUS");
+    // Only load item_template.csv when filtering by new/modified/stock
+    let checker = match &config.reference.stock_items {
+        Some(path) if config.needs_stock_items() => Some(OriginalItemChecker::new(path)?),
+        _ => None,
+    };
 
-    // Load MPQs in priority order
-    let mut mpq_paths = collect_mpqs(&args.data_dir)?;
-    if enus_dir.exists() {
-        mpq_paths.extend(collect_mpqs(&enus_dir)?);
+    // Icons of the items of any output
+    let mut icon_names = BTreeSet::new();
+    for output in &config.outputs {
+        let mut database = database.clone();
+        println!("Filtering {} items...", database.len());
+        config
+            .output_filter(output)
+            .apply(&mut database, checker.as_ref())?;
+        println!("Filtered to {} items", database.len());
+        icon_names.extend(database.icon_names());
+        write_output(database, output)?;
     }
 
-    if mpq_paths.is_empty() {
-        anyhow::bail!("No MPQ files found in data directory");
+    if let Some(export) = &config.icons.export {
+        println!(
+            "Exporting {} icons to: {}",
+            icon_names.len(),
+            export.dir.display()
+        );
+        icons::export_icons(database.archives(), &icon_names, export)?.print_summary();
     }
 
-    println!("Found {} MPQ files", mpq_paths.len());
+    Ok(())
+}
 
-    // Set up DBC parsing pipeline
-    let mut items = Items::default();
-    let mut item_display_infos = ItemDisplayInfos::default();
-    let mut item_classes = ItemClasses::default();
-    let mut item_sub_classes = ItemSubClasses::default();
-    let mut item_sets = ItemSets::default();
-    let mut spells = Spells::default();
-    let mut spells_desc_vars = SpellDescriptionVars::default();
+fn write_output(database: ItemDatabase, output: &OutputConfig) -> Result<()> {
+    if let Some(parent) = output.path.parent() {
+        fs::create_dir_all(parent)?;
+    }
 
-    {
-        let mut handlers: Vec<&mut dyn parse::Handler> = vec![
-            &mut items,
-            &mut item_display_infos,
-            &mut item_classes,
-            &mut item_sub_classes,
-            &mut item_sets,
-            &mut spells,
-            &mut spells_desc_vars,
-        ];
-        parse_dbcs(&mpq_paths, &mut handlers)?;
+    if output.provenance {
+        let path = output.sidecar("provenance");
+        let json = serde_json::to_string_pretty(database.provenance_map())?;
+        fs::write(&path, json)?;
+        println!("Wrote field provenance to: {}", path.display());
     }
 
-    let item_cache = load_item_cache_data().unwrap_or_default();
-    println!("Loaded {} item cache entries", item_cache.len());
+    if output.sets {
+        let path = output.sidecar("sets");
+        let sets: Vec<_> = database.sets().collect();
+        fs::write(&path, serde_json::to_string_pretty(&sets)?)?;
+        println!("Wrote {} item sets to: {}", sets.len(), path.display());
+    }
+
+    if !database.conflicts().is_empty() {
+        let path = output.sidecar("conflicts");
+        let json = serde_json::to_string_pretty(database.conflicts())?;
+        fs::write(&path, json)?;
+        println!("Wrote conflicting source values to: {}", path.display());
+    }
 
-    let items: Vec<Item> = items
-        .iter_rows()
-        .map(|item_row| {
-            let mut item = Item::from(item_row);
+    let items = database.into_items();
 
-            if let Some(display_info) =
-                item_display_infos.get(ItemDisplayInfoKey::new(item_row.display_info_id))
-            {
-                item.inventory_icon = display_info.inventory_icon[0].clone();
-                item.inventory_icon = format!(
-                    "https://wotlk.evowow.com/static/images/wow/icons/large/{}.jpg",
-                    item.inventory_icon.to_lowercase()
+    match output.format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&items)?;
+            fs::write(&output.path, json)?;
+        }
+        OutputFormat::Ron => {
+            let ron = ron::to_string(&items)?;
+            fs::write(&output.path, ron)?;
+        }
+    }
+
+    println!(
+        "Successfully exported {} items to: {}",
+        items.len(),
+        output.path.display()
+    );
+
+    Ok(())
+}
+
+fn diff(args: DiffArgs) -> Result<()> {
+    let config = match &args.config {
+        Some(path) => ProjectConfig::load(path)?,
+        None => builtin_config()?,
+    };
+    config.validate_locales()?;
+    config.merge.validate()?;
+    let layouts = config.layouts.load()?;
+    let pools = config.reference.load_enchantment_pools()?;
+    let locale = config.locales()?.swap_remove(0);
+    let load = |path: &Path| -> Result<ExportItems> {
+        if path.is_dir() {
+            let database = ItemDatabase::build(
+                &[path.to_path_buf()],
+                &config.merge,
+                &config.icons,
+                &layouts,
+                std::slice::from_ref(&locale),
+                &pools,
+            )?;
+            diff::export_items(database.iter())
+        } else {
+            diff::load_export(path)
+        }
+    };
+    let before = load(&args.before)?;
+    let after = load(&args.after)?;
+
+    let item_diff = ItemDiff::between(&before, &after);
+    println!(
+        "{} added, {} removed, {} changed items",
+        item_diff.added.len(),
+        item_diff.removed.len(),
+        item_diff.changed.len()
+    );
+
+    let json_path = format!("{}.json", args.output);
+    fs::write(&json_path, serde_json::to_string_pretty(&item_diff)?)?;
+    let markdown_path = format!("{}.md", args.output);
+    fs::write(&markdown_path, item_diff.to_markdown())?;
+    println!("Wrote item diff to: {json_path} and {markdown_path}");
+
+    Ok(())
+}
+
+fn history(args: HistoryArgs) -> Result<()> {
+    let store = HistoryStore::open(&args.db)?;
+    match args.query {
+        HistoryQuery::Builds => {
+            for export in store.exports()? {
+                println!(
+                    "build {} ({} items, sources {})",
+                    export.build,
+                    export.item_count,
+                    &export.source_hash[..12]
                 );
             }
-
-            if let Some(item_sub_class) =
-                item_sub_classes.get(item_row.class_id, item_row.subclass_id)
-            {
-                item.subclass = item_sub_class.display_name_lang.en_gb.clone();
+        }
+        HistoryQuery::FirstSeen { item_id } => match store.first_seen(item_id)? {
+            Some(export) => println!("Item {item_id} first seen in build {}", export.build),
+            None => println!("Item {item_id} was never exported"),
+        },
+        HistoryQuery::Show { item_id, build } => {
+            match store.item_at(item_id, build.unwrap_or(u32::MAX))? {
+                Some(item) => println!("{}", serde_json::to_string_pretty(&item)?),
+                None => println!("Item {item_id} not found"),
             }
+        }
+        HistoryQuery::ChangedSince { build, output } => {
+            let item_diff = store.changed_since(build)?;
+            println!(
+                "{} added, {} removed, {} changed items since build {build}",
+                item_diff.added.len(),
+                item_diff.removed.len(),
+                item_diff.changed.len()
+            );
+            let json_path = format!("{output}.json");
+            fs::write(&json_path, serde_json::to_string_pretty(&item_diff)?)?;
+            let markdown_path = format!("{output}.md");
+            fs::write(&markdown_path, item_diff.to_markdown())?;
+            println!("Wrote item diff to: {json_path} and {markdown_path}");
+        }
+    }
+    Ok(())
+}
 
-            if let Some(item_set) = item_sets.find_by_item_ids(&[item_row.id]) {
-                let spells = item_set
-                    .set_spell_id
-                    .iter()
-                    .zip(item_set.set_threshold)
-                    .filter_map(|(s, t)| {
-                        spells.get(SpellKey::new(*s)).map(|spell| {
-                            if let Some(spell_vars) =
-                                spells_desc_vars.get(spell.description_variables_id)
-                            {
-                                (t as u32, spell_vars.variables.clone())
-                            } else {
-                                (t as u32, spell.description_lang.en_gb.clone())
-                            }
-                        })
-                    })
-                    .collect();
-                item.set = Some(ItemSet {
-                    id: item_set.id.id,
-                    name: item_set.name_lang.en_gb.clone(),
-                    spells,
-                })
-            }
+/// Project config for commands that only read the archives, with
+/// `data_dirs` replacing its data directories.
+fn data_config(config: Option<&Path>, data_dirs: Vec<PathBuf>) -> Result<ProjectConfig> {
+    let mut config = match config {
+        Some(path) => ProjectConfig::load(path)?,
+        None => ProjectConfig::default(),
+    };
+    if !data_dirs.is_empty() {
+        config.data_dirs = data_dirs;
+    }
+    if config.data_dirs.is_empty() {
+        bail!("no data directories, pass --data-dir or --config");
+    }
+    config.validate_locales()?;
+    Ok(config)
+}
 
-            // Supplement with data from parsed_items.json
-            if let Some(parsed_data) = item_cache.get(&item.id.to_string()) {
-                if !parsed_data.name.is_empty() {
-                    item.name = parsed_data.name.clone();
-                }
-                if let Some(rarity_type) = &parsed_data.rarity_type {
-                    item.rarity = Rarity::from(rarity_type.clone());
-                }
-                if let Some(stats) = &parsed_data.stats {
-                    if !stats.is_empty() {
-                        item.stats = stats.clone();
-                    }
-                }
-                if let Some(spells) = &parsed_data.spells {
-                    if !spells.is_empty() {
-                        item.spells = spells.clone();
-                    }
-                }
-                if let Some(requires) = &parsed_data.requires {
-                    if !requires.is_empty() {
-                        item.requires = requires.clone();
-                    }
-                }
-                if let Some(level) = parsed_data.requires_level {
-                    item.required_level = level;
-                }
-                if let Some(damage) = &parsed_data.damage {
-                    if !damage.is_empty() {
-                        item.damage = damage.clone();
-                    }
-                }
-                if let Some(added_damage) = &parsed_data.added_damage {
-                    if !added_damage.is_empty() {
-                        item.added_damage = added_damage.clone();
-                    }
-                }
-                if let Some(armor) = &parsed_data.armor {
-                    if !armor.is_empty() {
-                        item.armor = armor.clone();
-                    }
-                }
-                if let Some(dps) = &parsed_data.dps {
-                    if !dps.is_empty() {
-                        item.dps = dps.clone();
-                    }
-                }
-                if let Some(speed) = &parsed_data.speed {
-                    if !speed.is_empty() {
-                        item.speed = speed.clone();
-                    }
-                }
-                if let Some(bonding) = &parsed_data.bonding {
-                    if !bonding.is_empty() {
-                        item.bonding = bonding.clone();
-                    }
-                }
-                if let Some(hands) = &parsed_data.hands {
-                    if !hands.is_empty() {
-                        item.hands = hands.clone();
-                    }
+fn inspect(args: InspectArgs) -> Result<()> {
+    let config = data_config(args.config.as_deref(), args.data_dir)?;
+    let layouts = config.layouts.load()?;
+    let mut chain = ArchiveChain::open(&mpq::collect_data_dirs(
+        &config.data_dirs,
+        &config.locales()?[0],
+    )?)?;
+    let files = chain.resolve(|name| name.to_lowercase().ends_with(".dbc"))?;
+
+    let Some(table) = &args.table else {
+        let found: Vec<_> = files.values().collect();
+        let file_data = chain.read_all(&found)?;
+        let mut unknown = 0;
+        for (file, data) in found.iter().zip(file_data) {
+            let name = &file.entry.name;
+            let (schema, header) = match DbcFile::parse(&data) {
+                Ok(dbc) => {
+                    let schema = match dump::known_layout(&layouts, name, &dbc) {
+                        Some(known) if known.path.is_some() => "layout",
+                        Some(_) => "builtin",
+                        None => "unknown",
+                    };
+                    let header =
+                        format!("{} records, {} fields", dbc.record_count, dbc.field_count);
+                    (schema, header)
                 }
+                Err(err) => ("unknown", format!("unreadable: {err}")),
+            };
+            if schema == "unknown" {
+                unknown += 1;
             }
+            println!(
+                "{name:<48} {schema:<8} {header:<28} {}",
+                chain.path(file.archive).display()
+            );
+        }
+        println!("{} tables, {unknown} without a known schema", found.len());
+        return Ok(());
+    };
 
-            item
-        })
-        .filter(|item| item.required_level <= 60)
-        .collect();
+    let Some(file) = files.values().find(|file| {
+        file.entry.name.eq_ignore_ascii_case(table)
+            || layout::table_name(&file.entry.name).eq_ignore_ascii_case(table)
+    }) else {
+        bail!("no table {table} in the archives");
+    };
+    let name = &file.entry.name;
+    let dbc = DbcFile::parse(&chain.read(file)?)?;
+    println!(
+        "{name} <- {}: {} records of {} fields in {} bytes, {} bytes of strings",
+        chain.path(file.archive).display(),
+        dbc.record_count,
+        dbc.field_count,
+        dbc.record_size,
+        dbc.string_block_size()
+    );
 
-    // Filter out items that exist in item_template.csv (keep only new items)
-    let checker = OriginalItemChecker::new()?;
-    let mut filtered_items = Vec::new();
-    println!("Filtering {} items against CSV data...", items.len());
-    for item in items {
-        if checker.is_item_new(item.id) {
-            filtered_items.push(item);
+    let (table_layout, schema) = dump::table_layout(&layouts, name, &dbc, args.infer)?;
+    if let Some(schema) = &schema {
+        println!("Guessed columns:");
+        for column in &schema.columns {
+            println!(
+                "  {:>3} {:<12} {:<9} {} distinct{}",
+                column.field,
+                column.name,
+                format!("{:?}", column.guess).to_lowercase(),
+                column.distinct,
+                if column.all_zero { ", always 0" } else { "" }
+            );
         }
     }
-    println!(
-        "Filtered to {} new items (not in CSV)",
-        filtered_items.len()
-    );
 
-    let mut items = filtered_items;
-    items.sort_by_key(|i| i.id);
+    if let Some(path) = &args.dbd {
+        let Some(schema) = &schema else {
+            bail!("{name} has a layout, pass --infer to write a guessed one");
+        };
+        let build = config.layouts.build.unwrap_or(WRATH_BUILD);
+        fs::write(path, schema.to_dbd(&format!("3.3.5.{build}")))?;
+        println!("Wrote guessed layout to: {}", path.display());
+    }
 
-    let output_path = match args.format {
-        OutputFormat::Json => {
-            let path = format!("{}.json", args.output);
-            let json = serde_json::to_string_pretty(&items)?;
-            fs::write(&path, json)?;
-            path
+    let bitmasks: HashSet<&str> = schema
+        .iter()
+        .flat_map(|schema| &schema.columns)
+        .filter(|column| column.guess == ColumnGuess::Bitmask)
+        .map(|column| column.name.as_str())
+        .collect();
+    for record in dbc.read(&table_layout)?.iter().take(args.rows) {
+        let fields: Vec<String> = table_layout
+            .fields
+            .iter()
+            .zip(record)
+            .map(|(field, value)| match value {
+                Value::Int(bits) if bitmasks.contains(field.name.as_str()) => {
+                    format!("{}={bits:#x}", field.name)
+                }
+                value => format!("{}={value}", field.name),
+            })
+            .collect();
+        println!("{}", fields.join(" "));
+    }
+
+    Ok(())
+}
+
+fn dump(args: DumpArgs) -> Result<()> {
+    if args.tables.is_empty() && !args.all {
+        bail!("name the tables to export or pass --all");
+    }
+    let config = data_config(args.config.as_deref(), args.data_dir)?;
+    let layouts = config.layouts.load()?;
+    let mut chain = ArchiveChain::open(&mpq::collect_data_dirs(
+        &config.data_dirs,
+        &config.locales()?[0],
+    )?)?;
+    let files = chain.resolve(|name| name.to_lowercase().ends_with(".dbc"))?;
+
+    let mut found = Vec::new();
+    for file in files.values() {
+        if args.all
+            || args.tables.iter().any(|table| {
+                file.entry.name.eq_ignore_ascii_case(table)
+                    || layout::table_name(&file.entry.name).eq_ignore_ascii_case(table)
+            })
+        {
+            found.push(file);
         }
-        OutputFormat::Ron => {
-            let path = format!("{}.ron", args.output);
-            let ron = ron::to_string(&items)?;
-            fs::write(&path, ron)?;
-            path
+    }
+    for table in &args.tables {
+        if !found.iter().any(|file| {
+            layout::table_name(&file.entry.name).eq_ignore_ascii_case(table)
+                || file.entry.name.eq_ignore_ascii_case(table)
+        }) {
+            bail!("no table {table} in the archives");
+        }
+    }
+
+    let mut sqlite = match args.format {
+        DumpFormat::Sqlite => {
+            if let Some(parent) = args.output.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Some(rusqlite::Connection::open(&args.output)?)
+        }
+        DumpFormat::Json | DumpFormat::Csv => {
+            fs::create_dir_all(&args.output)?;
+            None
         }
     };
 
+    let file_data = chain.read_all(&found)?;
+    let mut skipped = 0;
+    for (file, data) in found.iter().zip(file_data) {
+        let name = &file.entry.name;
+        let table = DbcFile::parse(&data).and_then(|dbc| {
+            let (table_layout, _) = dump::table_layout(&layouts, name, &dbc, false)?;
+            Ok(FlatTable::new(&table_layout, &dbc.read(&table_layout)?))
+        });
+        let table = match table {
+            Ok(table) => table,
+            Err(err) => {
+                println!("Skipping {name}: {err:#}");
+                skipped += 1;
+                continue;
+            }
+        };
+        match (&mut sqlite, args.format) {
+            (Some(conn), _) => table.write_sqlite(conn)?,
+            (None, DumpFormat::Csv) => {
+                table.write_csv(&args.output.join(format!("{}.csv", table.name)))?
+            }
+            (None, _) => table.write_json(&args.output.join(format!("{}.json", table.name)))?,
+        }
+        println!("Exported {} rows of {name}", table.rows.len());
+    }
+
     println!(
-        "Successfully exported {} items to: {}",
-        items.len(),
-        output_path
+        "Exported {} tables to: {}",
+        found.len() - skipped,
+        args.output.display()
     );
+    Ok(())
+}
 
+fn mpq_command(args: MpqArgs) -> Result<()> {
+    let config = data_config(args.config.as_deref(), args.data_dir)?;
+    let mut chain = ArchiveChain::open(&mpq::collect_data_dirs(
+        &config.data_dirs,
+        &config.locales()?[0],
+    )?)?;
+
+    match args.command {
+        MpqCommand::List { archive } => {
+            for (index, listing) in chain.list()?.into_iter().enumerate() {
+                let path = chain.path(index);
+                let name = path.file_name().and_then(|name| name.to_str());
+                if let Some(archive) = &archive
+                    && !name
+                        .is_some_and(|name| name.to_lowercase().contains(&archive.to_lowercase()))
+                {
+                    continue;
+                }
+                println!("{} ({} files)", path.display(), listing.len());
+                for entry in listing {
+                    println!(
+                        "  {:>10} {:>10} {:<24} {}",
+                        entry.size,
+                        entry.compressed_size,
+                        mpq::describe_flags(entry.flags),
+                        entry.name
+                    );
+                }
+            }
+        }
+        MpqCommand::Which { path } => {
+            let versions = chain.versions(&path)?;
+            if versions.is_empty() {
+                bail!("no archive contains {path}");
+            }
+            for (index, file) in versions.iter().enumerate() {
+                let role = match (index + 1 == versions.len(), file.is_deletion()) {
+                    (true, true) => "deletes it, the client doesn't load it",
+                    (true, false) => "loaded by the client",
+                    (false, true) => "deletes it",
+                    (false, false) => "overridden",
+                };
+                println!(
+                    "{} ({} bytes): {role}",
+                    chain.path(file.archive).display(),
+                    file.entry.size
+                );
+            }
+        }
+        MpqCommand::Search { pattern } => {
+            let glob = mpq::PathGlob::new(&pattern)?;
+            let files = chain.resolve(|name| glob.matches(name))?;
+            for file in files.values() {
+                println!(
+                    "{:>10} {} <- {}",
+                    file.entry.size,
+                    file.entry.name,
+                    chain.path(file.archive).display()
+                );
+            }
+            println!("{} files match {pattern}", files.len());
+        }
+        MpqCommand::Extract { patterns, output } => {
+            let globs = patterns
+                .iter()
+                .map(|pattern| mpq::PathGlob::new(pattern))
+                .collect::<Result<Vec<_>>>()?;
+            let files = chain.resolve(|name| globs.iter().any(|glob| glob.matches(name)))?;
+            if files.is_empty() {
+                bail!("no loaded file matches {}", patterns.join(", "));
+            }
+            let found: Vec<_> = files.values().collect();
+            for (file, data) in found.iter().zip(chain.read_all(&found)?) {
+                // Keep the archive's directories, never leaving `output`
+                let path = file
+                    .entry
+                    .name
+                    .split(['\\', '/'])
+                    .filter(|part| !matches!(*part, "" | "." | ".."))
+                    .fold(output.clone(), |path, part| path.join(part));
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, data)?;
+                println!("{} -> {}", file.entry.name, path.display());
+            }
+            println!("Extracted {} files to: {}", found.len(), output.display());
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,952 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Deserializer, Serialize, de};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use wow_dbc::wrath_tables::{
+    item::ItemRow, item_display_info::ItemDisplayInfoKey, spell::SpellKey,
+    spell_item_enchantment::SpellItemEnchantmentKey,
+};
+
+use crate::{
+    config::IconConfig,
+    diagnostics::{SourceReport, SourceStatus},
+    locale::LocalizedText,
+    models::{
+        InventoryType, Item, ItemCacheData, ItemCacheRow, ItemClass, ItemSet, Rarity, SocketColor,
+    },
+    mpq::DEFAULT_LOCALE,
+    parse::{DbcTables, item, item_display_info, item_sets, item_sub_class},
+    provenance::{ItemProvenance, Source},
+    random::RandomEnchantments,
+    requirements::Requirement,
+    stats::{StatModifier, StatType},
+    wdb::{WdbItem, WdbItemCache},
+    weapon::DamageSchool,
+};
+
+/// Name of the client tables in precedence lists.
+pub const DBC_SOURCE: &str = "dbc";
+
+/// Non-DBC data merged on top of the client tables, and how to merge it.
+///
+/// Usually read from a TOML file:
+///
+/// ```toml
+/// [[sources]]
+/// name = "beta"
+/// kind = "item_cache"
+/// path = "item_cache.beta.3.5.json"
+/// collected = "2024-11-02"
+///
+/// [[sources]]
+/// name = "fixes"
+/// kind = "override"
+/// path = "overrides.json"
+/// trust = 10
+///
+/// [policy]
+/// conflict = "keep_both"
+///
+/// [policy.precedence]
+/// name = ["fixes", "beta", "dbc"]
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SupplementalSources {
+    /// Sources in the order they were collected, oldest first.
+    pub sources: Vec<SourceConfig>,
+    pub policy: MergePolicy,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    /// Unique name used in precedence lists and conflict reports.
+    pub name: String,
+    pub kind: SourceKind,
+    pub path: PathBuf,
+    /// Higher trust wins when precedence and age don't decide.
+    #[serde(default)]
+    pub trust: i32,
+    /// Date the data was collected, such as `2024-11-02`.
+    #[serde(default)]
+    pub collected: Option<CollectedDate>,
+    /// Client locale of the source's text, such as `deDE`. The source is
+    /// only merged into items of that locale. `wdb` caches know their own,
+    /// other sources without one are taken to be `enUS`.
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// Calendar date a source was collected, written `2024-11-02`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CollectedDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl FromStr for CollectedDate {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let parts: Vec<&str> = text.trim().split('-').collect();
+        let [year, month, day] = parts[..] else {
+            bail!("`{text}` isn't a date like 2024-11-02");
+        };
+        let date = CollectedDate {
+            year: year.parse().with_context(|| format!("year of `{text}`"))?,
+            month: month
+                .parse()
+                .with_context(|| format!("month of `{text}`"))?,
+            day: day.parse().with_context(|| format!("day of `{text}`"))?,
+        };
+        let leap = date.year.is_multiple_of(4)
+            && (!date.year.is_multiple_of(100) || date.year.is_multiple_of(400));
+        let days = match date.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => bail!("`{text}` has no month {}", date.month),
+        };
+        if !(1..=days).contains(&date.day) {
+            bail!("`{text}` has no day {}", date.day);
+        }
+        Ok(date)
+    }
+}
+
+impl<'de> Deserialize<'de> for CollectedDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse()
+            .map_err(|err| de::Error::custom(format!("{err:#}")))
+    }
+}
+
+impl fmt::Display for CollectedDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// JSON item cache keyed by item id, e.g. `data/item_cache.beta.3.5.json`.
+    ItemCache,
+    /// Client `itemcache.wdb` file.
+    Wdb,
+    /// Hand maintained corrections in the item cache format.
+    Override,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct MergePolicy {
+    pub conflict: ConflictStrategy,
+    /// Sources with a lower trust are ignored.
+    pub min_trust: Option<i32>,
+    /// Trust of the client tables.
+    pub dbc_trust: i32,
+    /// Source names per item field, first wins. Sources that aren't listed
+    /// follow in `conflict` order.
+    pub precedence: BTreeMap<String, Vec<String>>,
+}
+
+/// How to pick a value when sources disagree.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// The most recently collected source wins. Undated sources count as
+    /// older than dated ones and later in the list as newer, and the client
+    /// tables are the oldest.
+    #[default]
+    PreferNewest,
+    /// The client tables win wherever they have a value.
+    PreferDbc,
+    /// The most trusted source wins and every disagreement is recorded.
+    KeepBoth,
+}
+
+/// Values of one item field that sources disagreed on.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldConflict {
+    pub field: &'static str,
+    /// Source name and value of every source with a value, winner first.
+    pub values: Vec<(String, serde_json::Value)>,
+}
+
+impl SourceConfig {
+    /// Source named after its path, for sources given on the command line.
+    pub fn from_path(kind: SourceKind, path: PathBuf) -> Self {
+        Self {
+            name: path.display().to_string(),
+            kind,
+            path,
+            trust: 0,
+            collected: None,
+            locale: None,
+        }
+    }
+
+    fn provenance(&self) -> Source {
+        let path = self.path.clone();
+        match self.kind {
+            SourceKind::ItemCache => Source::ItemCache { path },
+            SourceKind::Wdb => Source::Wdb { path },
+            SourceKind::Override => Source::Override { path },
+        }
+    }
+}
+
+impl SupplementalSources {
+    /// Read a merge config. Relative source paths are resolved against the
+    /// directory of the config file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut sources: Self =
+            toml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?;
+        sources.resolve_paths(path.parent().unwrap_or(Path::new("")));
+        sources.validate()?;
+        Ok(sources)
+    }
+
+    /// Make relative source paths relative to `base` instead.
+    pub fn resolve_paths(&mut self, base: &Path) {
+        for source in &mut self.sources {
+            source.path = base.join(&source.path);
+        }
+    }
+
+    /// Check that source names are unique and precedence lists only name
+    /// known sources and item fields.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::from([DBC_SOURCE]);
+        for source in &self.sources {
+            if !names.insert(source.name.as_str()) {
+                bail!("duplicate source name `{}`", source.name);
+            }
+        }
+        for (field, order) in &self.policy.precedence {
+            if !Item::FIELDS.contains(&field.as_str()) {
+                bail!("unknown item field `{field}` in precedence");
+            }
+            if let Some(name) = order.iter().find(|name| !names.contains(name.as_str())) {
+                bail!("unknown source `{name}` in precedence of `{field}`");
+            }
+        }
+        Ok(())
+    }
+
+    /// Read every source above the trust threshold and of `locale` into
+    /// per-item patches, reporting the outcome of each source in `reports`.
+    pub(crate) fn load_patches(
+        &self,
+        tables: &DbcTables,
+        icons: &IconConfig,
+        locale: &str,
+        reports: &mut Vec<SourceReport>,
+    ) -> Result<Vec<LoadedSource<'_>>> {
+        let mut loaded = Vec::new();
+        for (position, config) in self.sources.iter().enumerate() {
+            let report = |status| SourceReport {
+                name: config.name.clone(),
+                path: config.path.clone(),
+                locale: None,
+                status,
+            };
+            if self.policy.min_trust.is_some_and(|min| config.trust < min) {
+                println!("Skipping source {} below minimum trust", config.name);
+                reports.push(report(SourceStatus::BelowTrust));
+                continue;
+            }
+            let source_locale = match (&config.locale, config.kind) {
+                (Some(other), _) => Some(other.as_str()),
+                (None, SourceKind::Wdb) => None,
+                (None, _) => Some(DEFAULT_LOCALE),
+            };
+            if let Some(other) = source_locale
+                && !other.eq_ignore_ascii_case(locale)
+            {
+                reports.push(report(SourceStatus::OtherLocale {
+                    locale: other.to_string(),
+                }));
+                continue;
+            }
+            // A broken source is reported and left out, only strict builds fail
+            let failed = |err: anyhow::Error| {
+                println!("Failed to load source {}: {err:#}", config.name);
+                report(SourceStatus::Failed {
+                    error: format!("{err:#}"),
+                })
+            };
+            let source = config.provenance();
+            let patches: HashMap<i32, ItemPatch> = match config.kind {
+                SourceKind::ItemCache | SourceKind::Override => {
+                    let data = match load_item_cache_data(&config.path) {
+                        Ok(data) => data,
+                        Err(err) => {
+                            reports.push(failed(err));
+                            continue;
+                        }
+                    };
+                    data.iter()
+                        .filter_map(|(id, row)| {
+                            let id = id.parse().ok()?;
+                            Some((id, ItemPatch::from_cache_row(row, tables, &source)))
+                        })
+                        .collect()
+                }
+                SourceKind::Wdb => {
+                    let cache = match WdbItemCache::load(&config.path) {
+                        Ok(cache) => cache,
+                        Err(err) => {
+                            reports.push(failed(err));
+                            continue;
+                        }
+                    };
+                    println!(
+                        "Read {} (build {}, {})",
+                        config.path.display(),
+                        cache.build,
+                        cache.locale
+                    );
+                    if config.locale.is_none() && !cache.locale.eq_ignore_ascii_case(locale) {
+                        reports.push(report(SourceStatus::OtherLocale {
+                            locale: cache.locale,
+                        }));
+                        continue;
+                    }
+                    cache
+                        .items
+                        .iter()
+                        .map(|item| (item.id, ItemPatch::from_wdb(item, tables, icons, &source)))
+                        .collect()
+                }
+            };
+            println!("Loaded {} items from source {}", patches.len(), config.name);
+            reports.push(report(SourceStatus::Loaded {
+                items: patches.len(),
+            }));
+            loaded.push(LoadedSource {
+                rank: SourceRank {
+                    name: &config.name,
+                    trust: config.trust,
+                    collected: config.collected,
+                    position: position + 1,
+                },
+                patches,
+            });
+        }
+        Ok(loaded)
+    }
+}
+
+fn load_item_cache_data(path: &Path) -> Result<ItemCacheData> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let item_cache: ItemCacheData =
+        serde_json::from_str(&content).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(item_cache)
+}
+
+/// A supplemental source with its patches keyed by item id.
+pub(crate) struct LoadedSource<'a> {
+    pub rank: SourceRank<'a>,
+    pub patches: HashMap<i32, ItemPatch>,
+}
+
+/// What the merge policy knows about a source when ordering candidates.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SourceRank<'a> {
+    pub name: &'a str,
+    pub trust: i32,
+    pub collected: Option<CollectedDate>,
+    /// `0` for the client tables, then the order of the source list.
+    pub position: usize,
+}
+
+impl SourceRank<'_> {
+    pub fn dbc(policy: &MergePolicy) -> Self {
+        SourceRank {
+            name: DBC_SOURCE,
+            trust: policy.dbc_trust,
+            collected: None,
+            position: 0,
+        }
+    }
+
+    fn is_dbc(&self) -> bool {
+        self.position == 0
+    }
+
+    fn age(&self) -> (Option<CollectedDate>, usize) {
+        (self.collected, self.position)
+    }
+}
+
+/// Values one source has for an item, `None` where it has nothing.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ItemPatch {
+    pub name: Option<String>,
+    pub class: Option<ItemClass>,
+    pub subclass: Option<String>,
+    pub inventory_icon: Option<String>,
+    pub inventory_type: Option<InventoryType>,
+    pub set: Option<ItemSet>,
+    pub required_level: Option<u32>,
+    pub stats: Option<Vec<String>>,
+    pub spells: Option<Vec<String>>,
+    pub requires: Option<Vec<String>>,
+    pub requirements: Option<Vec<Requirement>>,
+    pub rarity: Option<Rarity>,
+    pub damage: Option<String>,
+    pub added_damage: Option<String>,
+    pub armor: Option<String>,
+    pub speed: Option<String>,
+    pub dps: Option<String>,
+    pub bonding: Option<String>,
+    pub hands: Option<String>,
+    pub sockets: Option<Vec<SocketColor>>,
+    pub socket_bonus: Option<String>,
+    pub random: Option<RandomEnchantments>,
+    /// Source of each field set above.
+    pub provenance: ItemProvenance,
+}
+
+impl ItemPatch {
+    /// Values of an item from the client tables.
+    pub fn from_dbc(tables: &DbcTables, icons: &IconConfig, item_row: &ItemRow) -> Self {
+        let mut patch = ItemPatch::default();
+        let item_source = tables.sources.source(item::FILE_NAME);
+
+        patch.class = Some(ItemClass::from(item_row.class_id));
+        patch.inventory_type = Some(InventoryType::from(item_row.inventory_type));
+        patch.provenance.set("class", item_source.clone());
+        patch.provenance.set("inventory_type", item_source);
+
+        if let Some(icon) = icon_url(tables, icons, item_row.display_info_id) {
+            patch.inventory_icon = Some(icon);
+            patch.provenance.set(
+                "inventory_icon",
+                tables.sources.source(item_display_info::FILE_NAME),
+            );
+        }
+
+        if let Some(subclass) = subclass_name(tables, item_row.class_id, item_row.subclass_id) {
+            patch.subclass = Some(subclass);
+            patch
+                .provenance
+                .set("subclass", tables.sources.source(item_sub_class::FILE_NAME));
+        }
+
+        if let Some(item_set) = tables.item_sets.by_item(item_row.id.id) {
+            let spell_text = tables.spell_text();
+            let spells = item_set
+                .set_spell_id
+                .iter()
+                .zip(item_set.set_threshold)
+                .filter_map(|(s, t)| {
+                    tables
+                        .spells
+                        .get(SpellKey::new(*s))
+                        .map(|spell| (t as u32, spell_text.render_description(spell)))
+                })
+                .collect();
+            patch.set = Some(ItemSet {
+                id: item_set.id.id,
+                name: item_set.name_lang.text().to_string(),
+                spells,
+            });
+            patch
+                .provenance
+                .set("set", tables.sources.source(item_sets::FILE_NAME));
+        }
+
+        patch
+    }
+
+    /// Values of a JSON item cache or override row. Empty values count as
+    /// missing.
+    pub fn from_cache_row(parsed_data: &ItemCacheRow, tables: &DbcTables, source: &Source) -> Self {
+        let mut patch = ItemPatch::default();
+        let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+        let non_empty_list = |value: &Option<Vec<String>>| value.clone().filter(|v| !v.is_empty());
+
+        if !parsed_data.name.is_empty() {
+            patch.name = Some(parsed_data.name.clone());
+        }
+        patch.rarity = parsed_data.rarity_type.clone().map(Rarity::from);
+        patch.stats = non_empty_list(&parsed_data.stats);
+        patch.spells = non_empty_list(&parsed_data.spells);
+        patch.requires = non_empty_list(&parsed_data.requires);
+        patch.requirements = patch
+            .requires
+            .as_ref()
+            .map(|requires| tables.requirements().parse_lines(requires));
+        patch.required_level = parsed_data.requires_level;
+        patch.damage = non_empty(&parsed_data.damage);
+        patch.added_damage = non_empty(&parsed_data.added_damage);
+        patch.armor = non_empty(&parsed_data.armor);
+        patch.dps = non_empty(&parsed_data.dps);
+        patch.speed = non_empty(&parsed_data.speed);
+        patch.bonding = non_empty(&parsed_data.bonding);
+        patch.hands = non_empty(&parsed_data.hands);
+
+        patch.set_provenance(source);
+        patch
+    }
+
+    /// Values of a record from a client `itemcache.wdb`.
+    ///
+    /// Values are formatted like the tooltip lines of the JSON item cache so
+    /// both sources produce the same display strings.
+    pub fn from_wdb(
+        wdb_item: &WdbItem,
+        tables: &DbcTables,
+        icons: &IconConfig,
+        source: &Source,
+    ) -> Self {
+        let mut patch = ItemPatch::default();
+
+        if !wdb_item.name.is_empty() {
+            patch.name = Some(wdb_item.name.clone());
+        }
+        patch.class = Some(ItemClass::from(wdb_item.class_id as i32));
+        patch.subclass = subclass_name(
+            tables,
+            wdb_item.class_id as i32,
+            wdb_item.subclass_id as i32,
+        );
+        patch.inventory_type = Some(InventoryType::from(wdb_item.inventory_type as i32));
+        patch.inventory_icon = icon_url(tables, icons, wdb_item.display_id as i32);
+        patch.rarity = Some(Rarity::from(wdb_item.quality as i32));
+        patch.required_level = Some(wdb_item.required_level);
+        patch.requirements = Some(tables.requirements().resolve_wdb(wdb_item));
+
+        let resistances = (1..)
+            .zip(wdb_item.resistances)
+            .filter(|(_, value)| *value > 0)
+            .filter_map(|(school, value)| {
+                StatType::resistance(school).map(|stat| StatModifier {
+                    stat,
+                    value: value as i32,
+                })
+            });
+        let stats: Vec<String> = wdb_item
+            .stats
+            .iter()
+            .filter_map(|stat| {
+                StatType::from_item_mod(stat.stat_type).map(|kind| StatModifier {
+                    stat: kind,
+                    value: stat.value,
+                })
+            })
+            .chain(resistances)
+            .map(|modifier| modifier.display())
+            .collect();
+        if !stats.is_empty() {
+            patch.stats = Some(stats);
+        }
+
+        let spell_text = tables.spell_text();
+        let spells: Vec<String> = wdb_item
+            .spells
+            .iter()
+            .filter_map(|wdb_spell| {
+                let spell = tables.spells.get(SpellKey::new(wdb_spell.id))?;
+                let prefix = match wdb_spell.trigger {
+                    1 => "Equip",
+                    2 => "Chance on hit",
+                    _ => "Use",
+                };
+                Some(format!(
+                    "{prefix}: {}",
+                    spell_text.render_description(spell)
+                ))
+            })
+            .collect();
+        if !spells.is_empty() {
+            patch.spells = Some(spells);
+        }
+
+        let mut damages = wdb_item.damages.iter();
+        if let Some(damage) = damages.next() {
+            patch.damage = Some(format!("{} - {} Damage", damage.min, damage.max));
+        }
+        if let Some(damage) = damages.next() {
+            patch.added_damage = Some(format!(
+                "+{} - {} {} Damage",
+                damage.min,
+                damage.max,
+                DamageSchool::from_id(damage.school).name()
+            ));
+        }
+        if wdb_item.delay > 0 {
+            let speed = wdb_item.delay as f32 / 1000.0;
+            patch.speed = Some(format!("Speed {speed:.2}"));
+            if !wdb_item.damages.is_empty() {
+                let total: f32 = wdb_item
+                    .damages
+                    .iter()
+                    .map(|damage| (damage.min + damage.max) / 2.0)
+                    .sum();
+                patch.dps = Some(format!("({:.1} damage per second)", total / speed));
+            }
+        }
+        if wdb_item.armor > 0 {
+            patch.armor = Some(format!("{} Armor", wdb_item.armor));
+        }
+
+        let bonding = match wdb_item.bonding {
+            1 => "Binds when picked up",
+            2 => "Binds when equipped",
+            3 => "Binds when used",
+            4 | 5 => "Quest Item",
+            _ => "",
+        };
+        if !bonding.is_empty() {
+            patch.bonding = Some(bonding.to_string());
+        }
+
+        let hands = match wdb_item.inventory_type {
+            13 => "One-Hand",
+            17 => "Two-Hand",
+            21 => "Main Hand",
+            22 => "Off Hand",
+            _ => "",
+        };
+        if !hands.is_empty() {
+            patch.hands = Some(hands.to_string());
+        }
+
+        let sockets: Vec<SocketColor> = wdb_item
+            .sockets
+            .iter()
+            .filter_map(|socket| SocketColor::from_id(socket.color))
+            .collect();
+        if !sockets.is_empty() {
+            patch.sockets = Some(sockets);
+            patch.socket_bonus = tables
+                .enchantments
+                .get(SpellItemEnchantmentKey::new(wdb_item.socket_bonus as i32))
+                .map(|bonus| format!("Socket Bonus: {}", bonus.name_lang.text()));
+        }
+
+        // Variants are resolved once the item's rarity and slot are merged
+        if wdb_item.random_property > 0 || wdb_item.random_suffix > 0 {
+            patch.random = Some(RandomEnchantments {
+                property_pool: (wdb_item.random_property > 0).then_some(wdb_item.random_property),
+                suffix_pool: (wdb_item.random_suffix > 0).then_some(wdb_item.random_suffix),
+                item_level: wdb_item.item_level,
+                variants: Vec::new(),
+            });
+        }
+
+        patch.set_provenance(source);
+        patch
+    }
+
+    /// Record `source` for every field this patch has a value for.
+    fn set_provenance(&mut self, source: &Source) {
+        let present = [
+            ("name", self.name.is_some()),
+            ("class", self.class.is_some()),
+            ("subclass", self.subclass.is_some()),
+            ("inventory_icon", self.inventory_icon.is_some()),
+            ("inventory_type", self.inventory_type.is_some()),
+            ("set", self.set.is_some()),
+            ("required_level", self.required_level.is_some()),
+            ("stats", self.stats.is_some()),
+            ("spells", self.spells.is_some()),
+            ("requires", self.requires.is_some()),
+            ("requirements", self.requirements.is_some()),
+            ("rarity", self.rarity.is_some()),
+            ("damage", self.damage.is_some()),
+            ("added_damage", self.added_damage.is_some()),
+            ("armor", self.armor.is_some()),
+            ("speed", self.speed.is_some()),
+            ("dps", self.dps.is_some()),
+            ("bonding", self.bonding.is_some()),
+            ("hands", self.hands.is_some()),
+            ("sockets", self.sockets.is_some()),
+            ("socket_bonus", self.socket_bonus.is_some()),
+            ("random", self.random.is_some()),
+        ];
+        for (field, _) in present.into_iter().filter(|(_, present)| *present) {
+            self.provenance.set(field, source.clone());
+        }
+    }
+}
+
+fn icon_url(tables: &DbcTables, icons: &IconConfig, display_info_id: i32) -> Option<String> {
+    let display_info = tables
+        .item_display_infos
+        .get(ItemDisplayInfoKey::new(display_info_id))?;
+    Some(icons.render(&display_info.inventory_icon[0]))
+}
+
+fn subclass_name(tables: &DbcTables, class_id: i32, subclass_id: i32) -> Option<String> {
+    tables
+        .item_sub_classes
+        .get(class_id, subclass_id)
+        .map(|row| row.display_name_lang.text().to_string())
+}
+
+impl MergePolicy {
+    /// Apply the patches of all sources to `item`, field by field.
+    /// Disagreements are returned when the strategy is `keep_both`.
+    pub(crate) fn apply(
+        &self,
+        item: &mut Item,
+        candidates: &[(SourceRank, &ItemPatch)],
+        provenance: &mut ItemProvenance,
+    ) -> Vec<FieldConflict> {
+        let mut conflicts = Vec::new();
+
+        macro_rules! merge {
+            ($field:ident) => {
+                merge!($field, |value| value)
+            };
+            ($field:ident, $wrap:expr) => {
+                let field = stringify!($field);
+                let values = candidates
+                    .iter()
+                    .filter_map(|(rank, patch)| patch.$field.as_ref().map(|v| (rank, *patch, v)))
+                    .collect();
+                if let Some((patch, value)) = self.pick(field, values, &mut conflicts) {
+                    item.$field = $wrap(value.clone());
+                    if let Some(source) = patch.provenance.get(field) {
+                        provenance.set(field, source.clone());
+                    }
+                }
+            };
+        }
+
+        merge!(name);
+        merge!(class);
+        merge!(subclass);
+        merge!(inventory_icon);
+        merge!(inventory_type);
+        merge!(set, Some);
+        merge!(required_level);
+        merge!(stats);
+        merge!(spells);
+        merge!(requires);
+        merge!(requirements);
+        merge!(rarity);
+        merge!(damage);
+        merge!(added_damage);
+        merge!(armor);
+        merge!(speed);
+        merge!(dps);
+        merge!(bonding);
+        merge!(hands);
+        merge!(sockets);
+        merge!(socket_bonus);
+        merge!(random, Some);
+
+        conflicts
+    }
+
+    /// Choose the winning value of `field`, recording a conflict if sources
+    /// disagree under `keep_both`.
+    fn pick<'p, T: Serialize + PartialEq>(
+        &self,
+        field: &'static str,
+        mut values: Vec<(&SourceRank, &'p ItemPatch, &'p T)>,
+        conflicts: &mut Vec<FieldConflict>,
+    ) -> Option<(&'p ItemPatch, &'p T)> {
+        values.sort_by(|(a, ..), (b, ..)| self.compare(field, a, b));
+        let (_, patch, winner) = *values.first()?;
+
+        if self.conflict == ConflictStrategy::KeepBoth
+            && values.iter().any(|(_, _, value)| *value != winner)
+        {
+            conflicts.push(FieldConflict {
+                field,
+                values: values
+                    .iter()
+                    .map(|(rank, _, value)| {
+                        let value = serde_json::to_value(value).unwrap_or_default();
+                        (rank.name.to_string(), value)
+                    })
+                    .collect(),
+            });
+        }
+
+        Some((patch, winner))
+    }
+
+    /// Order two sources for `field`, the winner first.
+    fn compare(&self, field: &str, a: &SourceRank, b: &SourceRank) -> Ordering {
+        let listed = |rank: &SourceRank| {
+            self.precedence
+                .get(field)
+                .and_then(|order| order.iter().position(|name| name == rank.name))
+                .unwrap_or(usize::MAX)
+        };
+        let newest = || b.age().cmp(&a.age());
+        let trusted = || b.trust.cmp(&a.trust);
+
+        listed(a).cmp(&listed(b)).then_with(|| match self.conflict {
+            ConflictStrategy::PreferNewest => newest().then_with(trusted),
+            ConflictStrategy::PreferDbc => b
+                .is_dbc()
+                .cmp(&a.is_dbc())
+                .then_with(trusted)
+                .then_with(newest),
+            ConflictStrategy::KeepBoth => trusted().then_with(newest),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> CollectedDate {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn collected_dates_compare_as_dates() {
+        assert!(date("2024-9-1") < date("2024-10-01"));
+        assert_eq!(date("2024-9-1"), date("2024-09-01"));
+        assert_eq!(date("2024-9-1").to_string(), "2024-09-01");
+        assert_eq!(date("2024-02-29").day, 29);
+    }
+
+    #[test]
+    fn invalid_collected_dates_are_rejected() {
+        for text in [
+            "",
+            "2024-11",
+            "2024-13-01",
+            "2023-02-29",
+            "2024-04-31",
+            "yesterday",
+        ] {
+            assert!(text.parse::<CollectedDate>().is_err(), "{text}");
+        }
+        let config = r#"
+            [[sources]]
+            name = "beta"
+            kind = "item_cache"
+            path = "item_cache.json"
+            collected = "2024-11-31"
+        "#;
+        let err = toml::from_str::<SupplementalSources>(config).unwrap_err();
+        assert!(err.to_string().contains("no day 31"), "{err}");
+    }
+
+    #[test]
+    fn wdb_items_keep_their_sockets() {
+        use crate::{
+            locale::localized,
+            parse::tests::{blank_row, parse_table},
+            wdb::WdbSocket,
+        };
+        use wow_dbc::wrath_tables::spell_item_enchantment::SpellItemEnchantment;
+
+        let mut bonus = blank_row::<SpellItemEnchantment>(38);
+        bonus.id = SpellItemEnchantmentKey::new(3312);
+        bonus.name_lang = localized("+8 Strength");
+        let mut tables = DbcTables::default();
+        parse_table(
+            &mut tables.enchantments,
+            &SpellItemEnchantment { rows: vec![bonus] },
+        );
+
+        let socket = |color| WdbSocket { color, content: 0 };
+        let wdb_item = WdbItem {
+            id: 1,
+            name: "Helm".to_string(),
+            sockets: vec![socket(1), socket(16), socket(4)],
+            socket_bonus: 3312,
+            ..Default::default()
+        };
+        let source = Source::Wdb {
+            path: PathBuf::from("itemcache.wdb"),
+        };
+        let patch = ItemPatch::from_wdb(&wdb_item, &tables, &IconConfig::default(), &source);
+        assert_eq!(
+            patch.sockets,
+            Some(vec![SocketColor::Meta, SocketColor::Yellow])
+        );
+        assert_eq!(
+            patch.socket_bonus.as_deref(),
+            Some("Socket Bonus: +8 Strength")
+        );
+
+        let rank = SourceRank {
+            name: "itemcache",
+            trust: 0,
+            collected: None,
+            position: 0,
+        };
+        let mut item = Item::from(&blank_row::<wow_dbc::wrath_tables::item::Item>(8));
+        let mut provenance = ItemProvenance::default();
+        MergePolicy::default().apply(&mut item, &[(rank, &patch)], &mut provenance);
+        assert_eq!(item.sockets, [SocketColor::Meta, SocketColor::Yellow]);
+        assert_eq!(item.socket_bonus, "Socket Bonus: +8 Strength");
+        assert_eq!(provenance.get("sockets"), Some(&source));
+    }
+
+    #[test]
+    fn unreadable_sources_are_reported_and_skipped() {
+        let dir = std::env::temp_dir().join(format!("sources-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("itemcache.wdb"), b"not a cache").unwrap();
+        let sources = SupplementalSources {
+            sources: vec![
+                SourceConfig::from_path(SourceKind::ItemCache, dir.join("missing.json")),
+                SourceConfig::from_path(SourceKind::Wdb, dir.join("itemcache.wdb")),
+            ],
+            ..Default::default()
+        };
+
+        let mut reports = Vec::new();
+        let loaded = sources.load_patches(
+            &DbcTables::default(),
+            &IconConfig::default(),
+            "enUS",
+            &mut reports,
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(loaded.unwrap().is_empty());
+        assert_eq!(reports.len(), 2);
+        for report in &reports {
+            assert!(report.is_problem());
+            let path = report.path.to_string_lossy();
+            assert!(
+                matches!(&report.status, SourceStatus::Failed { error } if error.contains(&*path)),
+                "{report:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn newer_source_wins_by_date() {
+        let policy = MergePolicy::default();
+        let rank = |name, collected: &str, position| SourceRank {
+            name,
+            trust: 0,
+            collected: Some(date(collected)),
+            position,
+        };
+        // Listed later, but collected earlier.
+        let (older, newer) = (rank("older", "2024-9-1", 2), rank("newer", "2024-10-01", 1));
+        assert_eq!(policy.compare("name", &newer, &older), Ordering::Less);
+        assert_eq!(policy.compare("name", &older, &newer), Ordering::Greater);
+    }
+}
@@ -1,8 +1,15 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use wow_dbc::wrath_tables::item::ItemRow;
 
-#[derive(Serialize, Clone, Debug)]
+use crate::{
+    random::RandomEnchantments,
+    requirements::{self, Requirement},
+    stats::{ItemStat, StatType},
+    weapon::WeaponStats,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Item {
     pub id: i32,
     pub name: String,
@@ -13,23 +20,53 @@ pub struct Item {
     pub set: Option<ItemSet>,
     pub required_level: u32,
     pub stats: Vec<String>,
+    /// `stats` and `Equip:` spell lines parsed into typed modifiers.
+    pub stat_modifiers: Vec<ItemStat>,
     pub spells: Vec<String>,
     pub requires: Vec<String>,
+    /// `required_level` and `requires` as typed entries, including class and
+    /// race restrictions known from client caches.
+    pub requirements: Vec<Requirement>,
     pub rarity: Rarity,
     pub damage: String,
     pub added_damage: String,
     pub armor: String,
     pub speed: String,
     pub dps: String,
+    /// `damage`, `added_damage`, `speed` and `dps` parsed into numbers.
+    pub weapon: Option<WeaponStats>,
     pub bonding: String,
     pub hands: String,
+    /// Colors of the item's gem sockets, in slot order.
+    #[serde(default)]
+    pub sockets: Vec<SocketColor>,
+    /// Bonus for matching every socket, such as `Socket Bonus: +4 Stamina`.
+    #[serde(default)]
+    pub socket_bonus: String,
+    /// Random property or suffix pools the item rolls from and, given the
+    /// server's pool mapping, the variants they hold.
+    #[serde(default)]
+    pub random: Option<RandomEnchantments>,
+    /// Text of the item in the other loaded client locales, keyed by locale
+    /// such as `deDE`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub localized: BTreeMap<String, LocalizedItem>,
+}
+
+/// The fields of an item a client locale translates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LocalizedItem {
+    pub name: String,
+    pub subclass: String,
+    pub set: Option<ItemSet>,
+    pub spells: Vec<String>,
 }
 
 impl From<&ItemRow> for Item {
     fn from(item: &ItemRow) -> Self {
         Item {
             id: item.id.id,
-            name: "<unknown>".to_string(), // Placeholder until we cross-reference with string data
+            name: Item::UNKNOWN_NAME.to_string(), // Placeholder until we cross-reference with string data
             class: ItemClass::from(item.class_id),
             subclass: "".to_string(),
             inventory_icon: String::new(),
@@ -37,29 +74,155 @@ impl From<&ItemRow> for Item {
             set: None,
             required_level: 0,
             stats: Vec::new(),
+            stat_modifiers: Vec::new(),
             spells: Vec::new(),
             requires: Vec::new(),
+            requirements: Vec::new(),
             rarity: Rarity::Common,
             damage: "".to_string(),
             added_damage: "".to_string(),
             armor: "".to_string(),
             speed: "".to_string(),
             dps: "".to_string(),
+            weapon: None,
             bonding: "".to_string(),
             hands: "".to_string(),
+            sockets: Vec::new(),
+            socket_bonus: String::new(),
+            random: None,
+            localized: BTreeMap::new(),
         }
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
+impl Item {
+    /// Names of all serialized fields but `localized`, in declaration order.
+    pub const FIELDS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "class",
+        "subclass",
+        "inventory_icon",
+        "inventory_type",
+        "set",
+        "required_level",
+        "stats",
+        "stat_modifiers",
+        "spells",
+        "requires",
+        "requirements",
+        "rarity",
+        "damage",
+        "added_damage",
+        "armor",
+        "speed",
+        "dps",
+        "weapon",
+        "bonding",
+        "hands",
+        "sockets",
+        "socket_bonus",
+        "random",
+    ];
+
+    /// The fields a client locale translates.
+    pub fn localized_text(&self) -> LocalizedItem {
+        LocalizedItem {
+            name: self.name.clone(),
+            subclass: self.subclass.clone(),
+            set: self.set.clone(),
+            spells: self.spells.clone(),
+        }
+    }
+
+    /// Name of items no source had a name for.
+    pub const UNKNOWN_NAME: &'static str = "<unknown>";
+
+    /// Whether a source provided a name for this item.
+    pub fn has_name(&self) -> bool {
+        !self.name.is_empty() && self.name != Item::UNKNOWN_NAME
+    }
+
+    /// Sum of all typed modifiers of `stat`.
+    pub fn stat_total(&self, stat: StatType) -> i32 {
+        self.stat_modifiers
+            .iter()
+            .filter_map(|item_stat| match item_stat {
+                ItemStat::Modifier(modifier) if modifier.stat == stat => Some(modifier.value),
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Whether a character of `level` and ChrClasses `class_id` meets the
+    /// level and class requirements of this item.
+    pub fn usable_by(&self, level: u32, class_id: i32) -> bool {
+        self.requirements
+            .iter()
+            .all(|requirement| match requirement {
+                Requirement::Level(required) => level >= *required,
+                Requirement::Classes { mask, .. } => mask & requirements::bit(class_id) != 0,
+                _ => true,
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ItemSet {
     pub name: String,
     pub id: i32,
     pub spells: Vec<(u32, String)>,
 }
 
+/// An ItemSet.dbc entry with all its pieces, exported next to the items.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ItemSetDetails {
+    pub id: i32,
+    pub name: String,
+    /// Member item ids in table order, including those Item.dbc lacks.
+    pub items: Vec<i32>,
+    /// Members without an Item.dbc row.
+    pub missing_items: Vec<i32>,
+    /// Bonuses ordered by the pieces they need.
+    pub bonuses: Vec<SetBonus>,
+    /// Skill needed for the bonuses, such as the profession of a crafted
+    /// set.
+    pub required_skill: Option<Requirement>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SetBonus {
+    /// Equipped pieces needed.
+    pub pieces: u32,
+    pub spell_id: i32,
+    /// Rendered spell description, empty if Spell.dbc lacks the spell.
+    pub text: String,
+}
+
+/// Gem socket color, the `SOCKET_COLOR_*` bit of the client.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketColor {
+    Meta,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl SocketColor {
+    pub fn from_id(color: u32) -> Option<Self> {
+        match color {
+            1 => Some(SocketColor::Meta),
+            2 => Some(SocketColor::Red),
+            4 => Some(SocketColor::Yellow),
+            8 => Some(SocketColor::Blue),
+            _ => None,
+        }
+    }
+}
+
 #[repr(u32)]
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum InventoryType {
     None = 0,
     Head = 1,
@@ -130,7 +293,7 @@ impl From<i32> for InventoryType {
 }
 
 #[repr(u32)]
-#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ItemClass {
     Consumable = 0,
     Container = 1,
@@ -176,7 +339,7 @@ impl From<i32> for ItemClass {
 }
 
 #[repr(u8)]
-#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Rarity {
     Common = 0,
     Uncommon = 1,
@@ -199,14 +362,15 @@ impl From<String> for Rarity {
     }
 }
 
+/// Client item quality, `0` poor and `1` common both being common.
 impl From<i32> for Rarity {
     fn from(value: i32) -> Self {
         match value {
-            0 => Rarity::Common,
-            1 => Rarity::Uncommon,
-            2 => Rarity::Rare,
-            3 => Rarity::Epic,
-            4 => Rarity::Legendary,
+            0 | 1 => Rarity::Common,
+            2 => Rarity::Uncommon,
+            3 => Rarity::Rare,
+            4 => Rarity::Epic,
+            5 => Rarity::Legendary,
             _ => Rarity::Custom(value),
         }
     }
@@ -0,0 +1,488 @@
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap, hash_map::Entry},
+    fs,
+    path::{Path, PathBuf},
+};
+use wow_mpq::{Archive, FileEntry};
+
+/// Locale whose archives are loaded from `Data/<locale>` unless another is
+/// chosen.
+pub const DEFAULT_LOCALE: &str = "enUS";
+
+/// Locales 3.3.5 clients were released in, as their `Data/<locale>`
+/// directories are named.
+pub const CLIENT_LOCALES: [&str; 10] = [
+    "enUS", "enGB", "koKR", "frFR", "deDE", "zhCN", "zhTW", "esES", "esMX", "ruRU",
+];
+
+/// `MPQ_FILE_DELETE_MARKER`, set on patch entries that delete a file.
+const FILE_DELETE_MARKER: u32 = 0x0200_0000;
+
+/// Names of the `MPQ_FILE_*` flags worth showing.
+const FILE_FLAGS: &[(u32, &str)] = &[
+    (0x0000_0100, "imploded"),
+    (0x0000_0200, "compressed"),
+    (0x0001_0000, "encrypted"),
+    (0x0002_0000, "fix-key"),
+    (0x0010_0000, "patch"),
+    (0x0100_0000, "single-unit"),
+    (FILE_DELETE_MARKER, "deleted"),
+    (0x0400_0000, "sector-crc"),
+];
+
+/// Archive groups in the order the 3.3.5 client loads them. Files of later
+/// groups replace those of earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Tier {
+    Common,
+    Common2,
+    Expansion,
+    Lichking,
+    Locale,
+    ExpansionLocale,
+    LichkingLocale,
+    Patch,
+    Patch2,
+    Patch3,
+    LocalePatch,
+    LocalePatch2,
+    LocalePatch3,
+    /// `patch-4.MPQ` .. `patch-9.MPQ`, `patch-A.MPQ` .. `patch-Z.MPQ`
+    UserPatch,
+    /// `patch-<locale>-4.MPQ` .. `patch-<locale>-Z.MPQ`
+    UserLocalePatch,
+}
+
+/// Load priority of an archive, higher priorities override lower ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArchivePriority {
+    tier: Tier,
+    /// Letter or digit of user patches, `4`..`9` then `a`..`z`.
+    letter: Option<char>,
+}
+
+/// Priority of an archive file name such as `patch-enUS-3.MPQ`, or `None` if
+/// the client doesn't load it (speech archives, backups, ...).
+pub fn archive_priority(file_name: &str, locale: &str) -> Option<ArchivePriority> {
+    let lower = file_name.to_lowercase();
+    let stem = lower.strip_suffix(".mpq")?;
+    let locale = locale.to_lowercase();
+
+    let tier = |tier| Some(ArchivePriority { tier, letter: None });
+    let user_patch = |tier, letter: &str| {
+        let mut chars = letter.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c @ ('4'..='9' | 'a'..='z')), None) => Some(ArchivePriority {
+                tier,
+                letter: Some(c),
+            }),
+            _ => None,
+        }
+    };
+
+    match stem {
+        "common" => return tier(Tier::Common),
+        "common-2" => return tier(Tier::Common2),
+        "expansion" => return tier(Tier::Expansion),
+        "lichking" => return tier(Tier::Lichking),
+        "patch" => return tier(Tier::Patch),
+        "patch-2" => return tier(Tier::Patch2),
+        "patch-3" => return tier(Tier::Patch3),
+        _ => {}
+    }
+    if let Some(rest) = stem.strip_suffix(&locale) {
+        return match rest {
+            "locale-" => tier(Tier::Locale),
+            "expansion-locale-" => tier(Tier::ExpansionLocale),
+            "lichking-locale-" => tier(Tier::LichkingLocale),
+            "patch-" => tier(Tier::LocalePatch),
+            _ => None,
+        };
+    }
+    if let Some(rest) = stem.strip_prefix(&format!("patch-{locale}-")) {
+        return match rest {
+            "2" => tier(Tier::LocalePatch2),
+            "3" => tier(Tier::LocalePatch3),
+            letter => user_patch(Tier::UserLocalePatch, letter),
+        };
+    }
+    user_patch(Tier::UserPatch, stem.strip_prefix("patch-")?)
+}
+
+/// Spelling of `locale` in [`CLIENT_LOCALES`], ignoring case.
+pub fn client_locale(locale: &str) -> Option<&'static str> {
+    CLIENT_LOCALES
+        .into_iter()
+        .find(|known| known.eq_ignore_ascii_case(locale))
+}
+
+/// Client locales with a directory in any of `data_dirs`, in
+/// [`CLIENT_LOCALES`] order.
+pub fn data_locales(data_dirs: &[PathBuf]) -> Result<Vec<&'static str>> {
+    let mut found = Vec::new();
+    for data_dir in data_dirs.iter().filter(|dir| dir.is_dir()) {
+        for entry in fs::read_dir(data_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir()
+                && let Some(locale) = entry.file_name().to_str().and_then(client_locale)
+            {
+                found.push(locale);
+            }
+        }
+    }
+    Ok(CLIENT_LOCALES
+        .into_iter()
+        .filter(|locale| found.contains(locale))
+        .collect())
+}
+
+/// Collect the MPQ files of WoW `Data` directories and their `locale`
+/// directories, lowest priority first. Later data directories win between
+/// archives of the same priority.
+pub fn collect_data_dirs(data_dirs: &[PathBuf], locale: &str) -> Result<Vec<PathBuf>> {
+    let mut archives = Vec::new();
+    for (index, data_dir) in data_dirs.iter().enumerate() {
+        println!("Scanning for {locale} MPQ files in: {}", data_dir.display());
+        for dir in [data_dir.clone(), data_dir.join(locale)] {
+            for path in list_mpqs(&dir)? {
+                let name = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default();
+                match archive_priority(name, locale) {
+                    Some(priority) => archives.push((priority, index, path)),
+                    // Speech archives only hold audio
+                    None if name.to_lowercase().contains("speech") => {}
+                    None => println!("Skipping {}: not loaded by the client", path.display()),
+                }
+            }
+        }
+    }
+
+    if archives.is_empty() {
+        anyhow::bail!("No MPQ files found in data directory");
+    }
+
+    archives.sort();
+    println!("Found {} MPQ files, in load order:", archives.len());
+    for (_, _, path) in &archives {
+        println!("  {}", path.display());
+    }
+    Ok(archives.into_iter().map(|(_, _, path)| path).collect())
+}
+
+fn list_mpqs(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|s| s.eq_ignore_ascii_case("mpq"))
+        {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// The version of a file the client loads.
+#[derive(Debug)]
+pub struct ResolvedFile {
+    /// Index of the archive in [`ArchiveChain::paths`].
+    pub archive: usize,
+    pub entry: FileEntry,
+}
+
+/// Open archives in load order, resolving each file to the archive the client
+/// takes it from. `wow_mpq` reads archives through its own file handles, so
+/// they are read rather than memory mapped.
+pub struct ArchiveChain {
+    paths: Vec<PathBuf>,
+    archives: Vec<Archive>,
+}
+
+impl ArchiveChain {
+    /// Open `paths`, lowest priority first.
+    pub fn open(paths: &[PathBuf]) -> Result<Self> {
+        let archives = paths
+            .par_iter()
+            .map(Archive::open)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            paths: paths.to_vec(),
+            archives,
+        })
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn path(&self, archive: usize) -> &Path {
+        &self.paths[archive]
+    }
+
+    /// Winning version of every file whose name matches `wanted`, keyed by
+    /// lowercase name. A delete marker or an empty entry in a higher
+    /// priority archive removes the file.
+    pub fn resolve(
+        &mut self,
+        wanted: impl Fn(&str) -> bool,
+    ) -> Result<BTreeMap<String, ResolvedFile>> {
+        let mut files: HashMap<String, Option<ResolvedFile>> = HashMap::new();
+        for (archive, listing) in self.list()?.into_iter().enumerate() {
+            for entry in listing {
+                if !wanted(&entry.name) {
+                    continue;
+                }
+                let key = entry.name.to_lowercase();
+                let file = ResolvedFile { archive, entry };
+                if file.is_deletion() {
+                    if files.get(&key).is_some_and(Option::is_some) {
+                        println!(
+                            "{} deleted by {}",
+                            file.entry.name,
+                            self.paths[archive].display()
+                        );
+                    }
+                    files.insert(key, None);
+                } else {
+                    files.insert(key, Some(file));
+                }
+            }
+        }
+        Ok(files
+            .into_iter()
+            .filter_map(|(name, file)| Some((name, file?)))
+            .collect())
+    }
+
+    /// Files of every archive in load order, listed concurrently.
+    pub fn list(&mut self) -> Result<Vec<Vec<FileEntry>>> {
+        Ok(self
+            .archives
+            .par_iter_mut()
+            .map(Archive::list)
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Every archive listing `name`, lowest priority first. The last one is
+    /// what the client loads, unless it deletes the file.
+    pub fn versions(&mut self, name: &str) -> Result<Vec<ResolvedFile>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .enumerate()
+            .flat_map(|(archive, listing)| {
+                listing
+                    .into_iter()
+                    .filter(|entry| entry.name.eq_ignore_ascii_case(name))
+                    .map(move |entry| ResolvedFile { archive, entry })
+            })
+            .collect())
+    }
+
+    pub fn read(&mut self, file: &ResolvedFile) -> Result<Vec<u8>> {
+        Ok(self.archives[file.archive].read_file(&file.entry.name)?)
+    }
+
+    /// Read and decompress `files` concurrently, returning their data in the
+    /// same order, or the first error.
+    pub fn read_all(&self, files: &[&ResolvedFile]) -> Result<Vec<Vec<u8>>> {
+        self.read_each(files).into_iter().collect()
+    }
+
+    /// Read and decompress `files` concurrently, returning the data or error
+    /// of each in the same order. Archive handles can't be shared between
+    /// threads, so each worker opens an archive the first time it reads from
+    /// it.
+    pub fn read_each(&self, files: &[&ResolvedFile]) -> Vec<Result<Vec<u8>>> {
+        files
+            .par_iter()
+            .map_init(
+                HashMap::new,
+                |opened: &mut HashMap<usize, Archive>, file| {
+                    let path = self.path(file.archive);
+                    let mpq = match opened.entry(file.archive) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => entry.insert(
+                            Archive::open(path)
+                                .with_context(|| format!("opening {}", path.display()))?,
+                        ),
+                    };
+                    let data = mpq.read_file(&file.entry.name).with_context(|| {
+                        format!("reading {} from {}", file.entry.name, path.display())
+                    })?;
+                    Ok(data)
+                },
+            )
+            .collect()
+    }
+}
+
+impl ResolvedFile {
+    /// Whether this entry removes the file instead of providing it.
+    pub fn is_deletion(&self) -> bool {
+        self.entry.flags & FILE_DELETE_MARKER != 0 || self.entry.size == 0
+    }
+}
+
+/// Names of the flags set on an archive entry, such as `compressed,encrypted`.
+pub fn describe_flags(flags: u32) -> String {
+    FILE_FLAGS
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Glob over archive paths such as `DBFilesClient\*.dbc`, ignoring case and
+/// accepting `/` for `\`.
+#[derive(Debug, Clone)]
+pub struct PathGlob(Pattern);
+
+impl PathGlob {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern = Pattern::new(&pattern.replace('\\', "/"))
+            .with_context(|| format!("invalid pattern `{pattern}`"))?;
+        Ok(Self(pattern))
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        self.0.matches_with(&name.replace('\\', "/"), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wow_mpq::ArchiveBuilder;
+
+    #[test]
+    fn archives_sort_in_client_load_order() {
+        let mut names = vec![
+            "patch-enUS-Z.MPQ",
+            "patch-A.MPQ",
+            "patch-enUS-3.MPQ",
+            "patch.MPQ",
+            "lichking-locale-enUS.MPQ",
+            "patch-9.MPQ",
+            "common.MPQ",
+            "patch-enUS.MPQ",
+            "expansion.MPQ",
+            "locale-enUS.MPQ",
+            "patch-3.MPQ",
+            "common-2.MPQ",
+            "patch-4.MPQ",
+            "lichking.MPQ",
+            "patch-enUS-2.MPQ",
+            "expansion-locale-enUS.MPQ",
+            "patch-2.MPQ",
+        ];
+        names.sort_by_key(|name| archive_priority(name, "enUS").unwrap());
+        assert_eq!(
+            names,
+            [
+                "common.MPQ",
+                "common-2.MPQ",
+                "expansion.MPQ",
+                "lichking.MPQ",
+                "locale-enUS.MPQ",
+                "expansion-locale-enUS.MPQ",
+                "lichking-locale-enUS.MPQ",
+                "patch.MPQ",
+                "patch-2.MPQ",
+                "patch-3.MPQ",
+                "patch-enUS.MPQ",
+                "patch-enUS-2.MPQ",
+                "patch-enUS-3.MPQ",
+                "patch-4.MPQ",
+                "patch-9.MPQ",
+                "patch-A.MPQ",
+                "patch-enUS-Z.MPQ",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_archives_the_client_ignores() {
+        assert_eq!(
+            archive_priority("PATCH-ENUS-2.mpq", "enUS"),
+            archive_priority("patch-enUS-2.MPQ", "enUS")
+        );
+        for name in [
+            "speech-enUS.MPQ",
+            "patch-deDE.MPQ",
+            "patch-10.MPQ",
+            "patch-3.MPQ.bak",
+            "backup.MPQ",
+        ] {
+            assert_eq!(archive_priority(name, "enUS"), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn later_archives_replace_and_delete_files() {
+        let dir = std::env::temp_dir().join(format!("archive-chain-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let common = dir.join("common.MPQ");
+        ArchiveBuilder::new()
+            .add_file_data(b"common".to_vec(), "DBFilesClient\\Item.dbc")
+            .add_file_data(b"common".to_vec(), "DBFilesClient\\Spell.dbc")
+            .add_file_data(b"common".to_vec(), "DBFilesClient\\Map.dbc")
+            .build(&common)
+            .unwrap();
+        let patch = dir.join("patch.MPQ");
+        ArchiveBuilder::new()
+            .add_file_data(b"patch".to_vec(), "DBFilesClient\\Item.dbc")
+            .add_file_data(Vec::new(), "DBFilesClient\\Spell.dbc")
+            .build(&patch)
+            .unwrap();
+
+        let mut chain = ArchiveChain::open(&[common, patch]).unwrap();
+        let files = chain.resolve(|name| name.ends_with(".dbc")).unwrap();
+        let names: Vec<_> = files.keys().map(String::as_str).collect();
+        assert_eq!(names, ["dbfilesclient\\item.dbc", "dbfilesclient\\map.dbc"]);
+        let item = &files["dbfilesclient\\item.dbc"];
+        assert_eq!(item.archive, 1);
+        assert_eq!(chain.read(item).unwrap(), b"patch");
+        assert_eq!(files["dbfilesclient\\map.dbc"].archive, 0);
+        assert_eq!(chain.versions("DBFilesClient\\Item.dbc").unwrap().len(), 2);
+
+        // Files of both archives come back in the order asked for
+        let map = &files["dbfilesclient\\map.dbc"];
+        let data = chain.read_all(&[map, item, map]).unwrap();
+        assert_eq!(data, [&b"common"[..], b"patch", b"common"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn delete_markers_remove_files() {
+        let entry = |flags, size| ResolvedFile {
+            archive: 1,
+            entry: FileEntry {
+                name: "DBFilesClient\\Item.dbc".to_string(),
+                size,
+                compressed_size: size,
+                flags,
+                hashes: None,
+                table_indices: None,
+            },
+        };
+        assert!(entry(FILE_DELETE_MARKER, 16).is_deletion());
+        assert!(entry(0, 0).is_deletion());
+        assert!(!entry(0x0000_0200, 16).is_deletion());
+    }
+}
@@ -1,86 +1,621 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
 use std::{
-    fs,
+    collections::BTreeMap,
     panic::{self, AssertUnwindSafe, UnwindSafe},
     path::{Path, PathBuf},
 };
-use wow_mpq::{Archive, FileEntry};
+use wow_mpq::FileEntry;
 
+use crate::{
+    dbc::{DbcFile, Value},
+    diagnostics::{TableReport, TableStatus},
+    layout::{self, Layout, Layouts},
+    mpq::ArchiveChain,
+    provenance::TableSources,
+    random::RandomEnchantmentResolver,
+    requirements::RequirementResolver,
+    spell_text::SpellTextRenderer,
+};
+
+pub mod chr_classes;
+pub mod chr_races;
+pub mod faction;
 pub mod item;
 pub mod item_class;
 pub mod item_display_info;
+pub mod item_random_properties;
+pub mod item_random_suffix;
 pub mod item_sets;
 pub mod item_sub_class;
+pub mod rand_prop_points;
+pub mod skill_line;
 pub mod spell_description_vars;
+pub mod spell_durations;
+pub mod spell_item_enchantment;
+pub mod spell_radius;
 pub mod spells;
 
-pub trait Handler: UnwindSafe {
-    fn can_handle(&self, _file_entry: &FileEntry) -> bool {
-        true
+use chr_classes::CharacterClasses;
+use chr_races::CharacterRaces;
+use faction::Factions;
+use item::Items;
+use item_class::ItemClasses;
+use item_display_info::ItemDisplayInfos;
+use item_random_properties::ItemRandomProperties;
+use item_random_suffix::ItemRandomSuffixes;
+use item_sets::ItemSets;
+use item_sub_class::ItemSubClasses;
+use rand_prop_points::RandPropPoints;
+use skill_line::SkillLines;
+use spell_description_vars::SpellDescriptionVars;
+use spell_durations::SpellDurations;
+use spell_item_enchantment::SpellItemEnchantments;
+use spell_radius::SpellRadii;
+use spells::Spells;
+
+pub trait Handler: UnwindSafe + Send {
+    /// Table this handler parses, such as `DBFilesClient\Item.dbc`.
+    fn file_name(&self) -> &'static str;
+
+    /// Name shown in diagnostics, the handler's type name by default.
+    fn name(&self) -> &'static str {
+        let type_name = std::any::type_name::<Self>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
     }
 
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()>;
+    /// Parse this handler's table, returning the rows read.
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize>;
 
-    fn finish(&self);
+    /// Called once every table is parsed, to build indexes over the rows.
+    fn finish(&mut self);
 }
 
-/// Collect MPQ files in priority order.
-#[inline(always)]
-pub fn collect_mpqs(dir: &Path) -> Result<Vec<PathBuf>> {
-    if !dir.exists() {
-        return Ok(Vec::new());
-    }
-
-    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
-        .filter_map(|e| {
-            let path = e.ok()?.path();
-            if path
-                .extension()
-                .and_then(|s| s.to_str())
-                .map(|s| s.eq_ignore_ascii_case("mpq"))
-                .unwrap_or(false)
+/// Handlers keyed by the lowercase name of the table they parse.
+#[derive(Default)]
+pub struct HandlerRegistry<'a> {
+    handlers: BTreeMap<String, &'a mut dyn Handler>,
+}
+
+impl<'a> HandlerRegistry<'a> {
+    pub fn register(&mut self, handler: &'a mut dyn Handler) {
+        let file_name = handler.file_name().to_lowercase();
+        if self.handlers.insert(file_name, handler).is_some() {
+            panic!("two handlers registered for the same table");
+        }
+    }
+
+    pub fn contains(&self, file_name: &str) -> bool {
+        self.handlers.contains_key(&file_name.to_lowercase())
+    }
+
+    pub fn get_mut(&mut self, file_name: &str) -> Option<&mut (dyn Handler + 'a)> {
+        self.handlers
+            .get_mut(&file_name.to_lowercase())
+            .map(|handler| &mut **handler)
+    }
+
+    pub fn handlers(&self) -> impl Iterator<Item = &(dyn Handler + 'a)> {
+        self.handlers.values().map(|handler| &**handler)
+    }
+}
+
+/// Every DBC table used to build items.
+#[derive(Debug, Default)]
+pub struct DbcTables {
+    pub items: Items,
+    pub item_display_infos: ItemDisplayInfos,
+    pub item_classes: ItemClasses,
+    pub item_sub_classes: ItemSubClasses,
+    pub item_sets: ItemSets,
+    pub spells: Spells,
+    pub spells_desc_vars: SpellDescriptionVars,
+    pub spell_durations: SpellDurations,
+    pub spell_radii: SpellRadii,
+    pub classes: CharacterClasses,
+    pub races: CharacterRaces,
+    pub skill_lines: SkillLines,
+    pub factions: Factions,
+    pub random_properties: ItemRandomProperties,
+    pub random_suffixes: ItemRandomSuffixes,
+    pub enchantments: SpellItemEnchantments,
+    pub rand_prop_points: RandPropPoints,
+    /// Archive each table was read from.
+    pub sources: TableSources,
+    /// Outcome of every table, in table name order.
+    pub reports: Vec<TableReport>,
+}
+
+impl DbcTables {
+    /// Parse all known tables from `mpq_paths`, in the given order, reading
+    /// tables with an override in `layouts` through it.
+    pub fn load(mpq_paths: &[PathBuf], layouts: &Layouts) -> Result<Self> {
+        let mut tables = Self::default();
+        {
+            let mut registry = HandlerRegistry::default();
+            for handler in tables.handlers() {
+                registry.register(handler);
+            }
+            tables.reports = parse_dbcs(mpq_paths, &mut registry, layouts)?;
+        }
+        for report in &tables.reports {
+            if let Some(archive) = &report.archive
+                && !report.is_problem()
             {
-                // Filter out files starting with "speech" (audio)
-                if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-                    if filename.to_lowercase().starts_with("speech") {
-                        return None;
-                    }
+                tables.sources.insert(&report.table, archive);
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Replace the text items are built with, subclass and set names and
+    /// spell descriptions, by that of the client locale of `mpq_paths`.
+    /// Only those localized string columns are read, every other value
+    /// stays as first loaded.
+    pub fn localize(
+        &mut self,
+        mpq_paths: &[PathBuf],
+        layouts: &Layouts,
+    ) -> Result<Vec<TableReport>> {
+        let tables = [
+            item_sub_class::FILE_NAME,
+            item_sets::FILE_NAME,
+            spells::FILE_NAME,
+        ];
+        let mut chain = ArchiveChain::open(mpq_paths)?;
+        let files =
+            chain.resolve(|name| tables.iter().any(|table| table.eq_ignore_ascii_case(name)))?;
+
+        let mut reports = Vec::new();
+        let mut read = |handler: &'static str, table: &str, keys: &[&str], column: &str| {
+            let file = files.get(&table.to_lowercase());
+            let layout = layouts.get(table);
+            let result = match file {
+                Some(file) => chain.read(file).and_then(|data| {
+                    let builtin;
+                    let layout = match layout {
+                        Some(layout) => layout,
+                        None => {
+                            builtin = layout::builtin(layout::table_name(table));
+                            builtin.as_ref().context("no layout to read it with")?
+                        }
+                    };
+                    read_localized(&data, layout, keys, column)
+                }),
+                None => Ok(Vec::new()),
+            };
+            let (texts, status) = match (file, result) {
+                (None, _) => (Vec::new(), TableStatus::Missing),
+                (Some(_), Ok(texts)) => {
+                    let rows = texts.len();
+                    (texts, TableStatus::Parsed { rows })
+                }
+                (Some(_), Err(err)) => (
+                    Vec::new(),
+                    TableStatus::Failed {
+                        error: format!("{err:#}"),
+                    },
+                ),
+            };
+            let archive = file.map(|file| chain.path(file.archive).to_path_buf());
+            match (&status, &archive) {
+                (TableStatus::Parsed { .. }, Some(archive)) => {
+                    println!("{table} {column} <- {}", archive.display())
+                }
+                (TableStatus::Failed { error }, _) => {
+                    println!("Failed to read {column} of {table}: {error}")
                 }
-                Some(path)
-            } else {
-                None
+                _ => println!("{table} not found in any archive"),
             }
+            reports.push(TableReport {
+                table: file.map_or(table.to_string(), |file| file.entry.name.clone()),
+                handler,
+                archive,
+                layout: layout.and_then(|layout| layout.path.clone()),
+                dropped_fields: Vec::new(),
+                locale: None,
+                status,
+            });
+            texts
+        };
+
+        let names = read(
+            self.item_sub_classes.name(),
+            item_sub_class::FILE_NAME,
+            &["ClassID", "SubClassID"],
+            "DisplayName_lang",
+        );
+        self.item_sub_classes.localize(
+            &names
+                .into_iter()
+                .map(|(key, name)| ((key[0], key[1]), name))
+                .collect(),
+        );
+        let names = read(
+            self.item_sets.name(),
+            item_sets::FILE_NAME,
+            &["ID"],
+            "Name_lang",
+        );
+        self.item_sets.localize(
+            &names
+                .into_iter()
+                .map(|(key, name)| (key[0], name))
+                .collect(),
+        );
+        let descriptions = read(
+            self.spells.name(),
+            spells::FILE_NAME,
+            &["ID"],
+            "Description_lang",
+        );
+        self.spells.localize(
+            &descriptions
+                .into_iter()
+                .map(|(key, description)| (key[0], description))
+                .collect(),
+        );
+        Ok(reports)
+    }
+
+    fn handlers(&mut self) -> [&mut dyn Handler; 17] {
+        [
+            &mut self.items,
+            &mut self.item_display_infos,
+            &mut self.item_classes,
+            &mut self.item_sub_classes,
+            &mut self.item_sets,
+            &mut self.spells,
+            &mut self.spells_desc_vars,
+            &mut self.spell_durations,
+            &mut self.spell_radii,
+            &mut self.classes,
+            &mut self.races,
+            &mut self.skill_lines,
+            &mut self.factions,
+            &mut self.random_properties,
+            &mut self.random_suffixes,
+            &mut self.enchantments,
+            &mut self.rand_prop_points,
+        ]
+    }
+
+    /// Renderer resolving spell description tokens against these tables.
+    pub fn spell_text(&self) -> SpellTextRenderer<'_> {
+        SpellTextRenderer::new(
+            &self.spells,
+            &self.spell_durations,
+            &self.spell_radii,
+            &self.spells_desc_vars,
+        )
+    }
+
+    /// Resolver for the random property and suffix variants of items.
+    pub fn random_enchantments(&self) -> RandomEnchantmentResolver<'_> {
+        RandomEnchantmentResolver::new(
+            &self.random_properties,
+            &self.random_suffixes,
+            &self.enchantments,
+            &self.rand_prop_points,
+        )
+    }
+
+    /// Resolver for class, race, skill, faction and spell requirements.
+    pub fn requirements(&self) -> RequirementResolver<'_> {
+        RequirementResolver::new(
+            &self.classes,
+            &self.races,
+            &self.skill_lines,
+            &self.factions,
+            &self.spells,
+        )
+    }
+}
+
+/// Values of the `keys` columns and the text of localized string `column`
+/// of every record.
+fn read_localized(
+    data: &[u8],
+    layout: &Layout,
+    keys: &[&str],
+    column: &str,
+) -> Result<Vec<(Vec<i32>, String)>> {
+    let dbc = DbcFile::parse(data)?;
+    let keys = keys
+        .iter()
+        .map(|key| dbc.column(layout, key))
+        .collect::<Result<Vec<_>>>()?;
+    let texts = dbc.column(layout, column)?;
+    Ok(texts
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| {
+            let key = keys
+                .iter()
+                .map(|values| match values[index] {
+                    Value::Int(value) => value as i32,
+                    _ => 0,
+                })
+                .collect();
+            let text = match text {
+                Value::LocString(text) => text.text().to_string(),
+                _ => String::new(),
+            };
+            (key, text)
         })
-        .collect();
-    files.sort_by(|a, b| a.file_name().unwrap().cmp(b.file_name().unwrap()));
-    Ok(files)
+        .collect())
 }
 
-/// Parse DBC files from MPQ archives.
+/// Parse the tables of `registry` from `mpq_paths`, lowest priority first.
+/// Only the version of each table the client loads is read, rewritten into
+/// the wrath layout first if `layouts` has an override for it. Tables are
+/// decompressed and parsed concurrently, returning the outcome of every
+/// registered table in table name order.
 #[inline(always)]
-pub fn parse_dbcs(mpq_paths: &[PathBuf], handlers: &mut [&mut dyn Handler]) -> Result<()> {
-    for path in mpq_paths {
-        println!("mpq: {}", path.display());
-        let mut archive = Archive::open(path)?;
-        for file_entry in archive.list()? {
-            if !file_entry.name.to_lowercase().ends_with(".dbc") {
-                continue;
+pub fn parse_dbcs(
+    mpq_paths: &[PathBuf],
+    registry: &mut HandlerRegistry,
+    layouts: &Layouts,
+) -> Result<Vec<TableReport>> {
+    let mut chain = ArchiveChain::open(mpq_paths)?;
+    let files = chain.resolve(|name| registry.contains(name))?;
+
+    // Both maps are keyed by lowercase table name, so every handler is
+    // paired with its own table
+    let found: Vec<_> = registry
+        .handlers
+        .keys()
+        .filter_map(|name| files.get(name))
+        .collect();
+    let mut file_data = chain.read_each(&found).into_iter();
+    let jobs: Vec<_> = registry
+        .handlers
+        .iter_mut()
+        .map(|(name, handler)| {
+            let file = files.get(name).map(|file| {
+                let archive = chain.path(file.archive).to_path_buf();
+                let data = file_data.next().unwrap_or_else(|| Ok(Vec::new()));
+                (file, archive, data)
+            });
+            (&mut **handler, file)
+        })
+        .collect();
+
+    // Reports come back in table name order, whichever thread finishes first
+    let reports: Vec<_> = jobs
+        .into_par_iter()
+        .map(|(handler, file)| {
+            let Some((file, archive, file_data)) = file else {
+                return TableReport {
+                    table: handler.file_name().to_string(),
+                    handler: handler.name(),
+                    archive: None,
+                    layout: None,
+                    dropped_fields: Vec::new(),
+                    locale: None,
+                    status: TableStatus::Missing,
+                };
+            };
+            let handler_name = handler.name();
+            let layout = layouts.get(&file.entry.name);
+            let status = match file_data {
+                Ok(file_data) => parse_table_data(handler, &file.entry, &file_data, layouts),
+                // The archive is still recorded, the error names the file
+                Err(err) => TableStatus::Failed {
+                    error: format!("{err:#}"),
+                },
+            };
+            TableReport {
+                table: file.entry.name.clone(),
+                handler: handler_name,
+                archive: Some(archive),
+                layout: layout.and_then(|layout| layout.path.clone()),
+                dropped_fields: layouts.dropped_fields(&file.entry.name),
+                locale: None,
+                status,
             }
-            let file_data = archive.read_file(&file_entry.name)?;
-            for handler in handlers.iter_mut() {
-                if handler.can_handle(&file_entry) {
-                    // if we panic, just ignore and continue
-                    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
-                        let _ = handler.parse(&file_entry, &file_data);
-                    }));
+        })
+        .collect();
+
+    for report in &reports {
+        match &report.status {
+            TableStatus::Parsed { rows } if *rows > 0 => {
+                let archive = report.archive.as_deref().unwrap_or(Path::new(""));
+                println!("{} <- {}", report.table, archive.display());
+                if !report.dropped_fields.is_empty() {
+                    println!(
+                        "  dropped override columns: {}",
+                        report.dropped_fields.join(", ")
+                    );
                 }
             }
+            TableStatus::Parsed { .. } => println!("{} has no rows", report.table),
+            TableStatus::Missing => println!("{} not found in any archive", report.table),
+            TableStatus::Failed { error } => println!("Failed to parse {}: {error}", report.table),
+            TableStatus::Panicked { message } => {
+                println!("Panicked parsing {}: {message}", report.table)
+            }
+        }
+    }
+
+    for handler in registry.handlers.values_mut() {
+        handler.finish();
+    }
+
+    Ok(reports)
+}
+
+/// Parse one table's data into `handler`, rewritten through its layout
+/// override if there is one.
+fn parse_table_data(
+    handler: &mut dyn Handler,
+    entry: &FileEntry,
+    file_data: &[u8],
+    layouts: &Layouts,
+) -> TableStatus {
+    match layouts.to_wrath(&entry.name, file_data) {
+        Ok(wrath_data) => {
+            let data = wrath_data.as_deref().unwrap_or(file_data);
+            // if we panic, record it and continue with the other tables
+            let result = panic::catch_unwind(AssertUnwindSafe(|| handler.parse(entry, data)));
+            match result {
+                Ok(Ok(rows)) => TableStatus::Parsed { rows },
+                Ok(Err(err)) => TableStatus::Failed {
+                    error: format!("{err:#}"),
+                },
+                Err(payload) => TableStatus::panicked(payload),
+            }
+        }
+        Err(err) => TableStatus::Failed {
+            error: format!("reading through layout: {err:#}"),
+        },
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        dbc::{LocString, Record},
+        layout::LOCALES,
+    };
+    use std::fs;
+    use wow_dbc::DbcTable;
+
+    /// Parse `records`, in the built-in layout of its table, into `handler`.
+    pub(crate) fn parse_records(handler: &mut dyn Handler, records: &[Record]) {
+        let layout = layout::builtin(layout::table_name(handler.file_name())).unwrap();
+        parse_data(handler, &crate::dbc::write(&layout, records));
+    }
+
+    /// Parse a `wow_dbc` table into `handler`.
+    pub(crate) fn parse_table(handler: &mut dyn Handler, table: &impl DbcTable) {
+        let mut data = Vec::new();
+        table.write(&mut data).unwrap();
+        parse_data(handler, &data);
+    }
+
+    /// Row of `T` with all of its `fields` zero, for rows too large to
+    /// spell out. Every field must be 32 bits wide.
+    pub(crate) fn blank_row<T: DbcTable>(fields: u32) -> T::Row
+    where
+        T::Row: Clone,
+    {
+        let mut data = crate::dbc::WDBC_MAGIC.to_vec();
+        for value in [1, fields, fields * 4, 1] {
+            data.extend(u32::to_le_bytes(value));
         }
+        data.resize(data.len() + fields as usize * 4 + 1, 0);
+        T::read(&mut data.as_slice()).unwrap().rows()[0].clone()
     }
 
-    for handler in handlers.iter_mut() {
+    /// Parse a whole table file into `handler`.
+    pub(crate) fn parse_data(handler: &mut dyn Handler, data: &[u8]) {
+        let entry = FileEntry {
+            name: handler.file_name().to_string(),
+            size: data.len() as u64,
+            compressed_size: data.len() as u64,
+            flags: 0,
+            hashes: None,
+            table_indices: None,
+        };
+        handler.parse(&entry, data).unwrap();
         handler.finish();
     }
 
-    Ok(())
+    /// Localized string with `text` in the slot of `locale`.
+    pub(crate) fn loc_string(locale: usize, text: &str) -> Value {
+        let mut strings = vec![String::new(); LOCALES];
+        strings[locale] = text.to_string();
+        Value::LocString(LocString { strings, flags: 0 })
+    }
+
+    /// ItemSubClass record of a deDE client, which fills the `deDE` slot.
+    pub(crate) fn sub_class(class_id: i64, sub_class_id: i64, name: &str) -> Record {
+        let layout = layout::builtin("ItemSubClass").unwrap();
+        let mut record: Vec<_> = layout.fields.iter().map(|f| f.default_value()).collect();
+        record[0] = Value::Int(class_id);
+        record[1] = Value::Int(sub_class_id);
+        record[10] = loc_string(3, name);
+        record
+    }
+
+    #[test]
+    fn reads_localized_column_by_key() {
+        let layout = layout::builtin("ItemSubClass").unwrap();
+        let data = crate::dbc::write(
+            &layout,
+            &[sub_class(2, 7, "Schwerter"), sub_class(4, 1, "Stoff")],
+        );
+        let texts = read_localized(
+            &data,
+            &layout,
+            &["ClassID", "SubClassID"],
+            "DisplayName_lang",
+        )
+        .unwrap();
+        assert_eq!(
+            texts,
+            [
+                (vec![2, 7], "Schwerter".to_string()),
+                (vec![4, 1], "Stoff".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn localizing_replaces_only_text() {
+        let mut sub_classes = ItemSubClasses::default();
+        parse_records(&mut sub_classes, &[sub_class(2, 7, "Swords")]);
+
+        sub_classes.localize(&[((2, 7), "Schwerter".to_string())].into());
+        let row = sub_classes.get(2, 7).unwrap();
+        assert_eq!(row.display_name_lang.en_gb, "Schwerter");
+        assert_eq!(row.class_id, 2);
+    }
+
+    #[test]
+    fn unreadable_tables_fail_alone() {
+        let dir = std::env::temp_dir().join(format!("parse-dbcs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("common.MPQ");
+        let sub_classes = crate::dbc::write(
+            &layout::builtin("ItemSubClass").unwrap(),
+            &[sub_class(2, 7, "Schwerter")],
+        );
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(vec![7; 4096], faction::FILE_NAME)
+            .add_file_data(sub_classes, item_sub_class::FILE_NAME)
+            .build(&path)
+            .unwrap();
+        // Break the compressed data of the first file
+        let mut archive = fs::read(&path).unwrap();
+        archive[0x20..0x30].fill(0xff);
+        fs::write(&path, archive).unwrap();
+
+        let mut tables = DbcTables::default();
+        let mut registry = HandlerRegistry::default();
+        registry.register(&mut tables.factions);
+        registry.register(&mut tables.item_sub_classes);
+        let reports = parse_dbcs(
+            std::slice::from_ref(&path),
+            &mut registry,
+            &Layouts::default(),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        let [factions, sub_classes] = &reports.unwrap()[..] else {
+            panic!("expected two reports");
+        };
+        assert_eq!(factions.archive.as_ref(), Some(&path));
+        let TableStatus::Failed { error } = &factions.status else {
+            panic!("expected a read error, got {:?}", factions.status);
+        };
+        assert!(
+            error.starts_with("reading DBFilesClient\\Faction.dbc from"),
+            "{error}"
+        );
+        assert_eq!(sub_classes.status, TableStatus::Parsed { rows: 1 });
+        assert!(tables.item_sub_classes.get(2, 7).is_some());
+    }
 }
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::chr_classes::{ChrClasses as DbcChrClasses, ChrClassesKey, ChrClassesRow},
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\ChrClasses.dbc";
+
+#[derive(Debug, Default)]
+pub struct CharacterClasses {
+    items: HashMap<ChrClassesKey, ChrClassesRow>,
+}
+
+impl CharacterClasses {
+    pub fn get(&self, id: ChrClassesKey) -> Option<&ChrClassesRow> {
+        self.items.get(&id)
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &ChrClassesRow> {
+        self.items.values()
+    }
+}
+
+impl Handler for CharacterClasses {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcChrClasses::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for CharacterClasses",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!(
+            "CharacterClasses finished with {} entries",
+            self.items.len()
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::chr_races::{ChrRaces as DbcChrRaces, ChrRacesKey, ChrRacesRow},
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\ChrRaces.dbc";
+
+#[derive(Debug, Default)]
+pub struct CharacterRaces {
+    items: HashMap<ChrRacesKey, ChrRacesRow>,
+}
+
+impl CharacterRaces {
+    pub fn get(&self, id: ChrRacesKey) -> Option<&ChrRacesRow> {
+        self.items.get(&id)
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &ChrRacesRow> {
+        self.items.values()
+    }
+}
+
+impl Handler for CharacterRaces {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcChrRaces::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for CharacterRaces",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!("CharacterRaces finished with {} entries", self.items.len());
+    }
+}
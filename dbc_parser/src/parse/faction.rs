@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::faction::{Faction as DbcFaction, FactionKey, FactionRow},
+};
+use wow_mpq::FileEntry;
+
+use crate::{locale::LocalizedText, parse::Handler};
+
+pub const FILE_NAME: &str = "DBFilesClient\\Faction.dbc";
+
+#[derive(Debug, Default)]
+pub struct Factions {
+    items: HashMap<FactionKey, FactionRow>,
+}
+
+impl Factions {
+    pub fn get(&self, id: FactionKey) -> Option<&FactionRow> {
+        self.items.get(&id)
+    }
+
+    /// Find a faction by its (en) name, ignoring case.
+    pub fn find_by_name(&self, name: &str) -> Option<&FactionRow> {
+        self.items
+            .values()
+            .find(|row| row.name_lang.text().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Handler for Factions {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcFaction::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for Factions",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!("Factions finished with {} entries", self.items.len());
+    }
+}
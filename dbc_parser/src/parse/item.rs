@@ -8,6 +8,8 @@ use wow_mpq::FileEntry;
 
 use crate::parse::Handler;
 
+pub const FILE_NAME: &str = "DBFilesClient\\Item.dbc";
+
 #[derive(Debug, Default)]
 pub struct Items {
     items: HashMap<ItemKey, ItemRow>,
@@ -17,28 +19,36 @@ impl Items {
     pub fn iter_rows(&self) -> impl Iterator<Item = &ItemRow> {
         self.items.values()
     }
+
+    pub fn contains(&self, id: i32) -> bool {
+        self.items.contains_key(&ItemKey::new(id))
+    }
 }
 
 impl Handler for Items {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcItem::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for Items",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items.insert(row.id, *row);
-            }
+        let parsed = DbcItem::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for Items",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, *row);
         }
-        Ok(())
+
+        Ok(parsed.rows().len())
     }
 
-    fn finish(&self) {
+    fn finish(&mut self) {
         println!("Items finished with {} entries", self.items.len());
     }
 }
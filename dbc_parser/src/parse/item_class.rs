@@ -8,6 +8,8 @@ use wow_mpq::FileEntry;
 
 use crate::parse::Handler;
 
+pub const FILE_NAME: &str = "DBFilesClient\\ItemClass.dbc";
+
 #[derive(Debug, Default)]
 pub struct ItemClasses {
     items: HashMap<i32, ItemClassRow>,
@@ -21,25 +23,29 @@ impl ItemClasses {
 }
 
 impl Handler for ItemClasses {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcItemClass::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for ItemClasses",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items.insert(row.class_id, row.clone());
-            }
+        let parsed = DbcItemClass::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for ItemClasses",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.class_id, row.clone());
         }
-        Ok(())
+
+        Ok(parsed.rows().len())
     }
 
-    fn finish(&self) {
+    fn finish(&mut self) {
         println!("ItemClasses finished with {} entries", self.items.len());
     }
 }
@@ -10,6 +10,8 @@ use wow_mpq::FileEntry;
 
 use crate::parse::Handler;
 
+pub const FILE_NAME: &str = "DBFilesClient\\ItemDisplayInfo.dbc";
+
 #[derive(Debug, Default)]
 pub struct ItemDisplayInfos {
     items: HashMap<ItemDisplayInfoKey, ItemDisplayInfoRow>,
@@ -19,28 +21,40 @@ impl ItemDisplayInfos {
     pub fn get(&self, id: ItemDisplayInfoKey) -> Option<&ItemDisplayInfoRow> {
         self.items.get(&id)
     }
+
+    /// Inventory icon name of every display, such as `INV_Sword_04`.
+    pub fn icons(&self) -> impl Iterator<Item = &str> {
+        self.items
+            .values()
+            .map(|row| row.inventory_icon[0].as_str())
+            .filter(|icon| !icon.is_empty())
+    }
 }
 
 impl Handler for ItemDisplayInfos {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcItemDisplayInfo::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for ItemDisplayInfos",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items.insert(row.id, row.clone());
-            }
+        let parsed = DbcItemDisplayInfo::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for ItemDisplayInfos",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
         }
-        Ok(())
+
+        Ok(parsed.rows().len())
     }
 
-    fn finish(&self) {
+    fn finish(&mut self) {
         println!(
             "ItemDisplayInfos finished with {} entries",
             self.items.len()
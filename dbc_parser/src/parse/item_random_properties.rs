@@ -0,0 +1,56 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::item_random_properties::{
+        ItemRandomProperties as DbcItemRandomProperties, ItemRandomPropertiesKey,
+        ItemRandomPropertiesRow,
+    },
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\ItemRandomProperties.dbc";
+
+#[derive(Debug, Default)]
+pub struct ItemRandomProperties {
+    items: HashMap<ItemRandomPropertiesKey, ItemRandomPropertiesRow>,
+}
+
+impl ItemRandomProperties {
+    pub fn get(&self, id: ItemRandomPropertiesKey) -> Option<&ItemRandomPropertiesRow> {
+        self.items.get(&id)
+    }
+}
+
+impl Handler for ItemRandomProperties {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcItemRandomProperties::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for ItemRandomProperties",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!(
+            "ItemRandomProperties finished with {} entries",
+            self.items.len()
+        );
+    }
+}
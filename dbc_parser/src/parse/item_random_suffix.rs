@@ -0,0 +1,55 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::item_random_suffix::{
+        ItemRandomSuffix as DbcItemRandomSuffix, ItemRandomSuffixKey, ItemRandomSuffixRow,
+    },
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\ItemRandomSuffix.dbc";
+
+#[derive(Debug, Default)]
+pub struct ItemRandomSuffixes {
+    items: HashMap<ItemRandomSuffixKey, ItemRandomSuffixRow>,
+}
+
+impl ItemRandomSuffixes {
+    pub fn get(&self, id: ItemRandomSuffixKey) -> Option<&ItemRandomSuffixRow> {
+        self.items.get(&id)
+    }
+}
+
+impl Handler for ItemRandomSuffixes {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcItemRandomSuffix::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for ItemRandomSuffixes",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!(
+            "ItemRandomSuffixes finished with {} entries",
+            self.items.len()
+        );
+    }
+}
@@ -1,57 +1,170 @@
 use anyhow::Result;
-use std::{collections::HashMap, io::Cursor};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+};
 use wow_dbc::{
     DbcTable,
-    wrath_tables::{
-        item::ItemKey,
-        item_set::{ItemSet as DbcItemSet, ItemSetKey, ItemSetRow},
-    },
+    wrath_tables::item_set::{ItemSet as DbcItemSet, ItemSetKey, ItemSetRow},
 };
 use wow_mpq::FileEntry;
 
-use crate::parse::Handler;
+use crate::{locale::localized, parse::Handler};
+
+pub const FILE_NAME: &str = "DBFilesClient\\ItemSet.dbc";
 
 #[derive(Debug, Default)]
 pub struct ItemSets {
-    items: HashMap<ItemSetKey, ItemSetRow>,
+    items: BTreeMap<i32, ItemSetRow>,
+    /// Set of every member item id, built by `finish`.
+    by_item: HashMap<i32, ItemSetKey>,
 }
 
 impl ItemSets {
-    #[allow(dead_code)]
     pub fn get(&self, key: ItemSetKey) -> Option<&ItemSetRow> {
-        self.items.get(&key)
+        self.items.get(&key.id)
+    }
+
+    /// Set `item_id` is a member of. Items listed by several sets belong to
+    /// the one with the lowest id.
+    pub fn by_item(&self, item_id: i32) -> Option<&ItemSetRow> {
+        self.get(*self.by_item.get(&item_id)?)
+    }
+
+    /// Replace the names with those of another client locale, keyed by set
+    /// id. Sets it lacks are left without one.
+    pub fn localize(&mut self, names: &HashMap<i32, String>) {
+        for (id, row) in &mut self.items {
+            row.name_lang = localized(names.get(id).map_or("", String::as_str));
+        }
     }
 
-    pub fn find_by_item_ids(&self, item_ids: &[ItemKey]) -> Option<&ItemSetRow> {
-        self.items.values().find(|item_set| {
-            item_set
-                .item_id
-                .iter()
-                .any(|item_id| item_ids.contains(&ItemKey::new(*item_id)))
-        })
+    /// All sets ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = &ItemSetRow> {
+        self.items.values()
     }
 }
 
+/// Member item ids of `set`, without the unused slots.
+pub fn members(set: &ItemSetRow) -> impl Iterator<Item = i32> + '_ {
+    set.item_id.iter().copied().filter(|&id| id > 0)
+}
+
 impl Handler for ItemSets {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcItemSet::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for ItemSets",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items.insert(row.id, row.clone());
+        let parsed = DbcItemSet::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for ItemSets",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        self.by_item.clear();
+        for set in self.items.values() {
+            for item_id in members(set) {
+                self.by_item.entry(item_id).or_insert(set.id);
             }
         }
-        Ok(())
+        println!(
+            "ItemSets finished with {} entries and {} member items",
+            self.items.len(),
+            self.by_item.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dbc::{Record, Value},
+        parse::{
+            DbcTables,
+            tests::{loc_string, parse_records},
+        },
+    };
+
+    /// ItemSet record of `members`, without bonuses.
+    fn set(id: i64, name: &str, members: &[i64]) -> Record {
+        let mut items = vec![Value::Int(0); 17];
+        for (slot, &member) in members.iter().enumerate() {
+            items[slot] = Value::Int(member);
+        }
+        vec![
+            Value::Int(id),
+            loc_string(0, name),
+            Value::Array(items),
+            Value::Array(vec![Value::Int(0); 8]),
+            Value::Array(vec![Value::Int(0); 8]),
+            Value::Int(0),
+            Value::Int(0),
+        ]
+    }
+
+    #[test]
+    fn indexes_sets_by_member() {
+        let mut sets = ItemSets::default();
+        parse_records(
+            &mut sets,
+            &[
+                set(5, "Battlegear of Wrath", &[100, 101]),
+                set(3, "Dreadnaught Battlegear", &[101, 102]),
+            ],
+        );
+
+        let set_of = |item_id| sets.by_item(item_id).map(|set| set.id.id);
+        assert_eq!(set_of(100), Some(5));
+        // Listed by both, the lower id wins
+        assert_eq!(set_of(101), Some(3));
+        assert_eq!(set_of(102), Some(3));
+        // Unused slots aren't members
+        assert_eq!(set_of(0), None);
+        assert_eq!(set_of(103), None);
+        let wrath = sets.get(ItemSetKey::new(5)).unwrap();
+        assert_eq!(members(wrath).collect::<Vec<_>>(), [100, 101]);
     }
 
-    fn finish(&self) {
-        println!("ItemSets finished with {} entries", self.items.len());
+    #[test]
+    fn indexes_members_missing_from_items() {
+        let mut tables = DbcTables::default();
+        let int = Value::Int;
+        parse_records(
+            &mut tables.items,
+            &[vec![
+                int(100),
+                int(4),
+                int(4),
+                int(-1),
+                int(6),
+                int(0),
+                int(5),
+                int(0),
+            ]],
+        );
+        parse_records(
+            &mut tables.item_sets,
+            &[set(5, "Battlegear of Wrath", &[100, 4242])],
+        );
+
+        assert!(!tables.items.contains(4242));
+        let set = tables.item_sets.by_item(4242).unwrap();
+        assert_eq!(set.id.id, 5);
+        assert_eq!(members(set).collect::<Vec<_>>(), [100, 4242]);
+        assert_eq!(tables.item_sets.by_item(100).unwrap().id.id, 5);
     }
 }
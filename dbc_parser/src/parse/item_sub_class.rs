@@ -6,7 +6,9 @@ use wow_dbc::{
 };
 use wow_mpq::FileEntry;
 
-use crate::parse::Handler;
+use crate::{locale::localized, parse::Handler};
+
+pub const FILE_NAME: &str = "DBFilesClient\\ItemSubClass.dbc";
 
 #[derive(Debug, Default)]
 pub struct ItemSubClasses {
@@ -17,29 +19,41 @@ impl ItemSubClasses {
     pub fn get(&self, class_id: i32, sub_class_id: i32) -> Option<&ItemSubClassRow> {
         self.items.get(&(class_id, sub_class_id))
     }
+
+    /// Replace the display names with those of another client locale, keyed
+    /// like [`Self::get`]. Subclasses it lacks are left without one.
+    pub fn localize(&mut self, names: &HashMap<(i32, i32), String>) {
+        for (key, row) in &mut self.items {
+            row.display_name_lang = localized(names.get(key).map_or("", String::as_str));
+        }
+    }
 }
 
 impl Handler for ItemSubClasses {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcItemSubClass::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for ItemSubClasses",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items
-                    .insert((row.class_id, row.sub_class_id), row.clone());
-            }
+        let parsed = DbcItemSubClass::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for ItemSubClasses",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items
+                .insert((row.class_id, row.sub_class_id), row.clone());
         }
-        Ok(())
+
+        Ok(parsed.rows().len())
     }
 
-    fn finish(&self) {
+    fn finish(&mut self) {
         println!("ItemSubClasses finished with {} entries", self.items.len());
     }
 }
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::rand_prop_points::{
+        RandPropPoints as DbcRandPropPoints, RandPropPointsKey, RandPropPointsRow,
+    },
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\RandPropPoints.dbc";
+
+#[derive(Debug, Default)]
+pub struct RandPropPoints {
+    items: HashMap<RandPropPointsKey, RandPropPointsRow>,
+}
+
+impl RandPropPoints {
+    pub fn get(&self, id: RandPropPointsKey) -> Option<&RandPropPointsRow> {
+        self.items.get(&id)
+    }
+}
+
+impl Handler for RandPropPoints {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcRandPropPoints::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for RandPropPoints",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, *row);
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!("RandPropPoints finished with {} entries", self.items.len());
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::skill_line::{SkillLine as DbcSkillLine, SkillLineKey, SkillLineRow},
+};
+use wow_mpq::FileEntry;
+
+use crate::{locale::LocalizedText, parse::Handler};
+
+pub const FILE_NAME: &str = "DBFilesClient\\SkillLine.dbc";
+
+#[derive(Debug, Default)]
+pub struct SkillLines {
+    items: HashMap<SkillLineKey, SkillLineRow>,
+}
+
+impl SkillLines {
+    pub fn get(&self, id: SkillLineKey) -> Option<&SkillLineRow> {
+        self.items.get(&id)
+    }
+
+    /// Find a skill by its (en) display name, ignoring case.
+    pub fn find_by_name(&self, name: &str) -> Option<&SkillLineRow> {
+        self.items
+            .values()
+            .find(|row| row.display_name_lang.text().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Handler for SkillLines {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcSkillLine::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for SkillLines",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!("SkillLines finished with {} entries", self.items.len());
+    }
+}
@@ -11,6 +11,8 @@ use wow_mpq::FileEntry;
 
 use crate::parse::Handler;
 
+pub const FILE_NAME: &str = "DBFilesClient\\SpellDescriptionVariables.dbc";
+
 #[derive(Debug, Default)]
 pub struct SpellDescriptionVars {
     items: HashMap<SpellDescriptionVariablesKey, SpellDescriptionVariablesRow>,
@@ -23,25 +25,29 @@ impl SpellDescriptionVars {
 }
 
 impl Handler for SpellDescriptionVars {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcSpellDescriptionVariables::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for SpellDescriptionVars",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items.insert(row.id, row.clone());
-            }
+        let parsed = DbcSpellDescriptionVariables::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for SpellDescriptionVars",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
         }
-        Ok(())
+
+        Ok(parsed.rows().len())
     }
 
-    fn finish(&self) {
+    fn finish(&mut self) {
         println!(
             "SpellDescriptionVars finished with {} entries",
             self.items.len()
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::spell_duration::{
+        SpellDuration as DbcSpellDuration, SpellDurationKey, SpellDurationRow,
+    },
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\SpellDuration.dbc";
+
+#[derive(Debug, Default)]
+pub struct SpellDurations {
+    items: HashMap<SpellDurationKey, SpellDurationRow>,
+}
+
+impl SpellDurations {
+    pub fn get(&self, id: SpellDurationKey) -> Option<&SpellDurationRow> {
+        self.items.get(&id)
+    }
+}
+
+impl Handler for SpellDurations {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcSpellDuration::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for SpellDurations",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, *row);
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!("SpellDurations finished with {} entries", self.items.len());
+    }
+}
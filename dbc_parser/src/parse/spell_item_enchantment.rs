@@ -0,0 +1,56 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::spell_item_enchantment::{
+        SpellItemEnchantment as DbcSpellItemEnchantment, SpellItemEnchantmentKey,
+        SpellItemEnchantmentRow,
+    },
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\SpellItemEnchantment.dbc";
+
+#[derive(Debug, Default)]
+pub struct SpellItemEnchantments {
+    items: HashMap<SpellItemEnchantmentKey, SpellItemEnchantmentRow>,
+}
+
+impl SpellItemEnchantments {
+    pub fn get(&self, id: SpellItemEnchantmentKey) -> Option<&SpellItemEnchantmentRow> {
+        self.items.get(&id)
+    }
+}
+
+impl Handler for SpellItemEnchantments {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcSpellItemEnchantment::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for SpellItemEnchantments",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!(
+            "SpellItemEnchantments finished with {} entries",
+            self.items.len()
+        );
+    }
+}
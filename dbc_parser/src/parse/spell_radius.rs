@@ -0,0 +1,50 @@
+use anyhow::Result;
+use std::{collections::HashMap, io::Cursor};
+use wow_dbc::{
+    DbcTable,
+    wrath_tables::spell_radius::{SpellRadius as DbcSpellRadius, SpellRadiusKey, SpellRadiusRow},
+};
+use wow_mpq::FileEntry;
+
+use crate::parse::Handler;
+
+pub const FILE_NAME: &str = "DBFilesClient\\SpellRadius.dbc";
+
+#[derive(Debug, Default)]
+pub struct SpellRadii {
+    items: HashMap<SpellRadiusKey, SpellRadiusRow>,
+}
+
+impl SpellRadii {
+    pub fn get(&self, id: SpellRadiusKey) -> Option<&SpellRadiusRow> {
+        self.items.get(&id)
+    }
+}
+
+impl Handler for SpellRadii {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
+        let mut cursor = Cursor::new(data);
+        println!("{}", file_entry.name);
+        let parsed = DbcSpellRadius::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for SpellRadii",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, *row);
+        }
+
+        Ok(parsed.rows().len())
+    }
+
+    fn finish(&mut self) {
+        println!("SpellRadii finished with {} entries", self.items.len());
+    }
+}
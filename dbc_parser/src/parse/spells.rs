@@ -6,7 +6,9 @@ use wow_dbc::{
 };
 use wow_mpq::FileEntry;
 
-use crate::parse::Handler;
+use crate::{locale::localized, parse::Handler};
+
+pub const FILE_NAME: &str = "DBFilesClient\\Spell.dbc";
 
 #[derive(Debug, Default)]
 pub struct Spells {
@@ -17,28 +19,41 @@ impl Spells {
     pub fn get(&self, id: SpellKey) -> Option<&SpellRow> {
         self.items.get(&id)
     }
+
+    /// Replace the descriptions with those of another client locale, keyed
+    /// by spell id. Spells it lacks are left without one.
+    pub fn localize(&mut self, descriptions: &HashMap<i32, String>) {
+        for (key, row) in &mut self.items {
+            let description = descriptions.get(&key.id).map_or("", String::as_str);
+            row.description_lang = localized(description);
+        }
+    }
 }
 
 impl Handler for Spells {
-    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<()> {
+    fn file_name(&self) -> &'static str {
+        FILE_NAME
+    }
+
+    fn parse(&mut self, file_entry: &FileEntry, data: &[u8]) -> Result<usize> {
         let mut cursor = Cursor::new(data);
         println!("{}", file_entry.name);
-        if let Ok(parsed) = DbcSpell::read(&mut cursor) {
-            println!(
-                "  Found {} with {} entries for Spells",
-                file_entry.name,
-                parsed.rows().len()
-            );
-
-            // Insert with deduplication (last wins due to HashMap behavior)
-            for row in parsed.rows() {
-                self.items.insert(row.id, row.clone());
-            }
+        let parsed = DbcSpell::read(&mut cursor)?;
+        println!(
+            "  Found {} with {} entries for Spells",
+            file_entry.name,
+            parsed.rows().len()
+        );
+
+        // Insert with deduplication (last wins due to HashMap behavior)
+        for row in parsed.rows() {
+            self.items.insert(row.id, row.clone());
         }
-        Ok(())
+
+        Ok(parsed.rows().len())
     }
 
-    fn finish(&self) {
+    fn finish(&mut self) {
         println!("Spells finished with {} entries", self.items.len());
     }
 }
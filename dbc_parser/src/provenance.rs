@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Where the value of an item field came from.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// A client table, with the MPQ archive that supplied it.
+    Dbc { table: String, archive: PathBuf },
+    /// A JSON item cache such as `data/item_cache.beta.3.5.json`.
+    ItemCache { path: PathBuf },
+    /// A client `itemcache.wdb`.
+    Wdb { path: PathBuf },
+    /// A hand maintained override file in the item cache format.
+    Override { path: PathBuf },
+    /// Placeholder value, nothing provided this field.
+    Default,
+}
+
+/// The source of every field of one item, keyed by field name.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct ItemProvenance {
+    fields: BTreeMap<&'static str, Source>,
+}
+
+impl ItemProvenance {
+    pub fn set(&mut self, field: &'static str, source: Source) {
+        self.fields.insert(field, source);
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Source> {
+        self.fields.get(field)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Source)> {
+        self.fields.iter().map(|(field, source)| (*field, source))
+    }
+}
+
+/// Archive that supplied each parsed `.dbc` file, keyed by lowercase file name.
+#[derive(Debug, Clone, Default)]
+pub struct TableSources {
+    archives: HashMap<String, (String, PathBuf)>,
+}
+
+impl TableSources {
+    pub fn insert(&mut self, file_name: &str, archive: &Path) {
+        self.archives.insert(
+            file_name.to_lowercase(),
+            (file_name.to_string(), archive.to_path_buf()),
+        );
+    }
+
+    /// Source for a table such as `DBFilesClient\Item.dbc`.
+    pub fn source(&self, file_name: &str) -> Source {
+        match self.archives.get(&file_name.to_lowercase()) {
+            Some((table, archive)) => Source::Dbc {
+                table: table.clone(),
+                archive: archive.clone(),
+            },
+            None => Source::Default,
+        }
+    }
+}
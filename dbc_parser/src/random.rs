@@ -0,0 +1,479 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+use wow_dbc::wrath_tables::{
+    item_random_properties::ItemRandomPropertiesKey, item_random_suffix::ItemRandomSuffixKey,
+    rand_prop_points::RandPropPointsKey, spell_item_enchantment::SpellItemEnchantmentKey,
+};
+
+use crate::{
+    locale::LocalizedText,
+    models::{InventoryType, Rarity},
+    parse::{
+        item_random_properties::ItemRandomProperties, item_random_suffix::ItemRandomSuffixes,
+        rand_prop_points::RandPropPoints, spell_item_enchantment::SpellItemEnchantments,
+    },
+    stats::{StatModifier, StatType},
+};
+
+/// `ITEM_ENCHANTMENT_TYPE_RESISTANCE`, argument is the school (`0` armor).
+const ENCHANTMENT_RESISTANCE: i32 = 4;
+/// `ITEM_ENCHANTMENT_TYPE_STAT`, argument is the `ITEM_MOD_*` stat type.
+const ENCHANTMENT_STAT: i32 = 5;
+
+/// Random enchantments an item rolls when it drops.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RandomEnchantments {
+    /// Pool of ItemRandomProperties entries, `RandomProperty` of
+    /// item_template.
+    pub property_pool: Option<i32>,
+    /// Pool of ItemRandomSuffix entries, `RandomSuffix` of item_template.
+    pub suffix_pool: Option<i32>,
+    /// Item level the suffix values scale with.
+    pub item_level: u32,
+    /// Every variant of the pools, empty without a pool mapping.
+    pub variants: Vec<RandomVariant>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RandomVariant {
+    pub kind: RandomKind,
+    /// ItemRandomProperties or ItemRandomSuffix id.
+    pub id: i32,
+    /// Name fragment appended to the item name, such as `of the Bear`.
+    pub name: String,
+    /// Chance of rolling this variant in percent.
+    pub chance: f32,
+    /// Tooltip lines such as `+7 Stamina`.
+    pub bonuses: Vec<String>,
+    /// `bonuses` that grant a stat, typed.
+    pub stat_modifiers: Vec<StatModifier>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RandomKind {
+    /// Fixed bonuses from ItemRandomProperties.
+    Property,
+    /// Bonuses from ItemRandomSuffix scaled by the item's budget.
+    Suffix,
+}
+
+/// The server's `item_enchantment_template`: the ItemRandomProperties or
+/// ItemRandomSuffix ids of each pool, with their chance.
+#[derive(Debug, Clone, Default)]
+pub struct EnchantmentPools {
+    pools: HashMap<i32, Vec<(i32, f32)>>,
+}
+
+impl EnchantmentPools {
+    /// Read a CSV export of `item_enchantment_template` with `entry`, `ench`
+    /// and `chance` columns.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut rdr = csv::Reader::from_reader(content.as_bytes());
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h.eq_ignore_ascii_case(name))
+                .with_context(|| format!("no `{name}` column in {}", path.display()))
+        };
+        let (entry, ench, chance) = (column("entry")?, column("ench")?, column("chance")?);
+
+        let mut pools: HashMap<i32, Vec<(i32, f32)>> = HashMap::new();
+        for result in rdr.records() {
+            let record = result?;
+            let field = |column: usize| record.get(column).unwrap_or_default().trim();
+            let (Ok(entry), Ok(ench), Ok(chance)) = (
+                field(entry).parse(),
+                field(ench).parse(),
+                field(chance).parse(),
+            ) else {
+                continue;
+            };
+            pools.entry(entry).or_default().push((ench, chance));
+        }
+        println!(
+            "Loaded {} random enchantment pools from {}",
+            pools.len(),
+            path.display()
+        );
+        Ok(Self { pools })
+    }
+
+    /// Entries and chances of `pool`.
+    pub fn get(&self, pool: i32) -> &[(i32, f32)] {
+        self.pools.get(&pool).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+}
+
+/// Resolves random properties and suffixes into their name and bonuses.
+pub struct RandomEnchantmentResolver<'a> {
+    properties: &'a ItemRandomProperties,
+    suffixes: &'a ItemRandomSuffixes,
+    enchantments: &'a SpellItemEnchantments,
+    points: &'a RandPropPoints,
+}
+
+impl<'a> RandomEnchantmentResolver<'a> {
+    pub fn new(
+        properties: &'a ItemRandomProperties,
+        suffixes: &'a ItemRandomSuffixes,
+        enchantments: &'a SpellItemEnchantments,
+        points: &'a RandPropPoints,
+    ) -> Self {
+        Self {
+            properties,
+            suffixes,
+            enchantments,
+            points,
+        }
+    }
+
+    /// Every variant in the pools of `random` for an item of `rarity` worn
+    /// in `slot`. Entries the tables lack are left out, as are suffixes of
+    /// items without a budget, which would grant nothing.
+    pub fn variants(
+        &self,
+        random: &RandomEnchantments,
+        rarity: Rarity,
+        slot: InventoryType,
+        pools: &EnchantmentPools,
+    ) -> Vec<RandomVariant> {
+        let properties = random
+            .property_pool
+            .map_or(&[][..], |pool| pools.get(pool))
+            .iter()
+            .filter_map(|&(id, chance)| self.property(id, chance));
+        let suffixes = match (
+            random.suffix_pool,
+            self.budget(random.item_level, rarity, slot),
+        ) {
+            (Some(pool), Some(budget)) => pools
+                .get(pool)
+                .iter()
+                .filter_map(|&(id, chance)| self.suffix(id, chance, budget))
+                .collect(),
+            _ => Vec::new(),
+        };
+        properties.chain(suffixes).collect()
+    }
+
+    fn property(&self, id: i32, chance: f32) -> Option<RandomVariant> {
+        let property = self.properties.get(ItemRandomPropertiesKey::new(id))?;
+        let mut variant = RandomVariant {
+            kind: RandomKind::Property,
+            id,
+            name: property.name_lang.text().to_string(),
+            chance,
+            bonuses: Vec::new(),
+            stat_modifiers: Vec::new(),
+        };
+        for &enchantment in &property.enchantment {
+            self.add_bonuses(&mut variant, enchantment, None);
+        }
+        Some(variant)
+    }
+
+    /// A suffix with its enchantments worth their allocation of `budget`.
+    fn suffix(&self, id: i32, chance: f32, budget: i32) -> Option<RandomVariant> {
+        let suffix = self.suffixes.get(ItemRandomSuffixKey::new(id))?;
+        let mut variant = RandomVariant {
+            kind: RandomKind::Suffix,
+            id,
+            name: suffix.name_lang.text().to_string(),
+            chance,
+            bonuses: Vec::new(),
+            stat_modifiers: Vec::new(),
+        };
+        for (&enchantment, &allocation) in suffix.enchantment.iter().zip(&suffix.allocation_pct) {
+            let value = budget * allocation / 10000;
+            self.add_bonuses(&mut variant, enchantment, Some(value));
+        }
+        Some(variant)
+    }
+
+    /// Add the text and stats of `enchantment` to `variant`. Suffixes pass
+    /// the `value` their `$i` placeholders stand for.
+    fn add_bonuses(&self, variant: &mut RandomVariant, enchantment: i32, value: Option<i32>) {
+        if enchantment <= 0 {
+            return;
+        }
+        let Some(row) = self
+            .enchantments
+            .get(SpellItemEnchantmentKey::new(enchantment))
+        else {
+            return;
+        };
+
+        let text = row.name_lang.text();
+        if !text.is_empty() {
+            let text = match value {
+                Some(value) => text.replace("$i", &value.to_string()),
+                None => text.to_string(),
+            };
+            variant.bonuses.push(text);
+        }
+        for effect in 0..row.effect.len() {
+            let arg = row.effect_arg[effect];
+            let stat = match row.effect[effect] {
+                ENCHANTMENT_STAT => StatType::from_item_mod(arg as u32),
+                ENCHANTMENT_RESISTANCE if arg == 0 => Some(StatType::Armor),
+                ENCHANTMENT_RESISTANCE => StatType::resistance(arg as u32),
+                _ => None,
+            };
+            if let Some(stat) = stat {
+                variant.stat_modifiers.push(StatModifier {
+                    stat,
+                    value: value.unwrap_or(row.effect_points_min[effect]),
+                });
+            }
+        }
+    }
+
+    /// RandPropPoints of an item, which suffix allocations are shares of.
+    /// Like the server, only uncommon, rare and epic items have one.
+    fn budget(&self, item_level: u32, rarity: Rarity, slot: InventoryType) -> Option<i32> {
+        let points = self.points.get(RandPropPointsKey::new(item_level as i32))?;
+        let column = slot_column(slot)?;
+        let budget = match rarity {
+            Rarity::Uncommon => points.good[column],
+            Rarity::Rare => points.superior[column],
+            Rarity::Epic => points.epic[column],
+            _ => return None,
+        };
+        (budget > 0).then_some(budget)
+    }
+}
+
+/// RandPropPoints column of a slot, larger slots getting larger budgets.
+fn slot_column(slot: InventoryType) -> Option<usize> {
+    let column = match slot {
+        InventoryType::Head
+        | InventoryType::Shirt
+        | InventoryType::Vest
+        | InventoryType::Legs
+        | InventoryType::TwoHand
+        | InventoryType::Robe => 0,
+        InventoryType::Shoulders
+        | InventoryType::Waist
+        | InventoryType::Feet
+        | InventoryType::Hands
+        | InventoryType::Trinket => 1,
+        InventoryType::Neck
+        | InventoryType::Wrist
+        | InventoryType::Ring
+        | InventoryType::Shield
+        | InventoryType::Back
+        | InventoryType::Held => 2,
+        InventoryType::OneHand | InventoryType::MainHand | InventoryType::OffHand => 3,
+        InventoryType::Bow | InventoryType::Thrown | InventoryType::Ranged => 4,
+        _ => return None,
+    };
+    Some(column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        locale::localized,
+        parse::{
+            DbcTables,
+            tests::{blank_row, parse_table},
+        },
+    };
+    use wow_dbc::wrath_tables::{
+        item_random_properties::ItemRandomProperties as DbcItemRandomProperties,
+        item_random_suffix::ItemRandomSuffix,
+        rand_prop_points::RandPropPoints as DbcRandPropPoints,
+        spell_item_enchantment::SpellItemEnchantment,
+    };
+
+    /// Pools 10 (suffix 5 and one the tables lack) and 20 (property 7),
+    /// with a row of the wrong type and one of another table.
+    const POOLS: &str = "Entry,ench,Chance,comment
+10,5,60.5,Bear
+10,6,39.5,
+20,7,100,
+entry,7,0,
+";
+
+    fn load_pools(name: &str, content: &str) -> Result<EnchantmentPools> {
+        let path = std::env::temp_dir().join(format!("{name}-{}.csv", std::process::id()));
+        fs::write(&path, content).unwrap();
+        let pools = EnchantmentPools::load(&path);
+        fs::remove_file(&path).unwrap();
+        pools
+    }
+
+    /// Stamina and strength enchantments, the "of the Bear" suffix and a
+    /// property of fixed stamina, and the budgets of item level 100.
+    fn tables() -> DbcTables {
+        let mut tables = DbcTables::default();
+        let enchantments = [
+            (2802, "+$i Stamina", 7),
+            (2803, "+$i Strength", 4),
+            (2804, "+8 Stamina", 7),
+        ]
+        .map(|(id, text, stat)| {
+            let mut row = blank_row::<SpellItemEnchantment>(38);
+            row.id = SpellItemEnchantmentKey::new(id);
+            row.name_lang = localized(text);
+            row.effect[0] = ENCHANTMENT_STAT;
+            row.effect_arg[0] = stat;
+            row.effect_points_min[0] = 8;
+            row
+        });
+        parse_table(
+            &mut tables.enchantments,
+            &SpellItemEnchantment {
+                rows: enchantments.to_vec(),
+            },
+        );
+
+        let mut suffix = blank_row::<ItemRandomSuffix>(29);
+        suffix.id = ItemRandomSuffixKey::new(5);
+        suffix.name_lang = localized("of the Bear");
+        suffix.enchantment[..2].copy_from_slice(&[2802, 2803]);
+        suffix.allocation_pct[..2].copy_from_slice(&[6666, 4444]);
+        parse_table(
+            &mut tables.random_suffixes,
+            &ItemRandomSuffix { rows: vec![suffix] },
+        );
+
+        let mut property = blank_row::<DbcItemRandomProperties>(24);
+        property.id = ItemRandomPropertiesKey::new(7);
+        property.name_lang = localized("of the Bear");
+        property.enchantment[0] = 2804;
+        parse_table(
+            &mut tables.random_properties,
+            &DbcItemRandomProperties {
+                rows: vec![property],
+            },
+        );
+
+        let mut points = blank_row::<DbcRandPropPoints>(16);
+        points.id = RandPropPointsKey::new(100);
+        points.epic = [80, 60, 45, 35, 25];
+        points.superior = [70, 52, 39, 30, 22];
+        points.good = [50, 40, 30, 20, 15];
+        parse_table(
+            &mut tables.rand_prop_points,
+            &DbcRandPropPoints { rows: vec![points] },
+        );
+        tables
+    }
+
+    #[test]
+    fn loads_pools_by_entry() {
+        let pools = load_pools("pools", POOLS).unwrap();
+        assert_eq!(pools.get(10), [(5, 60.5), (6, 39.5)]);
+        assert_eq!(pools.get(20), [(7, 100.0)]);
+        assert!(pools.get(30).is_empty());
+        assert!(!pools.is_empty());
+
+        let err = load_pools("pools-without-chance", "entry,ench\n10,5\n").unwrap_err();
+        assert!(err.to_string().contains("no `chance` column"), "{err}");
+    }
+
+    #[test]
+    fn scales_suffixes_with_the_budget() {
+        let tables = tables();
+        let resolver = tables.random_enchantments();
+        let pools = load_pools("suffix-pools", POOLS).unwrap();
+        let random = |item_level| RandomEnchantments {
+            property_pool: None,
+            suffix_pool: Some(10),
+            item_level,
+            variants: Vec::new(),
+        };
+        let bonuses = |rarity, slot| -> Vec<Vec<String>> {
+            resolver
+                .variants(&random(100), rarity, slot, &pools)
+                .into_iter()
+                .map(|variant| variant.bonuses)
+                .collect()
+        };
+
+        // 70 points of a rare helm: 6666 and 4444 ten-thousandths, rounded down
+        let variants = resolver.variants(&random(100), Rarity::Rare, InventoryType::Head, &pools);
+        let [bear] = &variants[..] else {
+            panic!("expected the one suffix the tables have, got {variants:?}");
+        };
+        assert_eq!(
+            (bear.kind, bear.id, bear.chance),
+            (RandomKind::Suffix, 5, 60.5)
+        );
+        assert_eq!(bear.name, "of the Bear");
+        assert_eq!(bear.bonuses, ["+46 Stamina", "+31 Strength"]);
+        assert_eq!(
+            bear.stat_modifiers,
+            [
+                StatModifier {
+                    stat: StatType::Stamina,
+                    value: 46
+                },
+                StatModifier {
+                    stat: StatType::Strength,
+                    value: 31
+                },
+            ]
+        );
+
+        // Quality picks the column set, the slot the column
+        assert_eq!(
+            bonuses(Rarity::Epic, InventoryType::Ring),
+            [["+29 Stamina", "+19 Strength"]]
+        );
+        assert_eq!(
+            bonuses(Rarity::Uncommon, InventoryType::Trinket),
+            [["+26 Stamina", "+17 Strength"]]
+        );
+
+        // Without a budget the suffix would grant nothing
+        for rarity in [Rarity::Common, Rarity::Legendary, Rarity::Custom(7)] {
+            assert!(
+                bonuses(rarity, InventoryType::Head).is_empty(),
+                "{rarity:?}"
+            );
+        }
+        assert!(bonuses(Rarity::Rare, InventoryType::Bag).is_empty());
+        let unknown_level =
+            resolver.variants(&random(101), Rarity::Rare, InventoryType::Head, &pools);
+        assert!(unknown_level.is_empty());
+    }
+
+    #[test]
+    fn properties_need_no_budget() {
+        let tables = tables();
+        let pools = load_pools("property-pools", POOLS).unwrap();
+        let random = RandomEnchantments {
+            property_pool: Some(20),
+            suffix_pool: None,
+            item_level: 0,
+            variants: Vec::new(),
+        };
+        let variants = tables.random_enchantments().variants(
+            &random,
+            Rarity::Legendary,
+            InventoryType::Head,
+            &pools,
+        );
+        let [bear] = &variants[..] else {
+            panic!("expected one property, got {variants:?}");
+        };
+        assert_eq!(
+            (bear.kind, bear.id, bear.chance),
+            (RandomKind::Property, 7, 100.0)
+        );
+        assert_eq!(bear.bonuses, ["+8 Stamina"]);
+        assert_eq!(bear.stat_modifiers[0].value, 8);
+    }
+}
@@ -0,0 +1,485 @@
+use serde::{Deserialize, Serialize};
+use wow_dbc::wrath_tables::{faction::FactionKey, skill_line::SkillLineKey, spell::SpellKey};
+
+use crate::{
+    locale::LocalizedText,
+    parse::{
+        chr_classes::CharacterClasses, chr_races::CharacterRaces, faction::Factions,
+        skill_line::SkillLines, spells::Spells,
+    },
+    wdb::WdbItem,
+};
+
+/// Something a character needs before it can use an item.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Requirement {
+    Level(u32),
+    /// Bit `1 << (class id - 1)` per allowed ChrClasses entry.
+    Classes {
+        mask: u32,
+        names: Vec<String>,
+    },
+    /// Bit `1 << (race id - 1)` per allowed ChrRaces entry.
+    Races {
+        mask: u32,
+        names: Vec<String>,
+    },
+    Skill {
+        id: Option<i32>,
+        name: String,
+        rank: u32,
+    },
+    Reputation {
+        faction_id: Option<i32>,
+        faction: String,
+        standing: Standing,
+    },
+    Spell {
+        id: Option<i32>,
+        name: String,
+    },
+    /// An achievement, known only by the name on the tooltip.
+    Achievement {
+        name: String,
+    },
+    /// A `Requires ...` line that isn't a character requirement, such as
+    /// recipe reagents or meta gem conditions.
+    Unparsed(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Standing {
+    Hated,
+    Hostile,
+    Unfriendly,
+    Neutral,
+    Friendly,
+    Honored,
+    Revered,
+    Exalted,
+}
+
+impl Standing {
+    /// Map a client reputation rank (`0` hated .. `7` exalted).
+    pub fn from_rank(rank: u32) -> Self {
+        match rank {
+            0 => Standing::Hated,
+            1 => Standing::Hostile,
+            2 => Standing::Unfriendly,
+            3 => Standing::Neutral,
+            4 => Standing::Friendly,
+            5 => Standing::Honored,
+            6 => Standing::Revered,
+            _ => Standing::Exalted,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let standing = match name.trim().to_lowercase().as_str() {
+            "hated" => Standing::Hated,
+            "hostile" => Standing::Hostile,
+            "unfriendly" => Standing::Unfriendly,
+            "neutral" => Standing::Neutral,
+            "friendly" => Standing::Friendly,
+            "honored" => Standing::Honored,
+            "revered" => Standing::Revered,
+            "exalted" => Standing::Exalted,
+            _ => return None,
+        };
+        Some(standing)
+    }
+}
+
+/// Resolves class, race, skill, faction and spell ids of requirements
+/// through the client tables.
+pub struct RequirementResolver<'a> {
+    classes: &'a CharacterClasses,
+    races: &'a CharacterRaces,
+    skills: &'a SkillLines,
+    factions: &'a Factions,
+    spells: &'a Spells,
+}
+
+impl<'a> RequirementResolver<'a> {
+    pub fn new(
+        classes: &'a CharacterClasses,
+        races: &'a CharacterRaces,
+        skills: &'a SkillLines,
+        factions: &'a Factions,
+        spells: &'a Spells,
+    ) -> Self {
+        Self {
+            classes,
+            races,
+            skills,
+            factions,
+            spells,
+        }
+    }
+
+    /// Requirements of a record from a client `itemcache.wdb`.
+    pub fn resolve_wdb(&self, wdb_item: &WdbItem) -> Vec<Requirement> {
+        let mut requirements = Vec::new();
+        if let Some(classes) = self.classes_from_mask(wdb_item.allowable_class as u32) {
+            requirements.push(classes);
+        }
+        if let Some(races) = self.races_from_mask(wdb_item.allowable_race as u32) {
+            requirements.push(races);
+        }
+        if wdb_item.required_skill > 0 {
+            let id = wdb_item.required_skill as i32;
+            let name = self
+                .skills
+                .get(SkillLineKey::new(id))
+                .map(|skill| skill.display_name_lang.text().to_string())
+                .unwrap_or_default();
+            requirements.push(Requirement::Skill {
+                id: Some(id),
+                name,
+                rank: wdb_item.required_skill_rank,
+            });
+        }
+        if wdb_item.required_reputation_faction > 0 {
+            let id = wdb_item.required_reputation_faction as i32;
+            let faction = self
+                .factions
+                .get(FactionKey::new(id))
+                .map(|faction| faction.name_lang.text().to_string())
+                .unwrap_or_default();
+            requirements.push(Requirement::Reputation {
+                faction_id: Some(id),
+                faction,
+                standing: Standing::from_rank(wdb_item.required_reputation_rank),
+            });
+        }
+        if wdb_item.required_spell > 0 {
+            let id = wdb_item.required_spell as i32;
+            let name = self
+                .spells
+                .get(SpellKey::new(id))
+                .map(|spell| spell.name_lang.text().to_string())
+                .unwrap_or_default();
+            requirements.push(Requirement::Spell { id: Some(id), name });
+        }
+        requirements
+    }
+
+    /// Parse tooltip lines such as `Requires Blacksmithing (225)`,
+    /// `Requires The Sons of Hodir - Honored`, `Requires Achievement: Explorer`
+    /// or `Classes: Shaman`.
+    pub fn parse_lines(&self, lines: &[String]) -> Vec<Requirement> {
+        lines.iter().map(|line| self.parse_line(line)).collect()
+    }
+
+    fn parse_line(&self, line: &str) -> Requirement {
+        let unparsed = || Requirement::Unparsed(line.to_string());
+        let line = line.trim();
+
+        if let Some(names) = line.strip_prefix("Classes:") {
+            return self.classes_from_names(names).unwrap_or_else(unparsed);
+        }
+        if let Some(names) = line.strip_prefix("Races:") {
+            return self.races_from_names(names).unwrap_or_else(unparsed);
+        }
+        let Some(text) = line.strip_prefix("Requires ") else {
+            return unparsed();
+        };
+        if let Some(level) = text
+            .strip_prefix("Level ")
+            .or_else(|| text.strip_prefix("level "))
+            .and_then(|level| level.trim().parse().ok())
+        {
+            return Requirement::Level(level);
+        }
+        if let Some(name) = achievement_name(text) {
+            return Requirement::Achievement {
+                name: name.to_string(),
+            };
+        }
+        // Comma separated names are recipe reagents, `at least` meta gem rules
+        if text.contains(',') || text.starts_with("at least") || text.contains("Requires") {
+            return unparsed();
+        }
+
+        if let Some((faction, standing)) = text.rsplit_once(" - ")
+            && let Some(standing) = Standing::from_name(standing)
+        {
+            return Requirement::Reputation {
+                faction_id: self.factions.find_by_name(faction).map(|row| row.id.id),
+                faction: faction.to_string(),
+                standing,
+            };
+        }
+
+        if let Some((name, rank)) = text.strip_suffix(')').and_then(|t| t.rsplit_once(" (")) {
+            let Ok(rank) = rank.parse() else {
+                return unparsed();
+            };
+            return match self.skills.find_by_name(name) {
+                Some(skill) => Requirement::Skill {
+                    id: Some(skill.id.id),
+                    name: name.to_string(),
+                    rank,
+                },
+                // A single reagent with a count
+                None => unparsed(),
+            };
+        }
+
+        // Specialisations like `Requires Armorsmith` are learned spells
+        Requirement::Spell {
+            id: None,
+            name: text.to_string(),
+        }
+    }
+
+    /// Classes of `mask`, or `None` if it allows every class. Without a
+    /// ChrClasses table the mask is kept with no names.
+    fn classes_from_mask(&self, mask: u32) -> Option<Requirement> {
+        let all: u32 = self.classes.iter_rows().map(|row| bit(row.id.id)).sum();
+        if unrestricted(mask, all) {
+            return None;
+        }
+        let mut rows: Vec<_> = self
+            .classes
+            .iter_rows()
+            .filter(|row| mask & bit(row.id.id) != 0)
+            .collect();
+        rows.sort_by_key(|row| row.id.id);
+        Some(Requirement::Classes {
+            mask,
+            names: rows
+                .iter()
+                .map(|row| row.name_lang.text().to_string())
+                .collect(),
+        })
+    }
+
+    /// Races of `mask`, or `None` if it allows every race. Without a
+    /// ChrRaces table the mask is kept with no names.
+    fn races_from_mask(&self, mask: u32) -> Option<Requirement> {
+        let all: u32 = self.races.iter_rows().map(|row| bit(row.id.id)).sum();
+        if unrestricted(mask, all) {
+            return None;
+        }
+        let mut rows: Vec<_> = self
+            .races
+            .iter_rows()
+            .filter(|row| mask & bit(row.id.id) != 0)
+            .collect();
+        rows.sort_by_key(|row| row.id.id);
+        Some(Requirement::Races {
+            mask,
+            names: rows
+                .iter()
+                .map(|row| row.name_lang.text().to_string())
+                .collect(),
+        })
+    }
+
+    fn classes_from_names(&self, names: &str) -> Option<Requirement> {
+        let names: Vec<String> = names.split(',').map(|n| n.trim().to_string()).collect();
+        let mask = names
+            .iter()
+            .map(|name| {
+                self.classes
+                    .iter_rows()
+                    .find(|row| row.name_lang.text().eq_ignore_ascii_case(name))
+                    .map(|row| bit(row.id.id))
+            })
+            .sum::<Option<u32>>()?;
+        Some(Requirement::Classes { mask, names })
+    }
+
+    fn races_from_names(&self, names: &str) -> Option<Requirement> {
+        let names: Vec<String> = names.split(',').map(|n| n.trim().to_string()).collect();
+        let mask = names
+            .iter()
+            .map(|name| {
+                self.races
+                    .iter_rows()
+                    .find(|row| row.name_lang.text().eq_ignore_ascii_case(name))
+                    .map(|row| bit(row.id.id))
+            })
+            .sum::<Option<u32>>()?;
+        Some(Requirement::Races { mask, names })
+    }
+}
+
+/// Whether a class or race `mask` allows everyone, given the bits of `all`
+/// table entries. `0` and `-1` allow everyone even without the table.
+fn unrestricted(mask: u32, all: u32) -> bool {
+    mask == 0 || mask == u32::MAX || (all != 0 && mask & all == all)
+}
+
+/// Achievement of `Requires Achievement: Explorer`, past the `Requires`.
+fn achievement_name(text: &str) -> Option<&str> {
+    let text = text.strip_prefix("the ").unwrap_or(text);
+    let name = text
+        .strip_prefix("Achievement")
+        .or_else(|| text.strip_prefix("achievement"))?;
+    let name = name.strip_prefix(':')?.trim().trim_matches('"');
+    (!name.is_empty()).then_some(name)
+}
+
+/// Mask bit of a ChrClasses or ChrRaces id.
+pub fn bit(id: i32) -> u32 {
+    if (1..=32).contains(&id) {
+        1 << (id - 1)
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        locale::localized,
+        parse::{
+            DbcTables,
+            tests::{blank_row, parse_table},
+        },
+    };
+    use wow_dbc::wrath_tables::{
+        chr_classes::{ChrClasses, ChrClassesKey},
+        chr_races::{ChrRaces, ChrRacesKey},
+    };
+
+    /// Tables with the Warrior and Shaman classes and the Human and Orc
+    /// races.
+    fn tables() -> DbcTables {
+        let class = |id, name| {
+            let mut row = blank_row::<ChrClasses>(60);
+            row.id = ChrClassesKey::new(id);
+            row.name_lang = localized(name);
+            row
+        };
+        let race = |id, name| {
+            let mut row = blank_row::<ChrRaces>(69);
+            row.id = ChrRacesKey::new(id);
+            row.name_lang = localized(name);
+            row
+        };
+
+        let mut tables = DbcTables::default();
+        parse_table(
+            &mut tables.classes,
+            &ChrClasses {
+                rows: vec![class(1, "Warrior"), class(7, "Shaman")],
+            },
+        );
+        parse_table(
+            &mut tables.races,
+            &ChrRaces {
+                rows: vec![race(1, "Human"), race(2, "Orc")],
+            },
+        );
+        tables
+    }
+
+    fn resolver(tables: &DbcTables) -> RequirementResolver<'_> {
+        RequirementResolver::new(
+            &tables.classes,
+            &tables.races,
+            &tables.skill_lines,
+            &tables.factions,
+            &tables.spells,
+        )
+    }
+
+    fn wdb_item(allowable_class: i32, allowable_race: i32) -> WdbItem {
+        WdbItem {
+            allowable_class,
+            allowable_race,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolves_class_and_race_masks() {
+        let tables = tables();
+        let resolver = resolver(&tables);
+        assert_eq!(
+            resolver.resolve_wdb(&wdb_item(bit(7) as i32, bit(2) as i32)),
+            [
+                Requirement::Classes {
+                    mask: 64,
+                    names: vec!["Shaman".to_string()],
+                },
+                Requirement::Races {
+                    mask: 2,
+                    names: vec!["Orc".to_string()],
+                },
+            ]
+        );
+        // Every class of the table, or everyone at all
+        assert_eq!(resolver.resolve_wdb(&wdb_item(65, -1)), []);
+        assert_eq!(resolver.resolve_wdb(&wdb_item(0, 0)), []);
+    }
+
+    #[test]
+    fn keeps_masks_without_their_tables() {
+        let tables = DbcTables::default();
+        let resolver = resolver(&tables);
+        assert_eq!(
+            resolver.resolve_wdb(&wdb_item(64, 2)),
+            [
+                Requirement::Classes {
+                    mask: 64,
+                    names: Vec::new(),
+                },
+                Requirement::Races {
+                    mask: 2,
+                    names: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(resolver.resolve_wdb(&wdb_item(-1, -1)), []);
+    }
+
+    #[test]
+    fn parses_requirement_lines() {
+        let tables = tables();
+        let lines = [
+            "Requires Level 40",
+            "Classes: Shaman, Warrior",
+            "Classes: Paladin",
+            "Requires Achievement: \"Explorer\"",
+            "Requires the Achievement: Loremaster",
+            "Requires The Sons of Hodir - Honored",
+            "Requires Armorsmith",
+            "Requires Heavy Leather (4)",
+            "Requires at least 2 Red gems",
+        ]
+        .map(str::to_string);
+        assert_eq!(
+            resolver(&tables).parse_lines(&lines),
+            [
+                Requirement::Level(40),
+                Requirement::Classes {
+                    mask: 65,
+                    names: vec!["Shaman".to_string(), "Warrior".to_string()],
+                },
+                Requirement::Unparsed("Classes: Paladin".to_string()),
+                Requirement::Achievement {
+                    name: "Explorer".to_string(),
+                },
+                Requirement::Achievement {
+                    name: "Loremaster".to_string(),
+                },
+                Requirement::Reputation {
+                    faction_id: None,
+                    faction: "The Sons of Hodir".to_string(),
+                    standing: Standing::Honored,
+                },
+                Requirement::Spell {
+                    id: None,
+                    name: "Armorsmith".to_string(),
+                },
+                Requirement::Unparsed("Requires Heavy Leather (4)".to_string()),
+                Requirement::Unparsed("Requires at least 2 Red gems".to_string()),
+            ]
+        );
+    }
+}
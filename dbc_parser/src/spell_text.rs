@@ -0,0 +1,603 @@
+use std::collections::HashMap;
+use wow_dbc::wrath_tables::{
+    spell::{SpellKey, SpellRow},
+    spell_radius::SpellRadiusKey,
+};
+
+use crate::{
+    locale::LocalizedText,
+    parse::{
+        spell_description_vars::SpellDescriptionVars, spell_durations::SpellDurations,
+        spell_radius::SpellRadii, spells::Spells,
+    },
+};
+
+/// How deep `$<var>` definitions and cross-spell references may nest.
+const MAX_DEPTH: usize = 8;
+
+/// Substitutes the `$` tokens of spell descriptions with values from the
+/// spell tables, producing the text the client shows in tooltips.
+///
+/// Supported tokens:
+/// - `$s1` `$m1` `$M1` effect points (`$s` shows `min to max` for dice rolls)
+/// - `$o1` periodic total, `$t1` tick period, `$a1` radius, `$d` duration
+/// - `$n` proc charges, `$h` proc chance, `$u` max stacks, `$x1` chain targets,
+///   `$q1` misc value, `$b1` points per combo, `$i` max targets, `$v` max target level
+/// - `$12345s1` any of the above taken from another spell
+/// - `$/10;s1` and `$*2;s1` scaled values, `${$m1*2}` arithmetic expressions
+/// - `$<name>` definitions from SpellDescriptionVariables
+/// - `$lsingular:plural;`, `$gmale:female;` and `$?cond[yes][no]` (renders `no`)
+pub struct SpellTextRenderer<'a> {
+    spells: &'a Spells,
+    durations: &'a SpellDurations,
+    radii: &'a SpellRadii,
+    desc_vars: &'a SpellDescriptionVars,
+}
+
+impl<'a> SpellTextRenderer<'a> {
+    pub fn new(
+        spells: &'a Spells,
+        durations: &'a SpellDurations,
+        radii: &'a SpellRadii,
+        desc_vars: &'a SpellDescriptionVars,
+    ) -> Self {
+        Self {
+            spells,
+            durations,
+            radii,
+            desc_vars,
+        }
+    }
+
+    /// Render the description of `spell`.
+    pub fn render_description(&self, spell: &SpellRow) -> String {
+        self.render(spell, spell.description_lang.text())
+    }
+
+    /// Render `text` using `spell` as the context for unqualified tokens.
+    pub fn render(&self, spell: &SpellRow, text: &str) -> String {
+        let variables = self.variables(spell);
+        let mut state = RenderState {
+            renderer: self,
+            spell,
+            variables: &variables,
+            last_number: 0.0,
+        };
+        let chars: Vec<char> = text.chars().collect();
+        state.render(&chars, Mode::Text, 0)
+    }
+
+    /// `$name=definition` lines from the spell's SpellDescriptionVariables.
+    fn variables(&self, spell: &SpellRow) -> HashMap<String, String> {
+        let Some(row) = self.desc_vars.get(spell.description_variables_id) else {
+            return HashMap::new();
+        };
+        row.variables
+            .lines()
+            .filter_map(|line| {
+                let (name, definition) = line.trim().strip_prefix('$')?.split_once('=')?;
+                Some((name.trim().to_lowercase(), definition.trim().to_string()))
+            })
+            .collect()
+    }
+
+    fn duration_ms(&self, spell: &SpellRow) -> i32 {
+        self.durations
+            .get(spell.duration_index)
+            .map(|duration| duration.duration)
+            .unwrap_or(0)
+    }
+
+    /// Resolve a single token of `spell` to its value.
+    fn resolve(&self, spell: &SpellRow, kind: char, index: usize) -> Option<Value> {
+        let index = index.min(2);
+        let base = spell.effect_base_points[index];
+        let dice = spell.effect_die_sides[index];
+        // Client rolls `base + 1 ..= base + dice`, or exactly `base` without dice
+        let min = (base + dice.min(1)) as f64;
+        let max = (base + dice) as f64;
+
+        let value = match kind {
+            's' | 'S' if min.abs() != max.abs() && dice > 1 => Value::Range(min.abs(), max.abs()),
+            's' | 'S' | 'm' => Value::Number(min.abs()),
+            'M' => Value::Number(max.abs()),
+            'o' | 'O' => {
+                let period = spell.effect_aura_period[index];
+                let duration = self.duration_ms(spell);
+                let ticks = if period > 0 && duration > 0 {
+                    (duration / period) as f64
+                } else {
+                    1.0
+                };
+                Value::Number(min.abs() * ticks)
+            }
+            't' | 'T' => Value::Number(spell.effect_aura_period[index] as f64 / 1000.0),
+            'a' | 'A' => {
+                let radius = self
+                    .radii
+                    .get(SpellRadiusKey::new(spell.effect_radius_index[index]))?;
+                Value::Number(radius.radius as f64)
+            }
+            'd' | 'D' => Value::Duration(self.duration_ms(spell)),
+            'n' | 'N' => Value::Number(spell.proc_charges as f64),
+            'h' | 'H' => Value::Number(spell.proc_chance as f64),
+            'u' | 'U' => Value::Number(spell.cumulative_aura as f64),
+            'x' | 'X' => Value::Number(spell.effect_chain_targets[index] as f64),
+            'q' | 'Q' => Value::Number(spell.effect_misc_value[index] as f64),
+            'b' | 'B' => Value::Number(spell.effect_points_per_combo[index] as f64),
+            'i' | 'I' => Value::Number(spell.max_targets as f64),
+            'v' | 'V' => Value::Number(spell.max_target_level as f64),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Human readable output, e.g. `5 to 10` or `30 sec`.
+    Text,
+    /// Plain numbers, used inside `${...}` expressions.
+    Numeric,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Number(f64),
+    Range(f64, f64),
+    /// Milliseconds, `<= 0` meaning no fixed duration.
+    Duration(i32),
+}
+
+impl Value {
+    fn scale(self, factor: f64) -> Self {
+        match self {
+            Value::Number(n) => Value::Number(n * factor),
+            Value::Range(min, max) => Value::Range(min * factor, max * factor),
+            Value::Duration(ms) => Value::Duration((ms as f64 * factor) as i32),
+        }
+    }
+
+    fn number(self) -> f64 {
+        match self {
+            Value::Number(n) => n,
+            Value::Range(min, _) => min,
+            Value::Duration(ms) => ms as f64 / 1000.0,
+        }
+    }
+
+    fn format(self, mode: Mode) -> String {
+        match (self, mode) {
+            (_, Mode::Numeric) => format_number(self.number()),
+            (Value::Number(n), Mode::Text) => format_number(n),
+            (Value::Range(min, max), Mode::Text) => {
+                format!("{} to {}", format_number(min), format_number(max))
+            }
+            (Value::Duration(ms), Mode::Text) => format_duration(ms),
+        }
+    }
+}
+
+/// A token such as `s1` or `12345d`.
+struct Reference {
+    spell_id: Option<i32>,
+    kind: char,
+    index: usize,
+}
+
+struct RenderState<'r, 'a> {
+    renderer: &'r SpellTextRenderer<'a>,
+    spell: &'r SpellRow,
+    variables: &'r HashMap<String, String>,
+    /// Last number written, used to pick between `$l` singular and plural.
+    last_number: f64,
+}
+
+impl RenderState<'_, '_> {
+    fn render(&mut self, chars: &[char], mode: Mode, depth: usize) -> String {
+        let mut out = String::new();
+        let mut pos = 0;
+        while pos < chars.len() {
+            if chars[pos] != '$' || depth > MAX_DEPTH {
+                out.push(chars[pos]);
+                pos += 1;
+                continue;
+            }
+            pos += 1;
+            match chars.get(pos) {
+                Some('$') => {
+                    out.push('$');
+                    pos += 1;
+                }
+                Some('{') => {
+                    let Some(end) = find_closing(chars, pos, '{', '}') else {
+                        out.push('$');
+                        continue;
+                    };
+                    let inner = self.render(&chars[pos + 1..end], Mode::Numeric, depth + 1);
+                    match evaluate(&inner) {
+                        Some(n) => self.write_value(&mut out, Value::Number(n), mode),
+                        None => out.push_str(&inner),
+                    }
+                    pos = end + 1;
+                    // Optional `.N` precision suffix, e.g. `${$m1/10}.1`
+                    if chars.get(pos) == Some(&'.')
+                        && chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit())
+                    {
+                        pos += 2;
+                    }
+                }
+                Some('<') => {
+                    let Some(end) = chars[pos..].iter().position(|c| *c == '>') else {
+                        out.push('$');
+                        continue;
+                    };
+                    let name: String = chars[pos + 1..pos + end].iter().collect();
+                    if let Some(definition) = self.variables.get(&name.to_lowercase()) {
+                        let definition: Vec<char> = definition.chars().collect();
+                        out.push_str(&self.render(&definition, mode, depth + 1));
+                    }
+                    pos += end + 1;
+                }
+                Some('/') | Some('*') => {
+                    let divide = chars[pos] == '/';
+                    let Some(end) = chars[pos..].iter().position(|c| *c == ';') else {
+                        out.push('$');
+                        continue;
+                    };
+                    let factor: String = chars[pos + 1..pos + end].iter().collect();
+                    pos += end + 1;
+                    let factor = factor.trim().parse::<f64>().unwrap_or(1.0);
+                    let factor = if divide && factor != 0.0 {
+                        1.0 / factor
+                    } else if divide {
+                        1.0
+                    } else {
+                        factor
+                    };
+                    match parse_reference(chars, &mut pos) {
+                        Some(reference) => match self.lookup(&reference, depth) {
+                            Some(value) => self.write_value(&mut out, value.scale(factor), mode),
+                            None => out.push('0'),
+                        },
+                        None => out.push('$'),
+                    }
+                }
+                Some('l') | Some('L') => {
+                    let Some(end) = chars[pos..].iter().position(|c| *c == ';') else {
+                        out.push('$');
+                        continue;
+                    };
+                    let forms: String = chars[pos + 1..pos + end].iter().collect();
+                    let (singular, plural) = forms.split_once(':').unwrap_or((&forms, &forms));
+                    if self.last_number == 1.0 {
+                        out.push_str(singular);
+                    } else {
+                        out.push_str(plural);
+                    }
+                    pos += end + 1;
+                }
+                Some('g') | Some('G') => {
+                    let Some(end) = chars[pos..].iter().position(|c| *c == ';') else {
+                        out.push('$');
+                        continue;
+                    };
+                    let forms: String = chars[pos + 1..pos + end].iter().collect();
+                    out.push_str(forms.split(':').next().unwrap_or_default());
+                    pos += end + 1;
+                }
+                Some('?') => {
+                    let (branch, end) = skip_condition(chars, pos);
+                    if let Some((start, stop)) = branch {
+                        out.push_str(&self.render(&chars[start..stop], mode, depth + 1));
+                    }
+                    pos = end;
+                }
+                Some(_) => {
+                    let start = pos;
+                    match parse_reference(chars, &mut pos) {
+                        Some(reference) => match self.lookup(&reference, depth) {
+                            Some(value) => self.write_value(&mut out, value, mode),
+                            None => {
+                                out.push('$');
+                                out.extend(&chars[start..pos]);
+                            }
+                        },
+                        None => out.push('$'),
+                    }
+                }
+                None => out.push('$'),
+            }
+        }
+        out
+    }
+
+    fn lookup(&self, reference: &Reference, depth: usize) -> Option<Value> {
+        match reference.spell_id {
+            Some(id) if depth <= MAX_DEPTH => {
+                let spell = self.renderer.spells.get(SpellKey::new(id))?;
+                self.renderer
+                    .resolve(spell, reference.kind, reference.index)
+            }
+            Some(_) => None,
+            None => self
+                .renderer
+                .resolve(self.spell, reference.kind, reference.index),
+        }
+    }
+
+    fn write_value(&mut self, out: &mut String, value: Value, mode: Mode) {
+        self.last_number = match value {
+            Value::Range(_, max) => max,
+            _ => value.number(),
+        };
+        out.push_str(&value.format(mode));
+    }
+}
+
+/// Parse `[spell id]<letter>[effect index]` at `pos`, advancing past it.
+fn parse_reference(chars: &[char], pos: &mut usize) -> Option<Reference> {
+    let mut cursor = *pos;
+    let digits_start = cursor;
+    while chars.get(cursor).is_some_and(|c| c.is_ascii_digit()) {
+        cursor += 1;
+    }
+    let spell_id = if cursor > digits_start {
+        let digits: String = chars[digits_start..cursor].iter().collect();
+        Some(digits.parse().ok()?)
+    } else {
+        None
+    };
+
+    let kind = *chars.get(cursor).filter(|c| c.is_ascii_alphabetic())?;
+    cursor += 1;
+
+    let index = match chars.get(cursor) {
+        Some(c @ '1'..='3') => {
+            cursor += 1;
+            *c as usize - '1' as usize
+        }
+        _ => 0,
+    };
+
+    *pos = cursor;
+    Some(Reference {
+        spell_id,
+        kind,
+        index,
+    })
+}
+
+/// Index of the bracket closing the one opened at `open_pos`.
+fn find_closing(chars: &[char], open_pos: usize, open: char, close: char) -> Option<usize> {
+    let mut level = 0;
+    for (i, c) in chars.iter().enumerate().skip(open_pos) {
+        if *c == open {
+            level += 1;
+        } else if *c == close {
+            level -= 1;
+            if level == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Skip a `?cond[a]?cond[b][c]` chain starting at `pos` (on the `?`).
+///
+/// Conditions depend on the player (known spells, auras), so the final
+/// fallback branch is returned as the range to render, alongside the position
+/// after the whole chain.
+fn skip_condition(chars: &[char], mut pos: usize) -> (Option<(usize, usize)>, usize) {
+    let mut fallback = None;
+    while chars.get(pos) == Some(&'?') {
+        let Some(open) = chars[pos..].iter().position(|c| *c == '[') else {
+            return (None, chars.len());
+        };
+        let Some(close) = find_closing(chars, pos + open, '[', ']') else {
+            return (None, chars.len());
+        };
+        pos = close + 1;
+        fallback = None;
+        if chars.get(pos) == Some(&'[') {
+            let Some(close) = find_closing(chars, pos, '[', ']') else {
+                return (None, chars.len());
+            };
+            fallback = Some((pos + 1, close));
+            pos = close + 1;
+            break;
+        }
+    }
+    (fallback, pos)
+}
+
+/// Evaluate `+ - * /` arithmetic with parentheses.
+fn evaluate(expression: &str) -> Option<f64> {
+    let tokens: Vec<char> = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut pos = 0;
+    let value = parse_sum(&tokens, &mut pos)?;
+    (pos == tokens.len()).then_some(value)
+}
+
+fn parse_sum(tokens: &[char], pos: &mut usize) -> Option<f64> {
+    let mut value = parse_product(tokens, pos)?;
+    while let Some(op @ ('+' | '-')) = tokens.get(*pos) {
+        *pos += 1;
+        let rhs = parse_product(tokens, pos)?;
+        if *op == '+' {
+            value += rhs;
+        } else {
+            value -= rhs;
+        }
+    }
+    Some(value)
+}
+
+fn parse_product(tokens: &[char], pos: &mut usize) -> Option<f64> {
+    let mut value = parse_atom(tokens, pos)?;
+    while let Some(op @ ('*' | '/')) = tokens.get(*pos) {
+        *pos += 1;
+        let rhs = parse_atom(tokens, pos)?;
+        if *op == '*' {
+            value *= rhs;
+        } else if rhs != 0.0 {
+            value /= rhs;
+        }
+    }
+    Some(value)
+}
+
+fn parse_atom(tokens: &[char], pos: &mut usize) -> Option<f64> {
+    match tokens.get(*pos)? {
+        '(' => {
+            *pos += 1;
+            let value = parse_sum(tokens, pos)?;
+            (tokens.get(*pos) == Some(&')')).then(|| *pos += 1)?;
+            Some(value)
+        }
+        '-' => {
+            *pos += 1;
+            parse_atom(tokens, pos).map(|value| -value)
+        }
+        _ => {
+            let start = *pos;
+            while tokens
+                .get(*pos)
+                .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+            {
+                *pos += 1;
+            }
+            let number: String = tokens[start..*pos].iter().collect();
+            number.parse().ok()
+        }
+    }
+}
+
+/// Whole numbers without decimals, everything else with one decimal.
+fn format_number(value: f64) -> String {
+    if (value - value.round()).abs() < 0.05 {
+        format!("{}", value.round() as i64)
+    } else {
+        format!("{value:.1}")
+    }
+}
+
+fn format_duration(ms: i32) -> String {
+    if ms <= 0 {
+        return "until cancelled".to_string();
+    }
+    let seconds = ms as f64 / 1000.0;
+    if seconds >= 3600.0 {
+        let hours = seconds / 3600.0;
+        let unit = if hours == 1.0 { "hour" } else { "hrs" };
+        format!("{} {unit}", format_number(hours))
+    } else if seconds >= 60.0 {
+        format!("{} min", format_number(seconds / 60.0))
+    } else {
+        format!("{} sec", format_number(seconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Handler;
+    use wow_dbc::{
+        DbcTable,
+        wrath_tables::{
+            spell::Spell,
+            spell_duration::{SpellDuration, SpellDurationKey, SpellDurationRow},
+        },
+    };
+    use wow_mpq::FileEntry;
+
+    /// Write `table` and parse it back into `handler`.
+    fn parse_table(handler: &mut impl Handler, table: &impl DbcTable) {
+        let mut data = Vec::new();
+        table.write(&mut data).unwrap();
+        let entry = FileEntry {
+            name: "DBFilesClient\\Test.dbc".to_string(),
+            size: data.len() as u64,
+            compressed_size: data.len() as u64,
+            flags: 0,
+            hashes: None,
+            table_indices: None,
+        };
+        handler.parse(&entry, &data).unwrap();
+    }
+
+    /// Spell with the points of its first effect rolling
+    /// `base + 1 ..= base + dice`.
+    fn spell(id: i32, duration_index: i32, base: i32, dice: i32, description: &str) -> SpellRow {
+        // An all zero record, the spell row has no constructor
+        let mut data = b"WDBC".to_vec();
+        for value in [1u32, 234, 936, 1] {
+            data.extend(value.to_le_bytes());
+        }
+        data.resize(data.len() + 936 + 1, 0);
+        let mut row = Spell::read(&mut data.as_slice()).unwrap().rows.remove(0);
+
+        row.id = SpellKey::new(id);
+        row.duration_index = SpellDurationKey::new(duration_index);
+        row.effect_base_points[0] = base;
+        row.effect_die_sides[0] = dice;
+        row.description_lang.en_gb = description.to_string();
+        row
+    }
+
+    fn render(rows: Vec<SpellRow>) -> Vec<String> {
+        let ids: Vec<SpellKey> = rows.iter().map(|row| row.id).collect();
+        let mut spells = Spells::default();
+        parse_table(&mut spells, &Spell { rows });
+        let mut durations = SpellDurations::default();
+        parse_table(
+            &mut durations,
+            &SpellDuration {
+                rows: vec![SpellDurationRow {
+                    id: SpellDurationKey::new(1),
+                    duration: 30_000,
+                    duration_per_level: 0,
+                    max_duration: 30_000,
+                }],
+            },
+        );
+        let (radii, desc_vars) = (SpellRadii::default(), SpellDescriptionVars::default());
+
+        let renderer = SpellTextRenderer::new(&spells, &durations, &radii, &desc_vars);
+        ids.into_iter()
+            .map(|id| renderer.render_description(spells.get(id).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_effect_points_and_duration() {
+        let rendered = render(vec![
+            spell(1, 1, 9, 1, "Deals $s1 damage over $d."),
+            spell(2, 0, 4, 6, "Heals for $s1."),
+            spell(3, 0, 0, 0, "Lasts $d."),
+        ]);
+        assert_eq!(
+            rendered,
+            [
+                "Deals 10 damage over 30 sec.",
+                "Heals for 5 to 10.",
+                "Lasts until cancelled."
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_scaled_and_cross_spell_tokens() {
+        let rendered = render(vec![
+            spell(1, 0, 49, 1, "Restores $/10;s1 mana."),
+            spell(2, 1, 0, 0, "Increases damage by $12345s1 for $12345d."),
+            spell(12345, 1, 19, 1, ""),
+            spell(3, 0, 0, 0, "Copies $99999s1."),
+        ]);
+        assert_eq!(rendered[0], "Restores 5 mana.");
+        assert_eq!(rendered[1], "Increases damage by 20 for 30 sec.");
+        // Spells the table lacks leave their token as is
+        assert_eq!(rendered[3], "Copies $99999s1.");
+    }
+}
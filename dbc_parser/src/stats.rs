@@ -0,0 +1,432 @@
+use serde::{Deserialize, Serialize};
+
+/// Item stat kinds, covering the `ITEM_MOD_*` types and resistances.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatType {
+    Mana,
+    Health,
+    Agility,
+    Strength,
+    Intellect,
+    Spirit,
+    Stamina,
+    DefenseRating,
+    DodgeRating,
+    ParryRating,
+    BlockRating,
+    HitRating,
+    CriticalStrikeRating,
+    HasteRating,
+    ResilienceRating,
+    ExpertiseRating,
+    ArmorPenetrationRating,
+    AttackPower,
+    RangedAttackPower,
+    FeralAttackPower,
+    SpellHealing,
+    SpellDamage,
+    SpellPower,
+    SpellPenetration,
+    ManaRegeneration,
+    HealthRegeneration,
+    BlockValue,
+    Armor,
+    HolyResistance,
+    FireResistance,
+    NatureResistance,
+    FrostResistance,
+    ShadowResistance,
+    ArcaneResistance,
+    AllResistances,
+}
+
+impl StatType {
+    /// Map an `ITEM_MOD_*` stat type from item data.
+    ///
+    /// Melee, ranged and spell variants of a rating collapse into the
+    /// combined rating, as the 3.3.5 client shows them.
+    pub fn from_item_mod(stat_type: u32) -> Option<Self> {
+        let stat = match stat_type {
+            0 => StatType::Mana,
+            1 => StatType::Health,
+            3 => StatType::Agility,
+            4 => StatType::Strength,
+            5 => StatType::Intellect,
+            6 => StatType::Spirit,
+            7 => StatType::Stamina,
+            12 => StatType::DefenseRating,
+            13 => StatType::DodgeRating,
+            14 => StatType::ParryRating,
+            15 => StatType::BlockRating,
+            16 | 17 | 18 | 31 => StatType::HitRating,
+            19 | 20 | 21 | 32 => StatType::CriticalStrikeRating,
+            28 | 29 | 30 | 36 => StatType::HasteRating,
+            35 => StatType::ResilienceRating,
+            37 => StatType::ExpertiseRating,
+            38 => StatType::AttackPower,
+            39 => StatType::RangedAttackPower,
+            40 => StatType::FeralAttackPower,
+            41 => StatType::SpellHealing,
+            42 => StatType::SpellDamage,
+            43 => StatType::ManaRegeneration,
+            44 => StatType::ArmorPenetrationRating,
+            45 => StatType::SpellPower,
+            46 => StatType::HealthRegeneration,
+            47 => StatType::SpellPenetration,
+            48 => StatType::BlockValue,
+            _ => return None,
+        };
+        Some(stat)
+    }
+
+    /// Resistance of a spell school (`1` holy .. `6` arcane).
+    pub fn resistance(school: u32) -> Option<Self> {
+        let stat = match school {
+            1 => StatType::HolyResistance,
+            2 => StatType::FireResistance,
+            3 => StatType::NatureResistance,
+            4 => StatType::FrostResistance,
+            5 => StatType::ShadowResistance,
+            6 => StatType::ArcaneResistance,
+            _ => return None,
+        };
+        Some(stat)
+    }
+
+    /// Name used in tooltip lines such as `+5 Stamina`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StatType::Mana => "Mana",
+            StatType::Health => "Health",
+            StatType::Agility => "Agility",
+            StatType::Strength => "Strength",
+            StatType::Intellect => "Intellect",
+            StatType::Spirit => "Spirit",
+            StatType::Stamina => "Stamina",
+            StatType::DefenseRating => "Defense Rating",
+            StatType::DodgeRating => "Dodge Rating",
+            StatType::ParryRating => "Parry Rating",
+            StatType::BlockRating => "Block Rating",
+            StatType::HitRating => "Hit Rating",
+            StatType::CriticalStrikeRating => "Critical Strike Rating",
+            StatType::HasteRating => "Haste Rating",
+            StatType::ResilienceRating => "Resilience Rating",
+            StatType::ExpertiseRating => "Expertise Rating",
+            StatType::ArmorPenetrationRating => "Armor Penetration Rating",
+            StatType::AttackPower => "Attack Power",
+            StatType::RangedAttackPower => "Ranged Attack Power",
+            StatType::FeralAttackPower => {
+                "Attack Power in Cat, Bear, Dire Bear, and Moonkin forms only"
+            }
+            StatType::SpellHealing => "Healing",
+            StatType::SpellDamage => "Spell Damage",
+            StatType::SpellPower => "Spell Power",
+            StatType::SpellPenetration => "Spell Penetration",
+            StatType::ManaRegeneration => "Mana every 5 seconds",
+            StatType::HealthRegeneration => "Health every 5 seconds",
+            StatType::BlockValue => "Block Value",
+            StatType::Armor => "Armor",
+            StatType::HolyResistance => "Holy Resistance",
+            StatType::FireResistance => "Fire Resistance",
+            StatType::NatureResistance => "Nature Resistance",
+            StatType::FrostResistance => "Frost Resistance",
+            StatType::ShadowResistance => "Shadow Resistance",
+            StatType::ArcaneResistance => "Arcane Resistance",
+            StatType::AllResistances => "to All Resistances",
+        }
+    }
+
+    /// Parse the name part of a `+N <name>` line.
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        // Mana regeneration is written as `+N Mana every 5 seconds`
+        if name.starts_with("mana every") || name.starts_with("mana per") {
+            return Some(StatType::ManaRegeneration);
+        }
+        if name.starts_with("health every") || name.starts_with("health per") {
+            return Some(StatType::HealthRegeneration);
+        }
+        if name.starts_with("attack power in cat") {
+            return Some(StatType::FeralAttackPower);
+        }
+        let stat = match name.as_str() {
+            "mana" => StatType::Mana,
+            "health" => StatType::Health,
+            "agility" => StatType::Agility,
+            "strength" => StatType::Strength,
+            "intellect" => StatType::Intellect,
+            "spirit" => StatType::Spirit,
+            "stamina" => StatType::Stamina,
+            "defense rating" | "defense" => StatType::DefenseRating,
+            "dodge rating" => StatType::DodgeRating,
+            "parry rating" => StatType::ParryRating,
+            "block rating" | "shield block rating" => StatType::BlockRating,
+            "hit rating" => StatType::HitRating,
+            "critical strike rating" | "crit rating" => StatType::CriticalStrikeRating,
+            "haste rating" => StatType::HasteRating,
+            "resilience rating" | "resilience" => StatType::ResilienceRating,
+            "expertise rating" => StatType::ExpertiseRating,
+            "armor penetration rating" => StatType::ArmorPenetrationRating,
+            "attack power" => StatType::AttackPower,
+            "ranged attack power" => StatType::RangedAttackPower,
+            "healing" | "healing spells" => StatType::SpellHealing,
+            "spell damage" => StatType::SpellDamage,
+            "spell power" => StatType::SpellPower,
+            "spell penetration" => StatType::SpellPenetration,
+            "block value" | "block" => StatType::BlockValue,
+            "armor" => StatType::Armor,
+            "holy resistance" => StatType::HolyResistance,
+            "fire resistance" => StatType::FireResistance,
+            "nature resistance" => StatType::NatureResistance,
+            "frost resistance" => StatType::FrostResistance,
+            "shadow resistance" => StatType::ShadowResistance,
+            "arcane resistance" => StatType::ArcaneResistance,
+            "to all resistances" | "all resistances" | "resist all" => StatType::AllResistances,
+            _ => return None,
+        };
+        Some(stat)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatModifier {
+    pub stat: StatType,
+    pub value: i32,
+}
+
+impl StatModifier {
+    /// Tooltip line for this modifier, e.g. `+5 Stamina`.
+    pub fn display(&self) -> String {
+        format!("{:+} {}", self.value, self.stat.name())
+    }
+}
+
+/// A stat line of an item, typed where it could be recognised.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ItemStat {
+    Modifier(StatModifier),
+    Unparsed(String),
+}
+
+/// `Equip:` phrases that grant a flat stat, matched on lowercase text.
+const EQUIP_PATTERNS: &[(&str, StatType)] = &[
+    ("critical strike rating by", StatType::CriticalStrikeRating),
+    ("hit rating by", StatType::HitRating),
+    ("haste rating by", StatType::HasteRating),
+    ("resilience rating by", StatType::ResilienceRating),
+    ("expertise rating by", StatType::ExpertiseRating),
+    (
+        "armor penetration rating by",
+        StatType::ArmorPenetrationRating,
+    ),
+    ("defense rating by", StatType::DefenseRating),
+    ("dodge rating by", StatType::DodgeRating),
+    ("parry rating by", StatType::ParryRating),
+    ("block rating by", StatType::BlockRating),
+    ("block value of your shield by", StatType::BlockValue),
+    ("spell penetration by", StatType::SpellPenetration),
+    ("increases spell power by", StatType::SpellPower),
+    (
+        "damage and healing done by magical spells and effects by up to",
+        StatType::SpellPower,
+    ),
+    (
+        "healing done by spells and effects by up to",
+        StatType::SpellHealing,
+    ),
+    (
+        "increases ranged attack power by",
+        StatType::RangedAttackPower,
+    ),
+    ("increases attack power by", StatType::AttackPower),
+    ("mana per 5 sec", StatType::ManaRegeneration),
+    ("health per 5 sec", StatType::HealthRegeneration),
+];
+
+/// Parse the stat lines of an item, plus flat stats granted by its
+/// `Equip:` spell lines.
+pub fn parse_item_stats(stats: &[String], spells: &[String]) -> Vec<ItemStat> {
+    let mut parsed: Vec<ItemStat> = stats
+        .iter()
+        .flat_map(|line| parse_stat_line(line))
+        .collect();
+    parsed.extend(
+        spells
+            .iter()
+            .filter_map(|line| parse_equip_line(line))
+            .map(ItemStat::Modifier),
+    );
+    parsed
+}
+
+/// Parse a line like `+5 Stamina` or `+6 Spell Power and +6 Intellect`.
+///
+/// The line is only split on `and` if every part is a stat, so names that
+/// contain the word, such as the feral attack power line, stay whole.
+pub fn parse_stat_line(line: &str) -> Vec<ItemStat> {
+    if let Some(modifier) = parse_modifier(line) {
+        return vec![ItemStat::Modifier(modifier)];
+    }
+    let parts: Option<Vec<_>> = line.split(" and ").map(parse_modifier).collect();
+    match parts {
+        Some(parts) if parts.len() > 1 => parts.into_iter().map(ItemStat::Modifier).collect(),
+        _ => vec![ItemStat::Unparsed(line.trim().to_string())],
+    }
+}
+
+fn parse_modifier(part: &str) -> Option<StatModifier> {
+    let part = part.trim();
+    let (value, name) = part.split_once(' ')?;
+    let value: i32 = value.strip_prefix('+').unwrap_or(value).parse().ok()?;
+    let stat = StatType::from_name(name)?;
+    Some(StatModifier { stat, value })
+}
+
+/// Parse a flat stat from a line like `Equip: Improves hit rating by 10.`
+///
+/// Procs such as `Your melee attacks have a chance to increase your haste
+/// rating by 200 for 10 sec.` don't grant a flat stat and yield nothing.
+pub fn parse_equip_line(line: &str) -> Option<StatModifier> {
+    let text = line.trim().to_lowercase();
+    let text = text.strip_prefix("equip:")?.trim();
+    if is_proc(text) {
+        return None;
+    }
+    let &(pattern, mut stat) = EQUIP_PATTERNS
+        .iter()
+        .find(|(pattern, _)| text.contains(pattern))?;
+    if stat == StatType::AttackPower && text.contains("forms only") {
+        stat = StatType::FeralAttackPower;
+    }
+    let offset = text.find(pattern)?;
+    // Regeneration lines carry the value before the phrase
+    let value = if pattern.ends_with("per 5 sec") {
+        last_number(&text[..offset])?
+    } else {
+        first_number(&text[offset + pattern.len()..])?
+    };
+    Some(StatModifier { stat, value })
+}
+
+/// Whether an `Equip:` line describes a triggered effect rather than a
+/// permanent bonus: a chance, a duration or a triggering event.
+fn is_proc(text: &str) -> bool {
+    const TRIGGERS: &[&str] = &["chance to", "when ", "whenever", "each time", "every time"];
+    if TRIGGERS.iter().any(|trigger| text.contains(trigger)) {
+        return true;
+    }
+    // `for 10 sec.`, but not `per 5 sec.`
+    text.match_indices("for ").any(|(offset, pattern)| {
+        let rest = &text[offset + pattern.len()..];
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        digits > 0 && rest[digits..].trim_start().starts_with("sec")
+    })
+}
+
+fn first_number(text: &str) -> Option<i32> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .find(|s| !s.is_empty())?
+        .parse()
+        .ok()
+}
+
+fn last_number(text: &str) -> Option<i32> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .rfind(|s| !s.is_empty())?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modifier(stat: StatType, value: i32) -> ItemStat {
+        ItemStat::Modifier(StatModifier { stat, value })
+    }
+
+    #[test]
+    fn parses_single_and_combined_stat_lines() {
+        assert_eq!(
+            parse_stat_line("+5 Stamina"),
+            [modifier(StatType::Stamina, 5)]
+        );
+        assert_eq!(
+            parse_stat_line("+6 Spell Power and +6 Intellect"),
+            [
+                modifier(StatType::SpellPower, 6),
+                modifier(StatType::Intellect, 6)
+            ]
+        );
+        assert_eq!(
+            parse_stat_line("-10 Spirit"),
+            [modifier(StatType::Spirit, -10)]
+        );
+        assert_eq!(
+            parse_stat_line("+4 Mana every 5 seconds"),
+            [modifier(StatType::ManaRegeneration, 4)]
+        );
+    }
+
+    #[test]
+    fn keeps_feral_attack_power_line_whole() {
+        let line = "+420 Attack Power in Cat, Bear, Dire Bear, and Moonkin forms only.";
+        assert_eq!(
+            parse_stat_line(line),
+            [modifier(StatType::FeralAttackPower, 420)]
+        );
+    }
+
+    #[test]
+    fn leaves_lines_with_unknown_parts_unparsed() {
+        assert_eq!(
+            parse_stat_line("+5 Stamina and +5 Awesomeness"),
+            [ItemStat::Unparsed(
+                "+5 Stamina and +5 Awesomeness".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn parses_flat_equip_bonuses() {
+        assert_eq!(
+            parse_equip_line("Equip: Improves hit rating by 10."),
+            Some(StatModifier {
+                stat: StatType::HitRating,
+                value: 10
+            })
+        );
+        assert_eq!(
+            parse_equip_line("Equip: Restores 8 mana per 5 sec."),
+            Some(StatModifier {
+                stat: StatType::ManaRegeneration,
+                value: 8
+            })
+        );
+        assert_eq!(
+            parse_equip_line(
+                "Equip: Increases attack power by 420 in Cat, Bear, Dire Bear, and Moonkin forms only."
+            ),
+            Some(StatModifier {
+                stat: StatType::FeralAttackPower,
+                value: 420
+            })
+        );
+        assert_eq!(
+            parse_equip_line("Use: Increases attack power by 100."),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_proc_equip_lines() {
+        for line in [
+            "Equip: Your melee attacks have a chance to increase your haste rating by 200 for 10 sec.",
+            "Equip: When struck in combat, increases your armor by 500 for 10 sec.",
+            "Equip: Each time you cast a spell, increases your spell power by 15 for 10 sec.",
+            "Equip: Increases your attack power by 300 for 15 sec.",
+        ] {
+            assert_eq!(parse_equip_line(line), None, "{line}");
+        }
+    }
+}
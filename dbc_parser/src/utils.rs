@@ -1,13 +1,29 @@
 use anyhow::Result;
-use std::{collections::HashSet, fs};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    filter::ItemStatus,
+    models::{InventoryType, Item, ItemClass, Rarity},
+};
 
 pub struct OriginalItemChecker {
-    existing_entries: HashSet<i32>, // Set of item IDs that exist in CSV
+    existing_entries: HashMap<i32, StockItem>, // Item IDs that exist in CSV
+}
+
+/// Values of a stock `item_template` row compared against built items.
+/// Columns missing from the CSV are `None` and not compared.
+#[derive(Debug, Clone, Default)]
+struct StockItem {
+    name: Option<String>,
+    class: Option<ItemClass>,
+    inventory_type: Option<InventoryType>,
+    rarity: Option<Rarity>,
+    required_level: Option<u32>,
 }
 
 impl OriginalItemChecker {
-    pub fn new() -> Result<Self> {
-        let existing_entries = load_csv_entries()?;
+    pub fn new(path: &Path) -> Result<Self> {
+        let existing_entries = load_csv_entries(path)?;
         println!(
             "Loaded {} existing entries from item_template.csv",
             existing_entries.len()
@@ -16,7 +32,30 @@ impl OriginalItemChecker {
     }
 
     pub fn is_item_new(&self, item_id: i32) -> bool {
-        !self.existing_entries.contains(&item_id)
+        !self.existing_entries.contains_key(&item_id)
+    }
+
+    /// Compare `item` against the stock row with the same id.
+    pub fn status(&self, item: &Item) -> ItemStatus {
+        let Some(stock) = self.existing_entries.get(&item.id) else {
+            return ItemStatus::New;
+        };
+        fn same<T: PartialEq>(stock: &Option<T>, value: &T) -> bool {
+            stock.as_ref().is_none_or(|stock| stock == value)
+        }
+
+        // Items no source named keep the stock name
+        let same_name = !item.has_name() || same(&stock.name, &item.name);
+        if same_name
+            && same(&stock.class, &item.class)
+            && same(&stock.inventory_type, &item.inventory_type)
+            && same(&stock.rarity, &item.rarity)
+            && same(&stock.required_level, &item.required_level)
+        {
+            ItemStatus::Stock
+        } else {
+            ItemStatus::Modified
+        }
     }
 
     #[allow(dead_code)]
@@ -28,18 +67,38 @@ impl OriginalItemChecker {
     }
 }
 
-fn load_csv_entries() -> Result<HashSet<i32>> {
-    let content = fs::read_to_string("data/wotlk_item_template.csv")?;
-    let mut existing_entries = HashSet::new();
+fn load_csv_entries(path: &Path) -> Result<HashMap<i32, StockItem>> {
+    let content = fs::read_to_string(path)?;
+    let mut existing_entries = HashMap::new();
 
     let mut rdr = csv::Reader::from_reader(content.as_bytes());
+    let headers = rdr.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let name = column("name");
+    let class = column("class");
+    let inventory_type = column("InventoryType");
+    let quality = column("Quality");
+    let required_level = column("RequiredLevel");
+
     for result in rdr.records() {
         let record = result?;
-        if let Some(entry_str) = record.get(0) {
+        let number = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .and_then(|value| value.trim().parse::<i32>().ok())
+        };
+        if let Some(entry_id) = number(Some(0)) {
             // "entry" is the first column
-            if let Ok(entry_id) = entry_str.parse::<i32>() {
-                existing_entries.insert(entry_id);
-            }
+            let stock = StockItem {
+                name: name
+                    .and_then(|column| record.get(column))
+                    .map(str::to_string),
+                class: number(class).map(ItemClass::from),
+                inventory_type: number(inventory_type).map(InventoryType::from),
+                rarity: number(quality).map(Rarity::from),
+                required_level: number(required_level).map(|level| level as u32),
+            };
+            existing_entries.insert(entry_id, stock);
         }
     }
 
@@ -0,0 +1,431 @@
+use anyhow::{Context, Result, bail};
+use std::{fs, path::Path};
+
+/// Signature of client cache files, `WDBI` stored little endian.
+const ITEM_CACHE_MAGIC: &[u8; 4] = b"BDIW";
+
+const MAX_ITEM_DAMAGES: usize = 2;
+const MAX_ITEM_SPELLS: usize = 5;
+const MAX_ITEM_SOCKETS: usize = 3;
+
+/// Records of a 3.3.5 `Cache/WDB/<locale>/itemcache.wdb` file.
+#[derive(Debug, Clone, Default)]
+pub struct WdbItemCache {
+    pub build: u32,
+    pub locale: String,
+    pub items: Vec<WdbItem>,
+}
+
+/// One item as sent by the server in `SMSG_ITEM_QUERY_SINGLE_RESPONSE`.
+#[derive(Debug, Clone, Default)]
+pub struct WdbItem {
+    pub id: i32,
+    pub class_id: u32,
+    pub subclass_id: u32,
+    pub name: String,
+    pub display_id: u32,
+    pub quality: u32,
+    pub flags: u32,
+    pub inventory_type: u32,
+    pub allowable_class: i32,
+    pub allowable_race: i32,
+    pub item_level: u32,
+    pub required_level: u32,
+    pub required_skill: u32,
+    pub required_skill_rank: u32,
+    pub required_spell: u32,
+    pub required_reputation_faction: u32,
+    pub required_reputation_rank: u32,
+    pub stats: Vec<WdbStat>,
+    pub damages: Vec<WdbDamage>,
+    pub armor: u32,
+    /// Holy, fire, nature, frost, shadow and arcane resistance.
+    pub resistances: [u32; 6],
+    /// Swing speed in milliseconds.
+    pub delay: u32,
+    pub spells: Vec<WdbSpell>,
+    pub bonding: u32,
+    pub description: String,
+    pub random_property: i32,
+    pub random_suffix: i32,
+    pub block: u32,
+    pub item_set: u32,
+    pub max_durability: u32,
+    pub sockets: Vec<WdbSocket>,
+    pub socket_bonus: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WdbStat {
+    pub stat_type: u32,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WdbDamage {
+    pub min: f32,
+    pub max: f32,
+    pub school: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WdbSpell {
+    pub id: i32,
+    pub trigger: u32,
+    pub charges: i32,
+    pub cooldown: i32,
+    pub category: u32,
+    pub category_cooldown: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WdbSocket {
+    pub color: u32,
+    pub content: u32,
+}
+
+impl WdbItemCache {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(4)? != ITEM_CACHE_MAGIC {
+            bail!("not an itemcache.wdb file");
+        }
+        let build = reader.u32()?;
+        let locale: String = reader.bytes(4)?.iter().rev().map(|b| *b as char).collect();
+        let _record_size = reader.u32()?;
+        let _version = reader.u32()?;
+
+        let mut items = Vec::new();
+        while reader.remaining() >= 8 {
+            let id = reader.i32()?;
+            let length = reader.u32()? as usize;
+            if id == 0 && length == 0 {
+                break;
+            }
+            let record = reader.bytes(length)?;
+            items.push(WdbItem::parse(id, record).with_context(|| format!("item {id}"))?);
+        }
+
+        Ok(Self {
+            build,
+            locale,
+            items,
+        })
+    }
+}
+
+impl WdbItem {
+    fn parse(id: i32, data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        let mut item = WdbItem {
+            id,
+            class_id: r.u32()?,
+            subclass_id: r.u32()?,
+            ..Default::default()
+        };
+        let _sound_override_subclass = r.i32()?;
+        item.name = r.cstring()?;
+        // Three unused name slots
+        for _ in 0..3 {
+            r.cstring()?;
+        }
+        item.display_id = r.u32()?;
+        item.quality = r.u32()?;
+        item.flags = r.u32()?;
+        let _flags2 = r.u32()?;
+        let _buy_price = r.u32()?;
+        let _sell_price = r.u32()?;
+        item.inventory_type = r.u32()?;
+        item.allowable_class = r.i32()?;
+        item.allowable_race = r.i32()?;
+        item.item_level = r.u32()?;
+        item.required_level = r.u32()?;
+        item.required_skill = r.u32()?;
+        item.required_skill_rank = r.u32()?;
+        item.required_spell = r.u32()?;
+        let _required_honor_rank = r.u32()?;
+        let _required_city_rank = r.u32()?;
+        item.required_reputation_faction = r.u32()?;
+        item.required_reputation_rank = r.u32()?;
+        let _max_count = r.i32()?;
+        let _stackable = r.i32()?;
+        let _container_slots = r.u32()?;
+
+        let stats_count = r.u32()?;
+        for _ in 0..stats_count {
+            let stat = WdbStat {
+                stat_type: r.u32()?,
+                value: r.i32()?,
+            };
+            if stat.value != 0 {
+                item.stats.push(stat);
+            }
+        }
+        let _scaling_stat_distribution = r.u32()?;
+        let _scaling_stat_value = r.u32()?;
+
+        for _ in 0..MAX_ITEM_DAMAGES {
+            let damage = WdbDamage {
+                min: r.f32()?,
+                max: r.f32()?,
+                school: r.u32()?,
+            };
+            if damage.max > 0.0 {
+                item.damages.push(damage);
+            }
+        }
+
+        item.armor = r.u32()?;
+        for resistance in item.resistances.iter_mut() {
+            *resistance = r.u32()?;
+        }
+        item.delay = r.u32()?;
+        let _ammo_type = r.u32()?;
+        let _ranged_mod_range = r.f32()?;
+
+        for _ in 0..MAX_ITEM_SPELLS {
+            let spell = WdbSpell {
+                id: r.i32()?,
+                trigger: r.u32()?,
+                charges: r.i32()?,
+                cooldown: r.i32()?,
+                category: r.u32()?,
+                category_cooldown: r.i32()?,
+            };
+            if spell.id > 0 {
+                item.spells.push(spell);
+            }
+        }
+
+        item.bonding = r.u32()?;
+        item.description = r.cstring()?;
+        let _page_text = r.u32()?;
+        let _language = r.u32()?;
+        let _page_material = r.u32()?;
+        let _start_quest = r.u32()?;
+        let _lock_id = r.u32()?;
+        let _material = r.i32()?;
+        let _sheath = r.u32()?;
+        item.random_property = r.i32()?;
+        item.random_suffix = r.i32()?;
+        item.block = r.u32()?;
+        item.item_set = r.u32()?;
+        item.max_durability = r.u32()?;
+        let _area = r.u32()?;
+        let _map = r.u32()?;
+        let _bag_family = r.u32()?;
+        let _totem_category = r.u32()?;
+
+        for _ in 0..MAX_ITEM_SOCKETS {
+            let socket = WdbSocket {
+                color: r.u32()?,
+                content: r.u32()?,
+            };
+            if socket.color != 0 {
+                item.sockets.push(socket);
+            }
+        }
+        item.socket_bonus = r.u32()?;
+
+        // Gem properties, disenchant skill, armor modifier, duration, limit
+        // category and holiday follow but aren't needed for items.
+        Ok(item)
+    }
+}
+
+/// Little endian cursor over a byte slice.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.remaining() < len {
+            bail!("unexpected end of data at offset {}", self.pos);
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn cstring(&mut self) -> Result<String> {
+        let Some(len) = self.data[self.pos..].iter().position(|b| *b == 0) else {
+            bail!("unterminated string at offset {}", self.pos);
+        };
+        let bytes = self.bytes(len)?;
+        self.pos += 1;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little endian writer for building cache files.
+    #[derive(Default)]
+    struct Writer(Vec<u8>);
+
+    impl Writer {
+        fn u32(&mut self, value: u32) -> &mut Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn i32(&mut self, value: i32) -> &mut Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn f32(&mut self, value: f32) -> &mut Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn cstring(&mut self, value: &str) -> &mut Self {
+            self.0.extend(value.as_bytes());
+            self.0.push(0);
+            self
+        }
+
+        fn zeros(&mut self, count: usize) -> &mut Self {
+            self.0.extend(vec![0; count * 4]);
+            self
+        }
+    }
+
+    /// A sword with one stat, one damage range and one spell.
+    fn sword_record() -> Vec<u8> {
+        let mut w = Writer::default();
+        w.u32(2).u32(7).i32(-1);
+        w.cstring("Arcanite Reaper")
+            .cstring("")
+            .cstring("")
+            .cstring("");
+        // Display id, quality, flags, flags2, buy and sell price
+        w.u32(1234).u32(4).u32(0).u32(0).u32(0).u32(0);
+        // Inventory type, allowable class and race, item and required level
+        w.u32(17).i32(-1).i32(-1).u32(63).u32(58);
+        // Required skill, rank, spell, honor and city rank, reputation
+        // faction and rank, max count, stackable, container slots
+        w.zeros(10);
+        w.u32(1).u32(7).i32(13);
+        w.zeros(2);
+        w.f32(153.0).f32(256.0).u32(0).zeros(3);
+        // Armor, resistances, delay, ammo type, ranged range
+        w.u32(0).zeros(6).u32(3800).u32(0).f32(0.0);
+        w.i32(18384)
+            .u32(1)
+            .i32(0)
+            .i32(-1)
+            .u32(0)
+            .i32(-1)
+            .zeros(4 * 6);
+        w.u32(2).cstring("");
+        // Page text, language, page material, start quest, lock, material,
+        // sheath
+        w.zeros(7);
+        // Random property and suffix, block, item set, durability
+        w.i32(0).i32(0).u32(0).u32(0).u32(120);
+        // Area, map, bag family, totem category
+        w.zeros(4);
+        // A red and a blue socket and their bonus
+        w.u32(2).u32(0).u32(8).u32(0).zeros(2).u32(3312);
+        w.0
+    }
+
+    fn cache(records: &[(i32, &[u8])]) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.0.extend(ITEM_CACHE_MAGIC);
+        w.u32(12340);
+        w.0.extend(b"SUne");
+        w.u32(0).u32(0);
+        for (id, record) in records {
+            w.i32(*id).u32(record.len() as u32);
+            w.0.extend(*record);
+        }
+        w.zeros(2);
+        w.0
+    }
+
+    #[test]
+    fn parses_item_records() {
+        let cache = WdbItemCache::parse(&cache(&[(12784, &sword_record())])).unwrap();
+        assert_eq!((cache.build, cache.locale.as_str()), (12340, "enUS"));
+
+        let [item] = &cache.items[..] else {
+            panic!("expected one item, got {}", cache.items.len());
+        };
+        assert_eq!(item.id, 12784);
+        assert_eq!(item.name, "Arcanite Reaper");
+        assert_eq!((item.class_id, item.subclass_id), (2, 7));
+        assert_eq!((item.quality, item.inventory_type), (4, 17));
+        assert_eq!((item.item_level, item.required_level), (63, 58));
+        assert_eq!(
+            (
+                item.stats.len(),
+                item.stats[0].stat_type,
+                item.stats[0].value
+            ),
+            (1, 7, 13)
+        );
+        assert_eq!(item.damages.len(), 1);
+        assert_eq!((item.damages[0].min, item.damages[0].max), (153.0, 256.0));
+        assert_eq!(item.delay, 3800);
+        assert_eq!(item.spells.len(), 1);
+        assert_eq!((item.spells[0].id, item.spells[0].trigger), (18384, 1));
+        assert_eq!((item.bonding, item.max_durability), (2, 120));
+        assert_eq!(
+            item.sockets
+                .iter()
+                .map(|socket| socket.color)
+                .collect::<Vec<_>>(),
+            [2, 8]
+        );
+        assert_eq!(item.socket_bonus, 3312);
+    }
+
+    #[test]
+    fn rejects_truncated_records() {
+        let record = sword_record();
+        let err = WdbItemCache::parse(&cache(&[(12784, &record[..record.len() / 2])])).unwrap_err();
+        assert_eq!(err.to_string(), "item 12784");
+        assert!(format!("{err:#}").contains("unexpected end of data"));
+
+        let mut data = cache(&[(12784, &record)]);
+        data.truncate(data.len() - 20);
+        let err = WdbItemCache::parse(&data).unwrap_err();
+        assert!(err.to_string().contains("unexpected end of data"), "{err}");
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(WdbItemCache::parse(b"WDBC").is_err());
+    }
+}
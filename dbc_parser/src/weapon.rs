@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+/// Largest difference between the stated and computed DPS that is still
+/// explained by the tooltip rounding to one decimal.
+const DPS_TOLERANCE: f32 = 0.051;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DamageSchool {
+    Physical,
+    Holy,
+    Fire,
+    Nature,
+    Frost,
+    Shadow,
+    Arcane,
+}
+
+impl DamageSchool {
+    /// Map a client school id (`0` physical .. `6` arcane).
+    pub fn from_id(school: u32) -> Self {
+        match school {
+            1 => DamageSchool::Holy,
+            2 => DamageSchool::Fire,
+            3 => DamageSchool::Nature,
+            4 => DamageSchool::Frost,
+            5 => DamageSchool::Shadow,
+            6 => DamageSchool::Arcane,
+            _ => DamageSchool::Physical,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let school = match name.to_lowercase().as_str() {
+            "" | "physical" => DamageSchool::Physical,
+            "holy" => DamageSchool::Holy,
+            "fire" => DamageSchool::Fire,
+            "nature" => DamageSchool::Nature,
+            "frost" => DamageSchool::Frost,
+            "shadow" => DamageSchool::Shadow,
+            "arcane" => DamageSchool::Arcane,
+            _ => return None,
+        };
+        Some(school)
+    }
+
+    /// Name used in damage lines such as `+5 - 10 Fire Damage`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageSchool::Physical => "Physical",
+            DamageSchool::Holy => "Holy",
+            DamageSchool::Fire => "Fire",
+            DamageSchool::Nature => "Nature",
+            DamageSchool::Frost => "Frost",
+            DamageSchool::Shadow => "Shadow",
+            DamageSchool::Arcane => "Arcane",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DamageRange {
+    pub school: DamageSchool,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Numeric weapon damage parsed from the tooltip lines of an item.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WeaponStats {
+    /// Base damage first, followed by any added elemental damage.
+    pub damage: Vec<DamageRange>,
+    /// Swing speed in milliseconds.
+    pub speed_ms: u32,
+    /// `(min + max) / 2 / speed` summed over all damage ranges.
+    pub dps: f32,
+    /// DPS as written on the tooltip, if known.
+    pub stated_dps: Option<f32>,
+    /// Whether `stated_dps` disagrees with `dps` beyond rounding.
+    pub dps_mismatch: bool,
+}
+
+/// Parse the `damage`, `added_damage`, `speed` and `dps` display strings of
+/// an item. Returns `None` for items without damage or speed.
+pub fn parse_weapon(
+    damage: &str,
+    added_damage: &str,
+    speed: &str,
+    dps: &str,
+) -> Option<WeaponStats> {
+    let damage: Vec<DamageRange> = [damage, added_damage]
+        .into_iter()
+        .filter_map(parse_damage)
+        .collect();
+    if damage.is_empty() {
+        return None;
+    }
+    let speed_ms = parse_speed(speed)?;
+
+    let computed = damage
+        .iter()
+        .map(|range| (range.min + range.max) / 2.0)
+        .sum::<f32>()
+        / (speed_ms as f32 / 1000.0);
+    let stated_dps = parse_dps(dps);
+    let dps_mismatch = stated_dps.is_some_and(|stated| (stated - computed).abs() > DPS_TOLERANCE);
+
+    Some(WeaponStats {
+        damage,
+        speed_ms,
+        dps: computed,
+        stated_dps,
+        dps_mismatch,
+    })
+}
+
+/// Parse `1 - 3 Damage`, `+5 - 10 Fire Damage` or `12 Damage`.
+pub fn parse_damage(line: &str) -> Option<DamageRange> {
+    let line = line.trim().strip_prefix('+').unwrap_or(line.trim());
+    let line = line.strip_suffix("Damage")?.trim();
+    let (range, school) = match line.rsplit_once(' ') {
+        Some((range, school)) if school.chars().all(|c| c.is_alphabetic()) => (range, school),
+        _ => (line, ""),
+    };
+    let school = DamageSchool::from_name(school)?;
+    let (min, max) = match range.split_once('-') {
+        Some((min, max)) => (min.trim().parse().ok()?, max.trim().parse().ok()?),
+        None => {
+            let value = range.trim().parse().ok()?;
+            (value, value)
+        }
+    };
+    Some(DamageRange { school, min, max })
+}
+
+/// Parse `Speed 1.90` into milliseconds.
+pub fn parse_speed(line: &str) -> Option<u32> {
+    let seconds: f32 = line.trim().strip_prefix("Speed")?.trim().parse().ok()?;
+    (seconds > 0.0).then(|| (seconds * 1000.0).round() as u32)
+}
+
+/// Parse `(1.1 damage per second)`.
+pub fn parse_dps(line: &str) -> Option<f32> {
+    let line = line.trim().trim_start_matches('(').trim_end_matches(')');
+    line.strip_suffix("damage per second")?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_damage_lines() {
+        let range = |school, min, max| Some(DamageRange { school, min, max });
+        assert_eq!(
+            parse_damage("44 - 115 Damage"),
+            range(DamageSchool::Physical, 44.0, 115.0)
+        );
+        assert_eq!(
+            parse_damage("+16 - 30 Nature Damage"),
+            range(DamageSchool::Nature, 16.0, 30.0)
+        );
+        assert_eq!(
+            parse_damage("12 Damage"),
+            range(DamageSchool::Physical, 12.0, 12.0)
+        );
+        assert_eq!(parse_damage("5 - 10 Chaos Damage"), None);
+        assert_eq!(parse_damage("+10 Stamina"), None);
+
+        assert_eq!(parse_speed("Speed 1.90"), Some(1900));
+        assert_eq!(parse_speed("Speed 0"), None);
+        assert_eq!(parse_dps("(53.9 damage per second)"), Some(53.9));
+        assert_eq!(parse_dps("53.9"), None);
+    }
+
+    #[test]
+    fn sums_dps_over_all_damage() {
+        let weapon = parse_weapon(
+            "44 - 115 Damage",
+            "+16 - 30 Nature Damage",
+            "Speed 1.90",
+            "(53.9 damage per second)",
+        )
+        .unwrap();
+        assert_eq!(weapon.damage.len(), 2);
+        assert_eq!(weapon.speed_ms, 1900);
+        assert!((weapon.dps - 53.947).abs() < 0.001, "{}", weapon.dps);
+        assert_eq!(weapon.stated_dps, Some(53.9));
+        assert!(!weapon.dps_mismatch);
+
+        assert_eq!(parse_weapon("", "", "Speed 1.90", ""), None);
+        assert_eq!(parse_weapon("44 - 115 Damage", "", "", ""), None);
+    }
+
+    #[test]
+    fn flags_stated_dps_beyond_rounding() {
+        let weapon = |dps| parse_weapon("10 - 20 Damage", "", "Speed 2.00", dps).unwrap();
+        assert!(!weapon("(7.5 damage per second)").dps_mismatch);
+        assert!(weapon("(7.6 damage per second)").dps_mismatch);
+        assert!(!weapon("").dps_mismatch);
+    }
+}
@@ -3,6 +3,43 @@ export interface ItemSpell {
   1: string;
 }
 
+export interface StatModifier {
+  stat: string;
+  value: number;
+}
+
+export type ItemStat = { Modifier: StatModifier } | { Unparsed: string };
+
+export interface DamageRange {
+  school: string;
+  min: number;
+  max: number;
+}
+
+export interface WeaponStats {
+  damage: DamageRange[];
+  speed_ms: number;
+  dps: number;
+  stated_dps: number | null;
+  dps_mismatch: boolean;
+}
+
+export type Requirement =
+  | { Level: number }
+  | { Classes: { mask: number; names: string[] } }
+  | { Races: { mask: number; names: string[] } }
+  | { Skill: { id: number | null; name: string; rank: number } }
+  | {
+      Reputation: {
+        faction_id: number | null;
+        faction: string;
+        standing: string;
+      };
+    }
+  | { Spell: { id: number | null; name: string } }
+  | { Achievement: { name: string } }
+  | { Unparsed: string };
+
 export interface ItemSet {
   name: string;
   id: number;
@@ -19,14 +56,17 @@ export interface Item {
   set: ItemSet | null;
   required_level: number;
   stats: string[];
+  stat_modifiers?: ItemStat[];
   spells: string[];
   requires: string[];
+  requirements?: Requirement[];
   rarity: string;
   damage: string;
   added_damage: string;
   armor: string;
   speed: string;
   dps: string;
+  weapon?: WeaponStats | null;
   bonding: string;
   hands: string;
 }